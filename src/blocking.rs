@@ -0,0 +1,377 @@
+use {
+    super::{Error, Result, TransactionBuilder},
+    crate::{CalcFeeResult, ConfirmationConfig, SendOutcome, TransactionRpcProvider},
+    base64::prelude::*,
+    futures::executor::block_on,
+    solana_account::Account,
+    solana_hash::Hash,
+    solana_message::{AddressLookupTableAccount, VersionedMessage},
+    solana_pubkey::Pubkey,
+    solana_rpc_client::rpc_client::RpcClient,
+    solana_rpc_client_api::{
+        config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig},
+        request::MAX_MULTIPLE_ACCOUNTS,
+        response::{RpcPrioritizationFee, RpcSimulateTransactionResult},
+    },
+    solana_signature::Signature,
+    solana_signer::signers::Signers,
+    solana_transaction::versioned::VersionedTransaction,
+    std::time::Instant,
+};
+
+/// Synchronous counterpart of [`TransactionRpcProvider`], implemented for the
+/// blocking [`RpcClient`].
+///
+/// Wrap it in a [`BlockingRpcWrapper`] to use it with
+/// [`TransactionBuilder::send_blocking`] and the other `_blocking` methods,
+/// which then run without a tokio runtime.
+pub trait BlockingRpcProvider: Send + Sync {
+    fn get_recent_prioritization_fees(
+        &self,
+        accounts: &[Pubkey],
+    ) -> Result<Vec<RpcPrioritizationFee>>;
+    fn get_lookup_table_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>>;
+    fn get_latest_blockhash(&self) -> Result<Hash>;
+    fn simulate_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        config: RpcSimulateTransactionConfig,
+    ) -> Result<RpcSimulateTransactionResult>;
+    fn send_and_confirm_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        config: Option<RpcSendTransactionConfig>,
+    ) -> Result<Signature>;
+    /// Balance of `pubkey` in lamports
+    fn get_balance(&self, pubkey: &Pubkey) -> Result<u64>;
+    /// Account stored at `pubkey`, `None` if it does not exist
+    fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>>;
+    /// Accounts stored at `pubkeys`, in order, `None` for those that do not
+    /// exist
+    fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>>;
+}
+
+impl BlockingRpcProvider for RpcClient {
+    fn get_recent_prioritization_fees(
+        &self,
+        accounts: &[Pubkey],
+    ) -> Result<Vec<RpcPrioritizationFee>> {
+        RpcClient::get_recent_prioritization_fees(self, accounts)
+            .map_err(|e| Error::SolanaRpcError(format!("failed to get prioritization fees: {e}")))
+    }
+
+    fn get_lookup_table_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        let accounts = BlockingRpcProvider::get_multiple_accounts(self, pubkeys)?;
+        crate::lookup::process_lookup_tables(pubkeys, accounts)
+    }
+
+    fn get_latest_blockhash(&self) -> Result<Hash> {
+        RpcClient::get_latest_blockhash(self)
+            .map_err(|e| Error::SolanaRpcError(format!("failed to get latest blockhash: {e}")))
+    }
+
+    fn simulate_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        config: RpcSimulateTransactionConfig,
+    ) -> Result<RpcSimulateTransactionResult> {
+        let result = self
+            .simulate_transaction_with_config(tx, config)
+            .map_err(|e| Error::SolanaRpcError(format!("failed to simulate transaction: {e}")))?;
+        if let Some(e) = result.value.err {
+            return Err(Error::SolanaSimulateError {
+                error: e.into(),
+                logs: result.value.logs.unwrap_or_default(),
+                transaction_base64: BASE64_STANDARD.encode(bincode::serialize(tx)?),
+            });
+        }
+        Ok(result.value)
+    }
+
+    /// Sends `tx` and polls its status with the
+    /// [`ConfirmationConfig::default`] interval and timeout, sleeping the
+    /// calling thread in between.
+    fn send_and_confirm_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        config: Option<RpcSendTransactionConfig>,
+    ) -> Result<Signature> {
+        // same preflight commitment as `RpcClient::send_transaction`
+        let config = config.unwrap_or_else(|| RpcSendTransactionConfig {
+            preflight_commitment: Some(self.commitment().commitment),
+            ..Default::default()
+        });
+        let signature = self
+            .send_transaction_with_config(tx, config)
+            .map_err(|e| Error::SolanaRpcError(format!("failed to send transaction: {e}")))?;
+        let confirmation = ConfirmationConfig::default();
+        let started = Instant::now();
+        loop {
+            let status = self
+                .get_signature_status_with_commitment(&signature, self.commitment())
+                .map_err(|e| {
+                    Error::SolanaRpcError(format!(
+                        "failed to confirm transaction: {signature} Error:{e}"
+                    ))
+                })?;
+            match status {
+                Some(Ok(())) => return Ok(signature),
+                Some(Err(error)) => {
+                    return Err(Error::TransactionFailed {
+                        signature,
+                        error,
+                        logs: vec![],
+                    });
+                }
+                None => {}
+            }
+            if started.elapsed() + confirmation.poll_interval > confirmation.timeout {
+                return Err(Error::Unconfirmed(signature));
+            }
+            std::thread::sleep(confirmation.poll_interval);
+        }
+    }
+
+    fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        RpcClient::get_balance(self, pubkey)
+            .map_err(|e| Error::SolanaRpcError(format!("failed to get balance: {e}")))
+    }
+
+    fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>> {
+        self.get_account_with_commitment(pubkey, self.commitment())
+            .map(|response| response.value)
+            .map_err(|e| Error::SolanaRpcError(format!("failed to get account: {e}")))
+    }
+
+    fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>> {
+        let mut accounts = Vec::with_capacity(pubkeys.len());
+        for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let response = self
+                .get_multiple_accounts_with_commitment(chunk, self.commitment())
+                .map_err(|e| Error::SolanaRpcError(format!("failed to get accounts: {e}")))?;
+            accounts.extend(response.value);
+        }
+        Ok(accounts)
+    }
+}
+
+/// [`TransactionRpcProvider`] over a [`BlockingRpcProvider`].
+///
+/// Each call blocks the thread polling it, so use it with the `_blocking`
+/// methods of [`TransactionBuilder`], never on an async runtime.
+#[derive(Clone)]
+pub struct BlockingRpcWrapper<B: BlockingRpcProvider>(pub B);
+
+impl<B: BlockingRpcProvider> From<B> for BlockingRpcWrapper<B> {
+    fn from(provider: B) -> Self {
+        Self(provider)
+    }
+}
+
+#[async_trait::async_trait]
+impl<B: BlockingRpcProvider> TransactionRpcProvider for BlockingRpcWrapper<B> {
+    async fn get_recent_prioritization_fees(
+        &self,
+        accounts: &[Pubkey],
+    ) -> Result<Vec<RpcPrioritizationFee>> {
+        self.0.get_recent_prioritization_fees(accounts)
+    }
+
+    async fn get_lookup_table_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.0.get_lookup_table_accounts(pubkeys)
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash> {
+        self.0.get_latest_blockhash()
+    }
+
+    async fn simulate_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        config: RpcSimulateTransactionConfig,
+    ) -> Result<RpcSimulateTransactionResult> {
+        self.0.simulate_transaction(tx, config)
+    }
+
+    async fn send_and_confirm_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        config: Option<RpcSendTransactionConfig>,
+    ) -> Result<Signature> {
+        self.0.send_and_confirm_transaction(tx, config)
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        self.0.get_balance(pubkey)
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>> {
+        self.0.get_account(pubkey)
+    }
+
+    async fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>> {
+        self.0.get_multiple_accounts(pubkeys)
+    }
+}
+
+impl TransactionBuilder {
+    /// Blocking version of [`TransactionBuilder::send`], driven by
+    /// [`futures::executor::block_on`] on the calling thread.
+    ///
+    /// # Re-entrancy
+    ///
+    /// This parks the calling thread until the transaction is confirmed.
+    /// Never call it from async code: on a single threaded runtime it
+    /// deadlocks, and on a multi threaded runtime it stalls a worker.
+    ///
+    /// `block_on` only polls the future, it does not provide a reactor.
+    /// Providers doing their own I/O through tokio, such as the nonblocking
+    /// [`RpcClient`](solana_rpc_client::nonblocking::rpc_client::RpcClient)
+    /// behind [`crate::NativeRpcWrapper`], still need a tokio runtime entered
+    /// on this thread (`Handle::enter`). Use a
+    /// [`BlockingRpcWrapper`] over the blocking [`RpcClient`] instead
+    /// where there is no runtime.
+    pub fn send_blocking<S: Signers + ?Sized, T: TransactionRpcProvider>(
+        &self,
+        rpc: &T,
+        payer: &Pubkey,
+        signers: &S,
+    ) -> Result<SendOutcome> {
+        block_on(self.send(rpc, payer, signers))
+    }
+
+    /// Blocking version of [`TransactionBuilder::create_message`], see
+    /// [`TransactionBuilder::send_blocking`] for the runtime requirements.
+    pub fn create_message_blocking<T: TransactionRpcProvider>(
+        &self,
+        payer: &Pubkey,
+        rpc: &T,
+    ) -> Result<VersionedMessage> {
+        block_on(self.create_message(payer, rpc))
+    }
+
+    /// Blocking version of [`TransactionBuilder::simulate`], see
+    /// [`TransactionBuilder::send_blocking`] for the runtime requirements.
+    pub fn simulate_blocking<S: Signers + ?Sized, T: TransactionRpcProvider>(
+        &self,
+        payer: &Pubkey,
+        signers: &S,
+        rpc: &T,
+        config: impl Into<RpcSimulateTransactionConfig>,
+    ) -> Result<RpcSimulateTransactionResult> {
+        block_on(self.simulate(payer, signers, rpc, config))
+    }
+
+    /// Blocking version of [`TransactionBuilder::calc_fee`], see
+    /// [`TransactionBuilder::send_blocking`] for the runtime requirements.
+    pub fn calc_fee_blocking<T: TransactionRpcProvider>(
+        &self,
+        payer: &Pubkey,
+        rpc: &T,
+        accounts: &[Pubkey],
+        max_prioritization_fee: u64,
+        percentile: Option<u8>,
+    ) -> Result<CalcFeeResult> {
+        block_on(self.calc_fee(payer, rpc, accounts, max_prioritization_fee, percentile))
+    }
+
+    /// Blocking version of [`TransactionBuilder::with_priority_fees`], see
+    /// [`TransactionBuilder::send_blocking`] for the runtime requirements.
+    pub fn with_priority_fees_blocking<T: TransactionRpcProvider>(
+        self,
+        payer: &Pubkey,
+        rpc: &T,
+        accounts: &[Pubkey],
+        max_prioritization_fee: u64,
+        percentile: Option<u8>,
+    ) -> Result<Self> {
+        block_on(self.with_priority_fees(payer, rpc, accounts, max_prioritization_fee, percentile))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::rpc::mock::MockRpc,
+        solana_keypair::Keypair,
+        solana_rpc_client_api::response::TransactionError,
+        solana_signer::Signer,
+    };
+
+    #[test]
+    fn test_send_blocking() -> anyhow::Result<()> {
+        let payer = Keypair::new();
+        let rpc = MockRpc::default();
+        let signature = TransactionBuilder::default()
+            .with_memo("blocking", &[&payer.pubkey()])
+            .send_blocking(&rpc, &payer.pubkey(), &[&payer])?;
+
+        let sent = rpc.sent();
+        assert_eq!(1, sent.len());
+        assert_eq!(sent[0].0.signatures[0], signature.signature());
+        assert_eq!(1, rpc.simulated().len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_blocking_fees() -> anyhow::Result<()> {
+        let payer = Keypair::new();
+        let rpc = MockRpc {
+            prioritization_fees: vec![1_000, 2_000],
+            units_consumed: Some(10_000),
+            ..Default::default()
+        };
+        let tx = TransactionBuilder::default().with_memo("blocking", &[&payer.pubkey()]);
+        let message = tx.create_message_blocking(&payer.pubkey(), &rpc)?;
+        assert_eq!(&rpc.blockhash, message.recent_blockhash());
+
+        let fee = tx.calc_fee_blocking(&payer.pubkey(), &rpc, &[], 10_000, None)?;
+        let tx = tx.with_priority_fees_blocking(&payer.pubkey(), &rpc, &[], 10_000, None)?;
+        assert_eq!(
+            (Some(fee.units), Some(fee.priority_fee)),
+            tx.current_compute_budget()
+        );
+        tx.simulate_blocking(
+            &payer.pubkey(),
+            &[&payer],
+            &rpc,
+            RpcSimulateTransactionConfig::default(),
+        )?;
+        assert_eq!(3, rpc.simulated().len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_blocking_rpc_provider() -> anyhow::Result<()> {
+        let payer = Keypair::new();
+        let rpc = BlockingRpcWrapper(RpcClient::new_mock("succeeds".to_string()));
+        let tx = TransactionBuilder::default().with_memo("blocking", &[&payer.pubkey()]);
+
+        // the mock reports a single fee of 10_000
+        let fee = tx.calc_fee_blocking(&payer.pubkey(), &rpc, &[], u64::MAX, None)?;
+        assert_eq!(10_000, fee.priority_fee);
+        let outcome = tx.send_blocking(&rpc, &payer.pubkey(), &[&payer])?;
+        assert!(matches!(outcome, SendOutcome::Confirmed(_)));
+
+        let rpc = BlockingRpcWrapper(RpcClient::new_mock("instruction_error".to_string()));
+        let result = tx.send_blocking(&rpc, &payer.pubkey(), &[&payer]);
+        assert!(matches!(
+            result,
+            Err(Error::TransactionFailed {
+                error: TransactionError::InstructionError(..),
+                ..
+            })
+        ));
+        Ok(())
+    }
+}
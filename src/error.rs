@@ -1,4 +1,4 @@
-use thiserror::Error;
+use {solana_rpc_client_api::response::TransactionError, thiserror::Error};
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -11,15 +11,37 @@ pub enum Error {
     #[error("Failed simulation: {0}")]
     SolanaSimulateFailure(String),
 
+    #[error("Failed simulation: {error}\nbase64: {transaction_base64}\n{}", .logs.join("\n"))]
+    SolanaSimulateError {
+        error: TransactionError,
+        logs: Vec<String>,
+        transaction_base64: String,
+    },
+
     #[error("Failed RPC call: {0}")]
     SolanaRpcError(String),
 
+    #[error("Transaction {signature} failed: {error}\n{}", .logs.join("\n"))]
+    TransactionFailed {
+        signature: solana_signature::Signature,
+        error: TransactionError,
+        logs: Vec<String>,
+    },
+
+    /// Broadcast, but not confirmed before the confirmation timeout. The
+    /// transaction may still land.
+    #[error("Transaction is not confirmed: {0}")]
+    Unconfirmed(solana_signature::Signature),
+
     #[error(transparent)]
     BorshError(#[from] std::io::Error),
 
     #[error(transparent)]
     ParseAccountError(#[from] solana_account_decoder::parse_account_data::ParseAccountError),
 
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+
     #[error(transparent)]
     ParsePubkeyError(#[from] solana_pubkey::ParsePubkeyError),
 
@@ -41,12 +63,27 @@ pub enum Error {
     )]
     PriorityFeeTooHigh(u64, u64),
 
+    #[error("Insufficient payer balance. Balance: {0} lamports, required: {1} lamports")]
+    InsufficientBalance(u64, u64),
+
+    #[error("Transaction too large: {size} bytes, limit is {limit} bytes")]
+    TransactionTooLarge { size: usize, limit: usize },
+
+    #[error("Too many accounts: {count}, limit is {limit}")]
+    TooManyAccounts { count: usize, limit: usize },
+
+    #[error("Invalid signature count {0}, the message requires {1}")]
+    InvalidSignatureCount(usize, usize),
+
     #[error("Compute budget already present in instructions")]
     ComputeBudgetAlreadyPresent,
 
     #[error("Internal moka cache error {0}")]
     MokaCacheError(String),
 
+    #[error("Account not found: {0}")]
+    AccountNotFound(solana_pubkey::Pubkey),
+
     #[error("Lookup table miss")]
     LookupTableMiss,
 
@@ -0,0 +1,69 @@
+use {
+    super::TransactionBuilder,
+    crate::fee::decode_compute_budget,
+    solana_instruction::Instruction,
+    std::fmt::Write,
+};
+
+/// Human readable rendering of an instruction's data.
+///
+/// Compute budget instructions are decoded, anything else is shown as hex.
+fn explain_data(ix: &Instruction) -> String {
+    if ix.program_id == solana_compute_budget_interface::ID
+        && let Some(decoded) = decode_compute_budget(&ix.data)
+    {
+        return format!("{decoded:?}");
+    }
+    let hex: String = ix.data.iter().map(|b| format!("{b:02x}")).collect();
+    format!("data=0x{hex}")
+}
+
+impl TransactionBuilder {
+    /// Multi-line description of the instructions and lookup configuration,
+    /// meant for debug logs.
+    pub fn explain(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "instructions={} lookup_keys={} address_tables={}",
+            self.instructions.len(),
+            self.lookup_tables_keys.as_ref().map_or(0, Vec::len),
+            self.address_lookup_tables.as_ref().map_or(0, Vec::len),
+        );
+        for (i, ix) in self.instructions.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "#{i} program={} accounts={} {}",
+                ix.program_id,
+                ix.accounts.len(),
+                explain_data(ix)
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_pubkey::Pubkey};
+
+    #[test]
+    fn test_explain_compute_budget() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let tx = TransactionBuilder::default()
+            .with_memo("hi", &[&payer])
+            .prepend_compute_budget_instructions(1_000_000, 200_000)?;
+        let explained = tx.explain();
+        let lines: Vec<&str> = explained.lines().collect();
+        assert_eq!(4, lines.len());
+        assert_eq!("instructions=3 lookup_keys=0 address_tables=0", lines[0]);
+        assert!(lines[1].ends_with("SetComputeUnitLimit(1000000)"));
+        assert!(lines[2].ends_with("SetComputeUnitPrice(200000)"));
+        assert!(lines[3].ends_with("data=0x6869"));
+        assert_eq!(
+            tx.current_compute_budget(),
+            (Some(1_000_000), Some(200_000))
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+use {
+    super::{Result, TransactionBuilder},
+    crate::TransactionRpcProvider,
+    solana_pubkey::Pubkey,
+    std::fmt::Display,
+};
+
+const EXPLORER_INSPECTOR_URL: &str = "https://explorer.solana.com/tx/inspector";
+const LOCALNET_URL: &str = "http://localhost:8899";
+
+/// Solana cluster, as understood by the Solana explorer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cluster {
+    MainnetBeta,
+    Devnet,
+    Testnet,
+    Localnet,
+}
+
+impl Cluster {
+    /// Query string selecting this cluster on <https://explorer.solana.com>
+    fn explorer_query(&self) -> String {
+        match self {
+            Cluster::Localnet => format!("cluster=custom&customUrl={}", url_encode(LOCALNET_URL)),
+            _ => format!("cluster={self}"),
+        }
+    }
+}
+
+impl Display for Cluster {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Cluster::MainnetBeta => "mainnet-beta",
+            Cluster::Devnet => "devnet",
+            Cluster::Testnet => "testnet",
+            Cluster::Localnet => "localnet",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Guesses the cluster from an RPC URL's host name.
+///
+/// Recognizes hosts containing `mainnet`, `devnet` or `testnet` (such as
+/// `api.devnet.solana.com` or `mainnet.helius-rpc.com`) and local addresses.
+/// Returns `None` for anything else, e.g. a private RPC without a cluster hint.
+pub fn cluster_from_url(url: &str) -> Option<Cluster> {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = without_scheme
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default();
+    let host_port = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = match host_port.strip_prefix('[') {
+        Some(ipv6) => ipv6.split(']').next().unwrap_or_default(),
+        None => host_port.split(':').next().unwrap_or_default(),
+    }
+    .to_ascii_lowercase();
+    match host.as_str() {
+        "localhost" | "127.0.0.1" | "0.0.0.0" | "::1" => Some(Cluster::Localnet),
+        h if h.contains("mainnet") => Some(Cluster::MainnetBeta),
+        h if h.contains("devnet") => Some(Cluster::Devnet),
+        h if h.contains("testnet") => Some(Cluster::Testnet),
+        _ => None,
+    }
+}
+
+/// Percent-encodes everything outside of the URL unreserved character set.
+fn url_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(b as char)
+            }
+            _ => encoded.push_str(&format!("%{b:02X}")),
+        }
+    }
+    encoded
+}
+
+impl TransactionBuilder {
+    /// Builds a <https://explorer.solana.com/tx/inspector> URL for the compiled
+    /// message, handy for sharing a transaction in support tickets.
+    ///
+    /// The message is compiled with a fresh blockhash, so the URL is a
+    /// snapshot and is not meant to be signed from.
+    pub async fn inspector_url<T: TransactionRpcProvider>(
+        &self,
+        payer: &Pubkey,
+        rpc: &T,
+        cluster: Cluster,
+    ) -> Result<String> {
+        let message = self.message_base64(payer, rpc).await?;
+        Ok(format!(
+            "{EXPLORER_INSPECTOR_URL}?message={}&{}",
+            url_encode(&message),
+            cluster.explorer_query()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::rpc::noop::NoopRpcNative,
+        base64::prelude::*,
+        solana_message::VersionedMessage,
+    };
+
+    fn url_decode(value: &str) -> String {
+        let mut decoded = Vec::with_capacity(value.len());
+        let bytes = value.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b'%' {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap();
+                decoded.push(u8::from_str_radix(hex, 16).unwrap());
+                i += 3;
+            } else {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+        String::from_utf8(decoded).unwrap()
+    }
+
+    #[test]
+    fn test_cluster_from_url() {
+        for (url, cluster) in [
+            ("https://api.mainnet-beta.solana.com", Cluster::MainnetBeta),
+            (
+                "https://mainnet.helius-rpc.com/?api-key=abc",
+                Cluster::MainnetBeta,
+            ),
+            ("https://api.devnet.solana.com", Cluster::Devnet),
+            (
+                "https://devnet.helius-rpc.com/?api-key=abc",
+                Cluster::Devnet,
+            ),
+            ("https://api.testnet.solana.com/", Cluster::Testnet),
+            ("http://localhost:8899", Cluster::Localnet),
+            ("http://127.0.0.1:8899", Cluster::Localnet),
+            ("http://[::1]:8899", Cluster::Localnet),
+        ] {
+            assert_eq!(Some(cluster), cluster_from_url(url), "{url}");
+        }
+        assert_eq!(None, cluster_from_url("https://rpc.example.com/devnet"));
+        assert_eq!(
+            None,
+            cluster_from_url("https://rpc.example.com/?cluster=testnet")
+        );
+        assert_eq!(None, cluster_from_url(""));
+    }
+
+    #[test]
+    fn test_url_encode() {
+        assert_eq!("AQ%2B%2F%3D", url_encode("AQ+/="));
+        assert_eq!("abc-_.~", url_encode("abc-_.~"));
+    }
+
+    #[tokio::test]
+    async fn test_inspector_url() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let tx = TransactionBuilder::default().with_memo("inspect", &[&payer]);
+        let url = tx
+            .inspector_url(&payer, &NoopRpcNative::default(), Cluster::Devnet)
+            .await?;
+
+        assert!(url.starts_with("https://explorer.solana.com/tx/inspector?message="));
+        assert!(url.ends_with("&cluster=devnet"));
+        let encoded = url
+            .trim_start_matches("https://explorer.solana.com/tx/inspector?message=")
+            .trim_end_matches("&cluster=devnet");
+        let message: VersionedMessage =
+            bincode::deserialize(&BASE64_STANDARD.decode(url_decode(encoded))?)?;
+        assert_eq!(payer, message.static_account_keys()[0]);
+        assert_eq!(1, message.instructions().len());
+
+        let url = tx
+            .inspector_url(&payer, &NoopRpcNative::default(), Cluster::Localnet)
+            .await?;
+        assert!(url.ends_with("&cluster=custom&customUrl=http%3A%2F%2Flocalhost%3A8899"));
+        Ok(())
+    }
+}
@@ -1,15 +1,19 @@
 use {
     super::{Error, Result, TransactionBuilder},
-    crate::TransactionRpcProvider,
+    crate::{SimulateConfigBuilder, TransactionRpcProvider},
     solana_compute_budget_interface::ComputeBudgetInstruction,
+    solana_instruction::Instruction,
     solana_pubkey::Pubkey,
-    solana_rpc_client_api::{
-        config::RpcSimulateTransactionConfig,
-        response::{RpcPrioritizationFee, RpcSimulateTransactionResult},
+    solana_rpc_client_api::response::{RpcPrioritizationFee, RpcSimulateTransactionResult},
+    std::{
+        collections::{HashMap, HashSet, VecDeque},
+        sync::{Arc, Mutex},
     },
 };
 
 const SOLANA_MAX_COMPUTE_UNITS: u32 = 1_400_000;
+/// Most accounts accepted by `getRecentPrioritizationFees`
+const MAX_PRIORITY_FEE_ACCOUNTS: usize = 128;
 const MAX_ACCEPTABLE_PRIORITY_FEE_MICROLAMPORTS: u64 = 90_000 * 1_000_000; // 0.00009 SOL per CU in microlamports
 
 /// Result of priority fee calculation containing the computed fee and compute
@@ -25,6 +29,238 @@ pub struct CalcFeeResult {
     pub units: u32,
     /// Result from RPC call get_recent_prioritization_fees
     pub prioritization_fees: Vec<RpcPrioritizationFee>,
+    /// Accounts passed to get_recent_prioritization_fees
+    pub accounts: Vec<Pubkey>,
+}
+
+impl CalcFeeResult {
+    /// The `SetComputeUnitLimit` and `SetComputeUnitPrice` instructions for
+    /// this result, see [`TransactionBuilder::apply_fee`].
+    pub fn into_instructions(&self) -> Vec<Instruction> {
+        compute_budget_instructions(self.units, self.priority_fee)
+    }
+
+    /// Change from the earlier estimate `prev` to this one, for monitoring
+    /// fee drift.
+    pub fn delta(&self, prev: &CalcFeeResult) -> FeeDelta {
+        FeeDelta {
+            priority_fee: i128::from(self.priority_fee) - i128::from(prev.priority_fee),
+            units: i64::from(self.units) - i64::from(prev.units),
+        }
+    }
+}
+
+/// Change between two [`CalcFeeResult`]s, see [`CalcFeeResult::delta`].
+/// Positive values are increases.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeeDelta {
+    /// Change of the priority fee in microlamports per compute unit
+    pub priority_fee: i128,
+    /// Change of the compute unit limit
+    pub units: i64,
+}
+
+/// Priority fee suggested by a [`PriorityFeeProvider`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeeEstimate {
+    /// Priority fee in microlamports per compute unit
+    pub priority_fee: u64,
+    /// Recent fees the estimate is based on, empty when the source does not
+    /// expose them
+    pub prioritization_fees: Vec<RpcPrioritizationFee>,
+}
+
+/// Source of priority fee estimates, see
+/// [`TransactionBuilder::with_priority_fees_from`].
+///
+/// Every [`TransactionRpcProvider`] estimates from
+/// `getRecentPrioritizationFees`. Implement it for an external fee oracle;
+/// the cap, fee floors and compute unit limit of
+/// [`TransactionBuilder::calc_fee`] still apply to its estimate.
+#[async_trait::async_trait]
+pub trait PriorityFeeProvider: Send + Sync {
+    /// Fee at `percentile` (0-100) of recent fees paid by transactions
+    /// write-locking `writable_accounts`
+    async fn estimate(&self, writable_accounts: &[Pubkey], percentile: u8) -> Result<FeeEstimate>;
+}
+
+#[async_trait::async_trait]
+impl<T: TransactionRpcProvider> PriorityFeeProvider for T {
+    async fn estimate(&self, writable_accounts: &[Pubkey], percentile: u8) -> Result<FeeEstimate> {
+        let prioritization_fees =
+            TransactionBuilder::get_recent_prioritization_fees(self, writable_accounts).await?;
+        if prioritization_fees.is_empty() {
+            return Err(crate::Error::SolanaRpcError(
+                "No prioritization fees available".to_string(),
+            ));
+        }
+        let mut sorted_fees: Vec<u64> = prioritization_fees
+            .iter()
+            .map(|f| f.prioritization_fee)
+            .collect();
+        sorted_fees.sort();
+
+        let index = (sorted_fees.len() * usize::from(percentile.min(100))).saturating_sub(1) / 100;
+        Ok(FeeEstimate {
+            priority_fee: sorted_fees[index],
+            prioritization_fees,
+        })
+    }
+}
+
+/// Recommended compute budget, e.g. from a central fee-market snapshot,
+/// applied with [`TransactionBuilder::with_budget`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ComputeBudget {
+    /// Compute unit limit
+    pub units: u32,
+    /// Compute unit price in microlamports
+    pub price_microlamports: u64,
+    /// Heap frame size in bytes, a multiple of 1024
+    pub heap_bytes: Option<u32>,
+    /// Limit on loaded accounts data size in bytes
+    pub loaded_data_bytes: Option<u32>,
+}
+
+impl ComputeBudget {
+    /// Compute budget instructions for this budget, optional ones only when
+    /// set.
+    pub fn instructions(&self) -> Vec<Instruction> {
+        let mut instructions = compute_budget_instructions(self.units, self.price_microlamports);
+        if let Some(bytes) = self.heap_bytes {
+            instructions.push(ComputeBudgetInstruction::request_heap_frame(bytes));
+        }
+        if let Some(bytes) = self.loaded_data_bytes {
+            instructions.push(ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(bytes));
+        }
+        instructions
+    }
+}
+
+/// Rolling cap on chosen priority fees, dampening runaway bidding during fee
+/// spikes.
+///
+/// Remembers the last `window` fees chosen through
+/// [`TransactionBuilder::with_priority_fees_surge_protected`] and caps each new
+/// fee at `multiplier` times their median. Clones share the same window, so
+/// pass one instance to every builder that should be dampened together.
+#[derive(Debug, Clone)]
+pub struct SurgeProtection {
+    window: usize,
+    multiplier: u64,
+    recent: Arc<Mutex<VecDeque<u64>>>,
+}
+
+impl Default for SurgeProtection {
+    /// Window of 20 fees, capped at 3x the median
+    fn default() -> Self {
+        Self::new(20, 3)
+    }
+}
+
+impl SurgeProtection {
+    pub fn new(window: usize, multiplier: u64) -> Self {
+        let window = window.max(1);
+        Self {
+            window,
+            multiplier,
+            recent: Arc::new(Mutex::new(VecDeque::with_capacity(window))),
+        }
+    }
+
+    /// Median of the fees in the window, `None` while it is empty.
+    pub fn median(&self) -> Option<u64> {
+        let recent = self.recent.lock().unwrap();
+        let mut sorted: Vec<u64> = recent.iter().copied().collect();
+        sorted.sort();
+        sorted.get(sorted.len() / 2).copied()
+    }
+
+    /// Caps `fee` at `multiplier` times the current median and records the
+    /// result in the window.
+    ///
+    /// A zero median does not cap, otherwise a quiet period would pin every
+    /// following fee at zero.
+    pub fn cap(&self, fee: u64) -> u64 {
+        let capped = match self.median() {
+            Some(median) if median > 0 => fee.min(median.saturating_mul(self.multiplier)),
+            _ => fee,
+        };
+        if capped < fee {
+            tracing::warn!(fee, capped, "priority fee capped by surge protection");
+        }
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() == self.window {
+            recent.pop_front();
+        }
+        recent.push_back(capped);
+        capped
+    }
+}
+
+fn compute_budget_instructions(units: u32, priority_fees: u64) -> Vec<Instruction> {
+    vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(units),
+        ComputeBudgetInstruction::set_compute_unit_price(priority_fees),
+    ]
+}
+
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+/// Compute unit price, in microlamports (1/1_000_000 lamport) per compute
+/// unit as set by `SetComputeUnitPrice`, that spends at most `total_lamports`
+/// on priority over `units`.
+///
+/// Rounds down, so the fee never exceeds the budget. Zero `units` return a
+/// price of zero.
+pub fn priority_lamports_to_price_per_cu(total_lamports: u64, units: u32) -> u64 {
+    if units == 0 {
+        return 0;
+    }
+    let price = u128::from(total_lamports) * MICRO_LAMPORTS_PER_LAMPORT / u128::from(units);
+    u64::try_from(price).unwrap_or(u64::MAX)
+}
+
+/// Priority fee in lamports paid for `units` at `price` microlamports per
+/// compute unit.
+///
+/// Rounds up like the runtime does, so a partial lamport is charged in full.
+pub fn price_per_cu_to_priority_lamports(price: u64, units: u32) -> u64 {
+    let micro_lamports = u128::from(price) * u128::from(units);
+    u64::try_from(micro_lamports.div_ceil(MICRO_LAMPORTS_PER_LAMPORT)).unwrap_or(u64::MAX)
+}
+
+/// Splits `total_lamports` into a compute unit price in microlamports for
+/// `units` and the remaining tip in lamports. `split` is the share of the
+/// priority fee, clamped to `0.0..=1.0`.
+///
+/// The price rounds down, so the priority fee never exceeds its share and
+/// the total stays within budget.
+fn split_tip(total_lamports: u64, split: f64, units: u32) -> (u64, u64) {
+    // the f64 product can round above large totals
+    let priority_lamports =
+        ((total_lamports as f64 * split.clamp(0.0, 1.0)) as u64).min(total_lamports);
+    let price = priority_lamports_to_price_per_cu(priority_lamports, units.max(1));
+    (price, total_lamports - priority_lamports)
+}
+
+/// Decodes a compute budget program instruction from its data.
+///
+/// Returns `None` for malformed data or unknown discriminators.
+pub(crate) fn decode_compute_budget(data: &[u8]) -> Option<ComputeBudgetInstruction> {
+    let (discriminator, value) = data.split_first()?;
+    let u32_value = || value.try_into().ok().map(u32::from_le_bytes);
+    match discriminator {
+        1 => u32_value().map(ComputeBudgetInstruction::RequestHeapFrame),
+        2 => u32_value().map(ComputeBudgetInstruction::SetComputeUnitLimit),
+        3 => value
+            .try_into()
+            .ok()
+            .map(u64::from_le_bytes)
+            .map(ComputeBudgetInstruction::SetComputeUnitPrice),
+        4 => u32_value().map(ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit),
+        _ => None,
+    }
 }
 
 impl TransactionBuilder {
@@ -47,29 +283,168 @@ impl TransactionBuilder {
             return Err(crate::Error::ComputeBudgetAlreadyPresent);
         }
 
-        self.instructions.splice(0..0, vec![
-            ComputeBudgetInstruction::set_compute_unit_limit(units),
-            ComputeBudgetInstruction::set_compute_unit_price(priority_fees),
-        ]);
+        self.instructions
+            .splice(0..0, compute_budget_instructions(units, priority_fees));
+        Ok(self)
+    }
+
+    /// The `SetComputeUnitLimit` and `SetComputeUnitPrice` instructions
+    /// [`TransactionBuilder::prepend_compute_budget_instructions`] would add,
+    /// without a builder, e.g. to show them in a UI before applying.
+    pub fn preview_compute_budget(units: u32, priority_fees: u64) -> Vec<Instruction> {
+        compute_budget_instructions(units, priority_fees)
+    }
+
+    /// Compute unit limit used by [`TransactionBuilder::calc_fee`] when the
+    /// simulation succeeds but the RPC reports no `units_consumed`.
+    ///
+    /// Defaults to half of the maximum compute units. The value is used as is,
+    /// without the buffer applied to simulated units.
+    pub fn with_default_compute_units(mut self, units: u32) -> Self {
+        self.default_compute_units = Some(units);
+        self
+    }
+
+    /// Cap on the compute unit limit chosen by
+    /// [`TransactionBuilder::calc_fee`], for runtimes with a limit other
+    /// than the default 1.4M units.
+    pub fn with_max_compute_units(mut self, units: u32) -> Self {
+        self.max_compute_units = Some(units);
+        self
+    }
+
+    /// Minimum priority fees (microlamports per compute unit) for accounts
+    /// known to be congested, such as hot AMM pools.
+    ///
+    /// [`TransactionBuilder::calc_fee`] raises the percentile fee to the
+    /// highest floor among the writable accounts of the instructions. Floors
+    /// are not capped by `max_prioritization_fee`.
+    pub fn with_fee_floors<I>(mut self, floors: I) -> Self
+    where
+        I: IntoIterator<Item = (Pubkey, u64)>,
+    {
+        self.fee_floors
+            .get_or_insert_with(HashMap::new)
+            .extend(floors);
+        self
+    }
+
+    /// Highest [`TransactionBuilder::with_fee_floors`] floor among the
+    /// writable accounts, `0` without any.
+    fn fee_floor(&self) -> u64 {
+        let Some(floors) = &self.fee_floors else {
+            return 0;
+        };
+        self.hooked_instructions()
+            .iter()
+            .flat_map(|ix| &ix.accounts)
+            .filter(|meta| meta.is_writable)
+            .filter_map(|meta| floors.get(&meta.pubkey))
+            .copied()
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Prepends all compute budget instructions of `budget` in one call.
+    /// Fails if ComputeBudget instructions are already present.
+    pub fn with_budget(mut self, budget: &ComputeBudget) -> Result<Self> {
+        if self
+            .instructions
+            .iter()
+            .any(|ix| ix.program_id == solana_compute_budget_interface::ID)
+        {
+            return Err(crate::Error::ComputeBudgetAlreadyPresent);
+        }
+        self.instructions.splice(0..0, budget.instructions());
         Ok(self)
     }
 
+    /// Compute unit limit and price set by the compute budget instructions
+    /// already in the transaction, if any.
+    pub fn current_compute_budget(&self) -> (Option<u32>, Option<u64>) {
+        let mut budget = (None, None);
+        for ix in self
+            .instructions
+            .iter()
+            .filter(|ix| ix.program_id == solana_compute_budget_interface::ID)
+        {
+            match decode_compute_budget(&ix.data) {
+                Some(ComputeBudgetInstruction::SetComputeUnitLimit(units)) => {
+                    budget.0 = Some(units)
+                }
+                Some(ComputeBudgetInstruction::SetComputeUnitPrice(price)) => {
+                    budget.1 = Some(price)
+                }
+                _ => {}
+            }
+        }
+        budget
+    }
+
+    /// Spends `total_lamports` on both priority fee and a Jito tip: `split` of
+    /// the total (clamped to `0.0..=1.0`) becomes the compute unit price, the
+    /// rest is transferred from `payer` to `tip_account` in a last
+    /// instruction.
+    ///
+    /// The compute unit limit is taken from the builder's `SetComputeUnitLimit`
+    /// instruction, or [`TransactionBuilder::with_default_compute_units`].
+    /// Existing limit and price instructions are replaced by new ones in
+    /// front.
+    pub fn allocate_tip(
+        mut self,
+        payer: &Pubkey,
+        tip_account: &Pubkey,
+        total_lamports: u64,
+        split: f64,
+    ) -> Result<Self> {
+        let units = self
+            .current_compute_budget()
+            .0
+            .or(self.default_compute_units)
+            .ok_or_else(|| {
+                Error::InvalidComputeUnits(0, "no compute unit limit to price".to_string())
+            })?;
+        let (price, tip) = split_tip(total_lamports, split, units);
+        self.instructions.retain(|ix| {
+            ix.program_id != solana_compute_budget_interface::ID
+                || !matches!(
+                    decode_compute_budget(&ix.data),
+                    Some(
+                        ComputeBudgetInstruction::SetComputeUnitLimit(_)
+                            | ComputeBudgetInstruction::SetComputeUnitPrice(_)
+                    )
+                )
+        });
+        self.instructions
+            .splice(0..0, compute_budget_instructions(units, price));
+        if tip > 0 {
+            self.instructions
+                .push(solana_system_interface::instruction::transfer(
+                    payer,
+                    tip_account,
+                    tip,
+                ));
+        }
+        Ok(self)
+    }
+
+    /// Prepends the compute budget instructions of a [`CalcFeeResult`], see
+    /// [`TransactionBuilder::calc_fee`].
+    pub fn apply_fee(self, calc: &CalcFeeResult) -> Result<Self> {
+        self.prepend_compute_budget_instructions(calc.units, calc.priority_fee)
+    }
+
     fn calc_fee_internal(
         &self,
-        prioritization_fees: Vec<RpcPrioritizationFee>,
+        accounts: &[Pubkey],
+        estimate: FeeEstimate,
         sim_result: RpcSimulateTransactionResult,
         max_prioritization_fee: u64,
-        percentile: Option<u8>,
     ) -> Result<CalcFeeResult> {
-        let percentile = percentile.unwrap_or(75).min(100);
-        let mut sorted_fees: Vec<u64> = prioritization_fees
-            .iter()
-            .map(|f| f.prioritization_fee)
-            .collect();
-        sorted_fees.sort();
-
-        let index = (sorted_fees.len() * percentile as usize).saturating_sub(1) / 100;
-        let priority_fee = sorted_fees[index].min(max_prioritization_fee);
+        let priority_fee = estimate
+            .priority_fee
+            .min(max_prioritization_fee)
+            .max(self.fee_floor());
         if priority_fee > MAX_ACCEPTABLE_PRIORITY_FEE_MICROLAMPORTS {
             return Err(crate::Error::PriorityFeeTooHigh(
                 priority_fee,
@@ -77,22 +452,33 @@ impl TransactionBuilder {
             ));
         }
 
-        let compute_unit_limit: u32 = sim_result
-            .units_consumed
-            .ok_or(crate::Error::InvalidComputeUnits(
-                0,
-                "RPC returned no units".to_string(),
-            ))?
-            .try_into()?;
-        // Add buffer but cap at Solana's maximum
-        let buffered_limit = compute_unit_limit
-            .saturating_add(compute_unit_limit / 10)
-            .min(SOLANA_MAX_COMPUTE_UNITS);
+        let max_units = self.max_compute_units.unwrap_or(SOLANA_MAX_COMPUTE_UNITS);
+        let buffered_limit = match sim_result.units_consumed {
+            Some(units_consumed) => {
+                let compute_unit_limit: u32 = units_consumed.try_into()?;
+                // Add buffer but cap at Solana's maximum
+                compute_unit_limit
+                    .saturating_add(compute_unit_limit / 10)
+                    .min(max_units)
+            }
+            None => {
+                let fallback = self
+                    .default_compute_units
+                    .unwrap_or(max_units / 2)
+                    .min(max_units);
+                tracing::warn!(
+                    fallback,
+                    "RPC returned no units, using default compute units"
+                );
+                fallback
+            }
+        };
 
         Ok(CalcFeeResult {
             priority_fee,
             units: buffered_limit,
-            prioritization_fees,
+            prioritization_fees: estimate.prioritization_fees,
+            accounts: accounts.to_vec(),
         })
     }
 }
@@ -116,30 +502,68 @@ impl TransactionBuilder {
         accounts: &[Pubkey],
         max_prioritization_fee: u64,
         percentile: Option<u8>,
+    ) -> Result<CalcFeeResult> {
+        self.calc_fee_with_provider(
+            payer,
+            rpc,
+            rpc,
+            accounts,
+            max_prioritization_fee,
+            percentile,
+        )
+        .await
+    }
+
+    /// [`TransactionBuilder::calc_fee`] with the fee estimated by `fees`
+    /// instead of the recent prioritization fees of `rpc`.
+    pub async fn calc_fee_with_provider<T: TransactionRpcProvider, F: PriorityFeeProvider>(
+        &self,
+        payer: &Pubkey,
+        rpc: &T,
+        fees: &F,
+        accounts: &[Pubkey],
+        max_prioritization_fee: u64,
+        percentile: Option<u8>,
     ) -> Result<CalcFeeResult> {
         if self.instructions.is_empty() {
             return Err(crate::Error::NoInstructions);
         }
-        let prioritization_fees =
-            TransactionBuilder::get_recent_prioritization_fees(rpc, accounts).await?;
-        if prioritization_fees.is_empty() {
-            return Err(crate::Error::SolanaRpcError(
-                "No prioritization fees available".to_string(),
-            ));
-        }
+        let estimate = fees
+            .estimate(accounts, percentile.unwrap_or(75).min(100))
+            .await?;
         let tx = self.unsigned_tx(payer, rpc).await?;
         let sim_result = self
-            .simulate_internal(rpc, &tx, RpcSimulateTransactionConfig {
-                sig_verify: false,
-                ..Default::default()
-            })
+            .simulate_internal(
+                rpc,
+                &tx,
+                SimulateConfigBuilder::new().sig_verify(false).build(),
+            )
             .await?;
-        self.calc_fee_internal(
-            prioritization_fees,
-            sim_result,
-            max_prioritization_fee,
-            percentile,
-        )
+        self.calc_fee_internal(accounts, estimate, sim_result, max_prioritization_fee)
+    }
+
+    /// Simulates the transaction and returns `units_consumed` as reported by
+    /// the RPC, without the buffer and cap applied by
+    /// [`TransactionBuilder::calc_fee`].
+    pub async fn raw_compute_units<T: TransactionRpcProvider>(
+        &self,
+        payer: &Pubkey,
+        rpc: &T,
+    ) -> Result<u64> {
+        let tx = self.unsigned_tx(payer, rpc).await?;
+        let sim_result = self
+            .simulate_internal(
+                rpc,
+                &tx,
+                SimulateConfigBuilder::new().sig_verify(false).build(),
+            )
+            .await?;
+        sim_result
+            .units_consumed
+            .ok_or(crate::Error::InvalidComputeUnits(
+                0,
+                "RPC returned no units".to_string(),
+            ))
     }
 
     /// Quick and dirty fee estimation using recent prioritization fees.
@@ -196,6 +620,30 @@ impl TransactionBuilder {
         accounts: &[Pubkey],
         max_prioritization_fee: u64,
         percentile: Option<u8>,
+    ) -> Result<Self> {
+        self.with_priority_fees_from(
+            payer,
+            rpc,
+            rpc,
+            accounts,
+            max_prioritization_fee,
+            percentile,
+        )
+        .await
+    }
+
+    /// [`TransactionBuilder::with_priority_fees`] with the fee estimated by
+    /// `fees`, e.g. an external fee oracle, see [`PriorityFeeProvider`].
+    /// `rpc` still simulates the compute units.
+    #[tracing::instrument(skip(rpc, fees, payer, accounts), level = tracing::Level::DEBUG)]
+    pub async fn with_priority_fees_from<T: TransactionRpcProvider, F: PriorityFeeProvider>(
+        self,
+        payer: &Pubkey,
+        rpc: &T,
+        fees: &F,
+        accounts: &[Pubkey],
+        max_prioritization_fee: u64,
+        percentile: Option<u8>,
     ) -> Result<Self> {
         if self
             .instructions
@@ -206,8 +654,504 @@ impl TransactionBuilder {
             return Ok(self);
         }
         let result = self
-            .calc_fee(payer, rpc, accounts, max_prioritization_fee, percentile)
+            .calc_fee_with_provider(
+                payer,
+                rpc,
+                fees,
+                accounts,
+                max_prioritization_fee,
+                percentile,
+            )
             .await?;
         self.prepend_compute_budget_instructions(result.units, result.priority_fee)
     }
+
+    /// Writable accounts of the instructions (hook instructions included),
+    /// deduped in order and capped at the 128 accounts accepted by
+    /// `getRecentPrioritizationFees`.
+    pub fn priority_fee_accounts(&self) -> Vec<Pubkey> {
+        let mut seen = HashSet::new();
+        self.hooked_instructions()
+            .iter()
+            .flat_map(|ix| &ix.accounts)
+            .filter(|meta| meta.is_writable && seen.insert(meta.pubkey))
+            .map(|meta| meta.pubkey)
+            .take(MAX_PRIORITY_FEE_ACCOUNTS)
+            .collect()
+    }
+
+    /// [`TransactionBuilder::with_priority_fees`] querying the fees of the
+    /// accounts the transaction write-locks, see
+    /// [`TransactionBuilder::priority_fee_accounts`].
+    #[tracing::instrument(skip(rpc, payer), level = tracing::Level::DEBUG)]
+    pub async fn with_priority_fees_auto<T: TransactionRpcProvider>(
+        self,
+        payer: &Pubkey,
+        rpc: &T,
+        max_prioritization_fee: u64,
+        percentile: Option<u8>,
+    ) -> Result<Self> {
+        let accounts = self.priority_fee_accounts();
+        self.with_priority_fees(payer, rpc, &accounts, max_prioritization_fee, percentile)
+            .await
+    }
+
+    /// Same as [`TransactionBuilder::with_priority_fees`], with the chosen fee
+    /// capped by [`SurgeProtection::cap`].
+    #[tracing::instrument(skip(rpc, payer, accounts, surge), level = tracing::Level::DEBUG)]
+    pub async fn with_priority_fees_surge_protected<T: TransactionRpcProvider>(
+        self,
+        payer: &Pubkey,
+        rpc: &T,
+        accounts: &[Pubkey],
+        max_prioritization_fee: u64,
+        percentile: Option<u8>,
+        surge: &SurgeProtection,
+    ) -> Result<Self> {
+        if self
+            .instructions
+            .iter()
+            .any(|ix| ix.program_id == solana_compute_budget_interface::ID)
+        {
+            tracing::warn!("ComputeBudgetProgram already exists");
+            return Ok(self);
+        }
+        let result = self
+            .calc_fee(payer, rpc, accounts, max_prioritization_fee, percentile)
+            .await?;
+        self.prepend_compute_budget_instructions(result.units, surge.cap(result.priority_fee))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::rpc::mock::MockRpc};
+
+    #[test]
+    fn test_priority_lamports_conversion() {
+        // 0.0001 SOL over 200_000 units
+        assert_eq!(500_000, priority_lamports_to_price_per_cu(100_000, 200_000));
+        assert_eq!(100_000, price_per_cu_to_priority_lamports(500_000, 200_000));
+        assert_eq!(0, priority_lamports_to_price_per_cu(100_000, 0));
+        assert_eq!(0, price_per_cu_to_priority_lamports(500_000, 0));
+        assert_eq!(0, price_per_cu_to_priority_lamports(0, 200_000));
+        // price rounds down, within budget
+        assert_eq!(333, priority_lamports_to_price_per_cu(1, 3_000));
+        // lamports round up, a partial lamport is charged
+        assert_eq!(1, price_per_cu_to_priority_lamports(333, 3_000));
+        assert_eq!(1, price_per_cu_to_priority_lamports(1, 1));
+        assert_eq!(u64::MAX, priority_lamports_to_price_per_cu(u64::MAX, 1));
+        assert_eq!(
+            u64::MAX,
+            price_per_cu_to_priority_lamports(u64::MAX, u32::MAX)
+        );
+    }
+
+    #[test]
+    fn test_preview_compute_budget() -> anyhow::Result<()> {
+        let preview = TransactionBuilder::preview_compute_budget(300_000, 5_000);
+        assert_eq!(
+            vec![
+                Some(ComputeBudgetInstruction::SetComputeUnitLimit(300_000)),
+                Some(ComputeBudgetInstruction::SetComputeUnitPrice(5_000)),
+            ],
+            preview
+                .iter()
+                .map(|ix| decode_compute_budget(&ix.data))
+                .collect::<Vec<_>>()
+        );
+        let tx =
+            TransactionBuilder::default().prepend_compute_budget_instructions(300_000, 5_000)?;
+        assert_eq!(tx.instructions, preview);
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_tip() {
+        // 250_000 lamports over 200_000 units
+        assert_eq!((1_250_000, 750_000), split_tip(1_000_000, 0.25, 200_000));
+        assert_eq!((0, 1_000), split_tip(1_000, 0.0, 1_000));
+        assert_eq!((1_000_000, 0), split_tip(1_000, 1.0, 1_000));
+        // clamped, and rounding down keeps the total within budget
+        assert_eq!((1_000_000, 0), split_tip(1_000, 2.0, 1_000));
+        assert_eq!((333, 0), split_tip(1, 1.0, 3_000));
+        // 2^54 - 1 rounds up to 2^54 as f64
+        let total = (1 << 54) - 1;
+        assert_eq!((total, 0), split_tip(total, 1.0, 1_000_000));
+    }
+
+    #[test]
+    fn test_allocate_tip() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let tip_account = Pubkey::new_unique();
+        let tx = TransactionBuilder::default()
+            .with_memo("tip", &[&payer])
+            .prepend_compute_budget_instructions(200_000, 1)?
+            .allocate_tip(&payer, &tip_account, 1_000_000, 0.25)?;
+
+        assert_eq!(4, tx.instructions.len());
+        assert_eq!(
+            (Some(200_000), Some(1_250_000)),
+            tx.current_compute_budget()
+        );
+        assert_eq!(
+            Some(ComputeBudgetInstruction::SetComputeUnitLimit(200_000)),
+            decode_compute_budget(&tx.instructions[0].data)
+        );
+        assert_eq!(
+            Some(ComputeBudgetInstruction::SetComputeUnitPrice(1_250_000)),
+            decode_compute_budget(&tx.instructions[1].data)
+        );
+        assert_eq!(spl_memo_interface::v3::ID, tx.instructions[2].program_id);
+        assert_eq!(
+            solana_system_interface::instruction::transfer(&payer, &tip_account, 750_000),
+            tx.instructions[3]
+        );
+
+        // all to the priority fee, no transfer
+        let tx = TransactionBuilder::default()
+            .with_memo("tip", &[&payer])
+            .with_default_compute_units(100_000)
+            .allocate_tip(&payer, &tip_account, 1_000, 1.0)?;
+        assert_eq!(3, tx.instructions.len());
+        assert_eq!((Some(100_000), Some(10_000)), tx.current_compute_budget());
+
+        let result = TransactionBuilder::default()
+            .with_memo("tip", &[&payer])
+            .allocate_tip(&payer, &tip_account, 1_000, 0.5);
+        assert!(matches!(result, Err(Error::InvalidComputeUnits(0, _))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_raw_compute_units() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let tx = TransactionBuilder::default().with_memo("units", &[&payer]);
+        let rpc = MockRpc {
+            units_consumed: Some(12_345),
+            ..Default::default()
+        };
+        assert_eq!(12_345, tx.raw_compute_units(&payer, &rpc).await?);
+
+        let rpc = MockRpc::default();
+        let result = tx.raw_compute_units(&payer, &rpc).await;
+        assert!(matches!(result, Err(Error::InvalidComputeUnits(0, _))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_calc_fee_missing_units() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let rpc = MockRpc {
+            prioritization_fees: vec![1_000],
+            units_consumed: None,
+            ..Default::default()
+        };
+        let tx = TransactionBuilder::default().with_memo("units", &[&payer]);
+        let result = tx.calc_fee(&payer, &rpc, &[], u64::MAX, None).await?;
+        assert_eq!(SOLANA_MAX_COMPUTE_UNITS / 2, result.units);
+        assert_eq!(1_000, result.priority_fee);
+
+        let tx = tx.with_default_compute_units(200_000);
+        let result = tx.calc_fee(&payer, &rpc, &[], u64::MAX, None).await?;
+        assert_eq!(200_000, result.units);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_calc_fee_max_compute_units() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let rpc = MockRpc {
+            prioritization_fees: vec![1_000],
+            units_consumed: Some(300_000),
+            ..Default::default()
+        };
+        let tx = TransactionBuilder::default().with_memo("cap", &[&payer]);
+        let result = tx.calc_fee(&payer, &rpc, &[], u64::MAX, None).await?;
+        assert_eq!(330_000, result.units);
+
+        let tx = tx.with_max_compute_units(200_000);
+        let result = tx.calc_fee(&payer, &rpc, &[], u64::MAX, None).await?;
+        assert_eq!(200_000, result.units);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_calc_fee_accounts() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let rpc = MockRpc {
+            prioritization_fees: vec![1_000],
+            units_consumed: Some(1_000),
+            ..Default::default()
+        };
+        let accounts = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let tx = TransactionBuilder::default().with_memo("accounts", &[&payer]);
+        let result = tx.calc_fee(&payer, &rpc, &accounts, u64::MAX, None).await?;
+        assert_eq!(accounts.to_vec(), result.accounts);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_calc_fee_floors() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let pool = Pubkey::new_unique();
+        let rpc = MockRpc {
+            prioritization_fees: vec![1_000],
+            units_consumed: Some(1_000),
+            ..Default::default()
+        };
+        let tx = TransactionBuilder::from(vec![solana_system_interface::instruction::transfer(
+            &payer, &pool, 1,
+        )])
+        .with_fee_floors([(pool, 50_000), (Pubkey::new_unique(), 90_000)]);
+        let result = tx.calc_fee(&payer, &rpc, &[pool], 10_000, None).await?;
+        assert_eq!(50_000, result.priority_fee);
+
+        // the percentile wins over a lower floor
+        let tx = tx.with_fee_floors([(pool, 500)]);
+        let result = tx.calc_fee(&payer, &rpc, &[pool], 10_000, None).await?;
+        assert_eq!(1_000, result.priority_fee);
+
+        // read-only accounts do not count
+        let tx = TransactionBuilder::default()
+            .with_memo("floor", &[&pool])
+            .with_fee_floors([(pool, 50_000)]);
+        let result = tx.calc_fee(&payer, &rpc, &[pool], 10_000, None).await?;
+        assert_eq!(1_000, result.priority_fee);
+        Ok(())
+    }
+
+    struct FixedFee(u64);
+
+    #[async_trait::async_trait]
+    impl PriorityFeeProvider for FixedFee {
+        async fn estimate(&self, _: &[Pubkey], _: u8) -> Result<FeeEstimate> {
+            Ok(FeeEstimate {
+                priority_fee: self.0,
+                prioritization_fees: vec![],
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_priority_fee_provider() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let rpc = MockRpc {
+            prioritization_fees: vec![100, 200, 300, 400],
+            units_consumed: Some(1_000),
+            ..Default::default()
+        };
+        assert_eq!(200, rpc.estimate(&[], 50).await?.priority_fee);
+        assert_eq!(400, rpc.estimate(&[], 100).await?.priority_fee);
+        assert_eq!(4, rpc.estimate(&[], 0).await?.prioritization_fees.len());
+
+        // no recent fees needed from the rpc with an oracle
+        let rpc = MockRpc {
+            units_consumed: Some(1_000),
+            ..Default::default()
+        };
+        let tx = TransactionBuilder::default().with_memo("oracle", &[&payer]);
+        assert!(
+            tx.calc_fee(&payer, &rpc, &[], u64::MAX, None)
+                .await
+                .is_err()
+        );
+        let tx = tx
+            .with_priority_fees_from(&payer, &rpc, &FixedFee(7_000), &[], 5_000, None)
+            .await?;
+        assert_eq!((Some(1_100), Some(5_000)), tx.current_compute_budget());
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_budget() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let budget = ComputeBudget {
+            units: 300_000,
+            price_microlamports: 5_000,
+            heap_bytes: Some(64 * 1024),
+            loaded_data_bytes: Some(128 * 1024),
+        };
+        let tx = TransactionBuilder::default()
+            .with_memo("budget", &[&payer])
+            .with_budget(&budget)?;
+        assert_eq!(5, tx.instructions.len());
+        let decoded: Vec<_> = tx.instructions[..4]
+            .iter()
+            .map(|ix| decode_compute_budget(&ix.data))
+            .collect();
+        assert_eq!(
+            vec![
+                Some(ComputeBudgetInstruction::SetComputeUnitLimit(300_000)),
+                Some(ComputeBudgetInstruction::SetComputeUnitPrice(5_000)),
+                Some(ComputeBudgetInstruction::RequestHeapFrame(64 * 1024)),
+                Some(ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit(
+                    128 * 1024
+                )),
+            ],
+            decoded
+        );
+        assert!(matches!(
+            tx.with_budget(&budget),
+            Err(Error::ComputeBudgetAlreadyPresent)
+        ));
+
+        let minimal = ComputeBudget {
+            units: 1_000,
+            ..Default::default()
+        };
+        assert_eq!(2, minimal.instructions().len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_surge_protection_cap() {
+        let surge = SurgeProtection::new(3, 3);
+        assert_eq!(None, surge.median());
+        assert_eq!(1_000, surge.cap(1_000));
+        assert_eq!(2_000, surge.cap(2_000));
+        // median 2_000
+        assert_eq!(6_000, surge.cap(50_000));
+        // the window holds [1_000, 2_000, 6_000], so escalating stays capped
+        assert_eq!(6_000, surge.cap(1_000_000));
+        assert_eq!(Some(6_000), surge.median());
+        // fees below the cap pass through
+        assert_eq!(500, surge.cap(500));
+
+        let quiet = SurgeProtection::default();
+        assert_eq!(0, quiet.cap(0));
+        assert_eq!(10_000, quiet.cap(10_000));
+    }
+
+    #[tokio::test]
+    async fn test_with_priority_fees_surge_protected() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let surge = SurgeProtection::default();
+        let mut rpc = MockRpc {
+            units_consumed: Some(1_000),
+            ..Default::default()
+        };
+        let mut chosen = vec![];
+        for fee in [1_000, 1_000, 100_000] {
+            rpc.prioritization_fees = vec![fee];
+            let tx = TransactionBuilder::default()
+                .with_memo("surge", &[&payer])
+                .with_priority_fees_surge_protected(&payer, &rpc, &[], u64::MAX, None, &surge)
+                .await?;
+            chosen.push(decode_compute_budget(&tx.instructions[1].data));
+        }
+        assert_eq!(
+            vec![
+                Some(ComputeBudgetInstruction::SetComputeUnitPrice(1_000)),
+                Some(ComputeBudgetInstruction::SetComputeUnitPrice(1_000)),
+                Some(ComputeBudgetInstruction::SetComputeUnitPrice(3_000)),
+            ],
+            chosen
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_priority_fee_accounts() {
+        let payer = Pubkey::new_unique();
+        let destinations: Vec<Pubkey> = (0..200).map(|_| Pubkey::new_unique()).collect();
+        let tx = TransactionBuilder::from(vec![
+            solana_system_interface::instruction::transfer(&payer, &destinations[0], 1),
+            solana_system_interface::instruction::transfer(&payer, &destinations[1], 1),
+        ])
+        .with_memo("readonly", &[&payer]);
+        assert_eq!(
+            vec![payer, destinations[0], destinations[1]],
+            tx.priority_fee_accounts()
+        );
+
+        let tx: TransactionBuilder = destinations
+            .iter()
+            .map(|to| solana_system_interface::instruction::transfer(&payer, to, 1))
+            .collect::<Vec<_>>()
+            .into();
+        let accounts = tx.priority_fee_accounts();
+        assert_eq!(MAX_PRIORITY_FEE_ACCOUNTS, accounts.len());
+        assert_eq!(&destinations[..127], &accounts[1..]);
+    }
+
+    #[test]
+    fn test_calc_fee_result_delta() {
+        let estimate = |priority_fee, units| CalcFeeResult {
+            priority_fee,
+            units,
+            prioritization_fees: vec![],
+            accounts: vec![],
+        };
+        let prev = estimate(1_000, 200_000);
+        let next = estimate(1_500, 150_000);
+        assert_eq!(
+            FeeDelta {
+                priority_fee: 500,
+                units: -50_000
+            },
+            next.delta(&prev)
+        );
+        assert_eq!(
+            FeeDelta {
+                priority_fee: -500,
+                units: 50_000
+            },
+            prev.delta(&next)
+        );
+        assert_eq!(FeeDelta::default(), prev.delta(&prev));
+    }
+
+    #[test]
+    fn test_calc_fee_result_instructions() -> anyhow::Result<()> {
+        let calc = CalcFeeResult {
+            priority_fee: 200_000,
+            units: 1_000_000,
+            prioritization_fees: vec![],
+            accounts: vec![],
+        };
+        let instructions = calc.into_instructions();
+        assert_eq!(2, instructions.len());
+        assert!(
+            instructions
+                .iter()
+                .all(|ix| ix.program_id == solana_compute_budget_interface::ID)
+        );
+        assert_eq!(
+            Some(ComputeBudgetInstruction::SetComputeUnitLimit(1_000_000)),
+            decode_compute_budget(&instructions[0].data)
+        );
+        assert_eq!(
+            Some(ComputeBudgetInstruction::SetComputeUnitPrice(200_000)),
+            decode_compute_budget(&instructions[1].data)
+        );
+
+        let payer = Pubkey::new_unique();
+        let tx = TransactionBuilder::default()
+            .with_memo("fee", &[&payer])
+            .apply_fee(&calc)?;
+        assert_eq!(3, tx.instructions.len());
+        assert_eq!(instructions, tx.instructions[..2]);
+        assert!(tx.apply_fee(&calc).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_compute_budget() {
+        assert_eq!(
+            Some(ComputeBudgetInstruction::RequestHeapFrame(32 * 1024)),
+            decode_compute_budget(&ComputeBudgetInstruction::request_heap_frame(32 * 1024).data)
+        );
+        assert_eq!(
+            Some(ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit(
+                1024
+            )),
+            decode_compute_budget(
+                &ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(1024).data
+            )
+        );
+        assert_eq!(None, decode_compute_budget(&[]));
+        assert_eq!(None, decode_compute_budget(&[2, 1]));
+        assert_eq!(None, decode_compute_budget(&[9, 1, 0, 0, 0]));
+    }
 }
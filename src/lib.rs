@@ -1,10 +1,21 @@
 #![doc = include_str!("../README.md")]
 
+#[cfg(feature = "blocking")]
+mod blocking;
 mod error;
+mod explain;
+mod explorer;
 mod fee;
 mod lookup;
+mod partial;
 mod rpc;
+mod simulate;
+mod size;
+mod time;
+mod token;
 mod transaction;
+#[cfg(feature = "blocking")]
+pub use blocking::{BlockingRpcProvider, BlockingRpcWrapper};
 use {
     borsh::BorshSerialize,
     solana_hash::Hash,
@@ -16,11 +27,26 @@ use {
 };
 pub use {
     error::*,
-    fee::CalcFeeResult,
+    explorer::{Cluster, cluster_from_url},
+    fee::{
+        CalcFeeResult,
+        ComputeBudget,
+        FeeDelta,
+        FeeEstimate,
+        PriorityFeeProvider,
+        SurgeProtection,
+        price_per_cu_to_priority_lamports,
+        priority_lamports_to_price_per_cu,
+    },
     lookup::*,
     moka::{self, future::Cache},
     nitrogen_instruction_builder::*,
+    partial::PartiallySignedTransaction,
     rpc::*,
+    simulate::SimulateConfigBuilder,
+    size::{MAX_TX_ACCOUNTS, PACKET_DATA_SIZE, account_count, transaction_size},
+    solana_transaction_status_client_types::UiTransactionEncoding,
+    token::*,
     transaction::*,
 };
 pub type Result<T> = std::result::Result<T, Error>;
@@ -46,6 +72,10 @@ impl<T: BorshSerialize> InstructionBuilderExt for InstructionBuilder<T> {
 /// use alternative RPC endpoints. All implementations must handle errors by
 /// converting them to the crate's [`Error`] type.
 ///
+/// Account, balance and performance sample reads are optional: their default
+/// bodies fail with [`Error::CustomError`], so implement the ones your
+/// provider serves.
+///
 /// # Examples
 /// ```no_run
 /// # use soly::{TransactionRpcProvider, NativeRpcWrapper};
@@ -63,6 +93,17 @@ pub trait TransactionRpcProvider: Send + Sync {
         &self,
         pubkeys: &[Pubkey],
     ) -> Result<Vec<AddressLookupTableAccount>>;
+    /// [`TransactionRpcProvider::get_lookup_table_accounts`] read at
+    /// `commitment`, e.g. `processed` for tables created moments ago.
+    /// Defaults to the provider's own commitment.
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: solana_commitment_config::CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        let _ = commitment;
+        self.get_lookup_table_accounts(pubkeys).await
+    }
     async fn get_latest_blockhash(&self) -> Result<Hash>;
     async fn simulate_transaction(
         &self,
@@ -74,6 +115,52 @@ pub trait TransactionRpcProvider: Send + Sync {
         tx: &solana_transaction::versioned::VersionedTransaction,
         config: Option<solana_rpc_client_api::config::RpcSendTransactionConfig>,
     ) -> Result<Signature>;
+    /// Balance of `pubkey` in lamports
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        let _ = pubkey;
+        Err(Error::CustomError(
+            "get_balance is not supported by this provider".to_string(),
+        ))
+    }
+    /// Account stored at `pubkey`, `None` if it does not exist
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        let _ = pubkey;
+        Err(Error::CustomError(
+            "get_account is not supported by this provider".to_string(),
+        ))
+    }
+    /// Accounts stored at `pubkeys`, in order, `None` for those that do not
+    /// exist
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        let _ = pubkeys;
+        Err(Error::CustomError(
+            "get_multiple_accounts is not supported by this provider".to_string(),
+        ))
+    }
+    /// Accounts owned by `program` which match all `filters`
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<solana_rpc_client_api::filter::RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        let _ = (program, filters);
+        Err(Error::CustomError(
+            "get_program_accounts is not supported by this provider".to_string(),
+        ))
+    }
+    /// Up to `limit` most recent performance samples, newest first
+    async fn get_recent_performance_samples(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+        let _ = limit;
+        Err(Error::CustomError(
+            "get_recent_performance_samples is not supported by this provider".to_string(),
+        ))
+    }
 }
 
 impl From<Instruction> for TransactionBuilder {
@@ -1,17 +1,24 @@
 use {
-    crate::{Error, Result},
+    crate::{Error, Result, TransactionBuilder, TransactionRpcProvider, transaction_size},
     solana_account::Account,
     solana_account_decoder::parse_address_lookup_table::{
         LookupTableAccountType,
         parse_address_lookup_table,
     },
+    solana_address_lookup_table_interface::state::{AddressLookupTable, LookupTableMeta},
+    solana_commitment_config::CommitmentConfig,
+    solana_hash::Hash,
+    solana_instruction::error::InstructionError,
     solana_message::AddressLookupTableAccount,
     solana_pubkey::Pubkey,
     solana_rpc_client::nonblocking::rpc_client::RpcClient,
-    std::str::FromStr,
+    std::{collections::HashSet, str::FromStr},
     tracing::debug,
 };
 
+/// Instructions to create and manage lookup tables
+pub mod alt;
+
 async fn get_multiple_accts(
     lookup_tables: &[Pubkey],
     rpc: impl AsRef<RpcClient>,
@@ -22,7 +29,7 @@ async fn get_multiple_accts(
         .map_err(|e| Error::SolanaRpcError(format!("failed to get lookup table accounts: {e}")))
 }
 
-fn process_lookup_tables(
+pub(crate) fn process_lookup_tables(
     lookup_tables: &[Pubkey],
     accounts: Vec<Option<Account>>,
 ) -> Result<Vec<AddressLookupTableAccount>> {
@@ -75,6 +82,106 @@ fn process_lookup_tables(
     Ok(lookup_tables_state)
 }
 
+/// Address lookup table together with its on-chain metadata, see
+/// [`fetch_lookup_tables_full`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupTableState {
+    pub key: Pubkey,
+    /// Deactivation and extension slots, and the table authority
+    pub meta: LookupTableMeta,
+    pub addresses: Vec<Pubkey>,
+}
+
+impl LookupTableState {
+    /// Whether the table is not deactivated. A deactivated table can be
+    /// closed once its deactivation slot is no longer recent.
+    pub fn is_active(&self) -> bool {
+        self.meta.deactivation_slot == u64::MAX
+    }
+}
+
+impl From<LookupTableState> for AddressLookupTableAccount {
+    fn from(state: LookupTableState) -> Self {
+        AddressLookupTableAccount {
+            key: state.key,
+            addresses: state.addresses,
+        }
+    }
+}
+
+/// Lookup tables resolved for one transaction, see
+/// [`TransactionBuilder::create_message_with_tables`]. Pass them to sibling
+/// transactions with [`TransactionBuilder::with_resolved_tables`] to skip
+/// fetching them again.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResolvedTables(pub Vec<AddressLookupTableAccount>);
+
+impl From<Vec<AddressLookupTableAccount>> for ResolvedTables {
+    fn from(tables: Vec<AddressLookupTableAccount>) -> Self {
+        Self(tables)
+    }
+}
+
+impl From<ResolvedTables> for Vec<AddressLookupTableAccount> {
+    fn from(tables: ResolvedTables) -> Self {
+        tables.0
+    }
+}
+
+impl std::ops::Deref for ResolvedTables {
+    type Target = [AddressLookupTableAccount];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+fn process_lookup_tables_full(
+    lookup_tables: &[Pubkey],
+    accounts: Vec<Option<Account>>,
+) -> Result<Vec<LookupTableState>> {
+    let mut states = Vec::with_capacity(accounts.len());
+    for (key, maybe_account) in lookup_tables.iter().zip(accounts) {
+        let Some(account) = maybe_account else {
+            tracing::warn!("lookup table account {key} not found");
+            continue;
+        };
+        match AddressLookupTable::deserialize(&account.data) {
+            Ok(table) => states.push(LookupTableState {
+                key: *key,
+                meta: table.meta,
+                addresses: table.addresses.into_owned(),
+            }),
+            Err(InstructionError::UninitializedAccount) => {
+                tracing::warn!("lookup table {key} is uninitialized")
+            }
+            Err(e) => {
+                return Err(Error::CustomError(format!(
+                    "invalid lookup table {key}: {e}"
+                )));
+            }
+        }
+    }
+    Ok(states)
+}
+
+/// Fetches lookup tables including their metadata, such as the
+/// `deactivation_slot` needed to know when a table can be closed.
+///
+/// Missing and uninitialized tables are skipped, like
+/// [`fetch_lookup_tables`] does. Empty tables are kept.
+pub async fn fetch_lookup_tables_full(
+    lookup_tables: &[Pubkey],
+    rpc: impl AsRef<RpcClient>,
+) -> Result<Vec<LookupTableState>> {
+    if lookup_tables.is_empty() {
+        return Ok(Vec::with_capacity(0));
+    }
+    debug!(lookup_tables =? lookup_tables.len(), "fetching full lookup tables");
+    let accounts = get_multiple_accts(lookup_tables, rpc).await?;
+    process_lookup_tables_full(lookup_tables, accounts)
+}
+
 /// Fetches lookup tables from the Solana blockchain.
 pub async fn fetch_lookup_tables(
     lookup_tables: &[Pubkey],
@@ -88,12 +195,162 @@ pub async fn fetch_lookup_tables(
     process_lookup_tables(lookup_tables, accounts)
 }
 
+/// [`fetch_lookup_tables`] at `commitment` instead of the client's
+pub async fn fetch_lookup_tables_with_commitment(
+    lookup_tables: &[Pubkey],
+    rpc: impl AsRef<RpcClient>,
+    commitment: CommitmentConfig,
+) -> Result<Vec<AddressLookupTableAccount>> {
+    if lookup_tables.is_empty() {
+        return Ok(Vec::with_capacity(0));
+    }
+    debug!(lookup_tables =? lookup_tables.len(), commitment =? commitment.commitment, "fetching lookup tables");
+    let accounts = rpc
+        .as_ref()
+        .get_multiple_accounts_with_commitment(lookup_tables, commitment)
+        .await
+        .map_err(|e| Error::SolanaRpcError(format!("failed to get lookup table accounts: {e}")))?
+        .value;
+    process_lookup_tables(lookup_tables, accounts)
+}
+
+impl TransactionBuilder {
+    /// Keys of the referenced lookup tables (both
+    /// [`TransactionBuilder::address_lookup_tables`] and
+    /// [`TransactionBuilder::lookup_tables_keys`]) that can no longer be used:
+    /// deactivated, closed or uninitialized.
+    ///
+    /// An empty result means the transaction may be sent. Otherwise abort, or
+    /// refresh the tables. Accounts are read with
+    /// [`TransactionRpcProvider::get_account`].
+    pub async fn verify_tables_active<T: TransactionRpcProvider>(
+        &self,
+        rpc: &T,
+    ) -> Result<Vec<Pubkey>> {
+        let mut keys: Vec<Pubkey> = self
+            .address_lookup_tables
+            .iter()
+            .flatten()
+            .map(|table| table.key)
+            .chain(self.lookup_tables_keys.iter().flatten().copied())
+            .collect();
+        let mut seen = std::collections::HashSet::with_capacity(keys.len());
+        keys.retain(|key| seen.insert(*key));
+        let accounts =
+            futures::future::try_join_all(keys.iter().map(|key| rpc.get_account(key))).await?;
+        let states = process_lookup_tables_full(&keys, accounts)?;
+        Ok(keys
+            .into_iter()
+            .filter(|key| {
+                !states
+                    .iter()
+                    .any(|state| state.key == *key && state.is_active())
+            })
+            .collect())
+    }
+}
+
+impl TransactionBuilder {
+    /// Accounts of the instructions (hook instructions included) which a
+    /// lookup table can hold: neither signers nor program ids. Deduped, in
+    /// order.
+    pub(crate) fn lookup_candidates(&self, payer: &Pubkey) -> Vec<Pubkey> {
+        let instructions = self.hooked_instructions();
+        let programs: HashSet<Pubkey> = instructions.iter().map(|ix| ix.program_id).collect();
+        let mut seen = HashSet::new();
+        instructions
+            .iter()
+            .flat_map(|ix| &ix.accounts)
+            .filter(|meta| !meta.is_signer && !programs.contains(&meta.pubkey))
+            .map(|meta| meta.pubkey)
+            .filter(|key| key != payer && seen.insert(*key))
+            .collect()
+    }
+
+    /// Keeps the fewest `candidates` which shrink the compiled transaction,
+    /// replacing the configured lookup tables.
+    ///
+    /// Only tables holding a non-signer account of the instructions (hook
+    /// instructions included) are considered, program ids cannot be loaded
+    /// from a table. Tables are added greedily, largest saving first, while
+    /// each one still saves bytes: a table referenced for one or two accounts
+    /// usually costs more than it saves. Without any, the message compiles
+    /// as legacy.
+    pub fn optimize_lookup_tables(
+        mut self,
+        payer: &Pubkey,
+        candidates: &[AddressLookupTableAccount],
+    ) -> Result<Self> {
+        let used: HashSet<Pubkey> = self.lookup_candidates(payer).into_iter().collect();
+        let size = |tables: &[AddressLookupTableAccount]| -> Result<usize> {
+            let tables = (!tables.is_empty()).then_some(tables);
+            transaction_size(&self.compile_with_tables(payer, tables, Hash::default())?)
+        };
+
+        let mut remaining: Vec<&AddressLookupTableAccount> = candidates
+            .iter()
+            .filter(|table| table.addresses.iter().any(|key| used.contains(key)))
+            .collect();
+        let mut chosen: Vec<AddressLookupTableAccount> = Vec::new();
+        let mut best = size(&chosen)?;
+        while !remaining.is_empty() {
+            let mut sizes = Vec::with_capacity(remaining.len());
+            for (i, table) in remaining.iter().enumerate() {
+                let mut tables = chosen.clone();
+                tables.push((*table).clone());
+                sizes.push((size(&tables)?, i));
+            }
+            let Some((smallest, i)) = sizes.into_iter().min() else {
+                break;
+            };
+            if smallest >= best {
+                break;
+            }
+            best = smallest;
+            chosen.push(remaining.remove(i).clone());
+        }
+        debug!(
+            candidates = candidates.len(),
+            chosen = chosen.len(),
+            size = best,
+            "optimized lookup tables"
+        );
+        self.lookup_tables_keys = None;
+        self.address_lookup_tables = (!chosen.is_empty()).then_some(chosen);
+        Ok(self)
+    }
+}
+
+/// Derives the program derived address for each `(seeds, program_id)` pair.
+fn derive_lookup_table_pdas(seeds_and_programs: &[(Vec<Vec<u8>>, Pubkey)]) -> Vec<Pubkey> {
+    seeds_and_programs
+        .iter()
+        .map(|(seeds, program_id)| {
+            let seeds: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+            Pubkey::find_program_address(&seeds, program_id).0
+        })
+        .collect()
+}
+
+/// Fetches lookup tables published at program derived addresses.
+///
+/// Each entry holds the seeds and the program id used to derive the table
+/// address, see [`Pubkey::find_program_address`].
+pub async fn fetch_lookup_tables_from_pdas(
+    seeds_and_programs: &[(Vec<Vec<u8>>, Pubkey)],
+    rpc: impl AsRef<RpcClient>,
+) -> Result<Vec<AddressLookupTableAccount>> {
+    let lookup_tables = derive_lookup_table_pdas(seeds_and_programs);
+    debug!(lookup_tables =? lookup_tables, "derived lookup table addresses");
+    fetch_lookup_tables(&lookup_tables, rpc).await
+}
+
 #[cfg(test)]
 mod tests {
     use {
         super::*,
+        crate::rpc::mock::MockRpc,
         base64::prelude::*,
-        solana_account::{AccountSharedData, WritableAccount},
         solana_address_lookup_table_interface::{
             program::ID as LOOKUP_TABLE_PROGRAM_ID,
             state::AddressLookupTable,
@@ -116,14 +373,13 @@ mod tests {
     fn convert(data: &str) -> anyhow::Result<Account> {
         let data: Vec<u8> = BASE64_STANDARD.decode(data)?;
         let address_lookup = AddressLookupTable::deserialize(&data)?;
-        let account_shared_data = AccountSharedData::create(
-            1,
-            address_lookup.serialize_for_tests()?,
-            LOOKUP_TABLE_PROGRAM_ID,
-            false,
-            0,
-        );
-        Ok(account_shared_data.into())
+        Ok(Account {
+            lamports: 1,
+            data: address_lookup.serialize_for_tests()?,
+            owner: LOOKUP_TABLE_PROGRAM_ID,
+            executable: false,
+            rent_epoch: 0,
+        })
     }
     #[test]
     fn test_empty_table() -> anyhow::Result<()> {
@@ -151,6 +407,98 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_derive_lookup_table_pdas() {
+        let pdas = derive_lookup_table_pdas(&[
+            (vec![b"soly".to_vec()], LOOKUP_TABLE_PROGRAM_ID),
+            (vec![b"soly".to_vec(), vec![1]], LOOKUP_TABLE_PROGRAM_ID),
+        ]);
+        assert_eq!(2, pdas.len());
+        assert_eq!(
+            solana_pubkey::pubkey!("DjWS7vehV7cNaguvxmCtAeJkmz2jBvyv2SxPwrxX3qyQ"),
+            pdas[0]
+        );
+        assert_ne!(pdas[0], pdas[1]);
+        assert!(derive_lookup_table_pdas(&[]).is_empty());
+    }
+
+    fn table_account(deactivation_slot: u64) -> anyhow::Result<Account> {
+        let table = AddressLookupTable {
+            meta: LookupTableMeta {
+                deactivation_slot,
+                ..LookupTableMeta::default()
+            },
+            addresses: vec![Pubkey::new_unique()].into(),
+        };
+        Ok(Account {
+            lamports: 1,
+            data: table.serialize_for_tests()?,
+            owner: LOOKUP_TABLE_PROGRAM_ID,
+            executable: false,
+            rent_epoch: 0,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_verify_tables_active() -> anyhow::Result<()> {
+        let active = Pubkey::new_unique();
+        let deactivated = Pubkey::new_unique();
+        let closed = Pubkey::new_unique();
+        let mut rpc = MockRpc::default();
+        rpc.accounts.insert(active, table_account(u64::MAX)?);
+        rpc.accounts.insert(deactivated, table_account(100)?);
+
+        let tx = TransactionBuilder::default()
+            .with_address_tables([AddressLookupTableAccount {
+                key: active,
+                addresses: vec![],
+            }])
+            .with_lookup_keys([deactivated, active]);
+        assert_eq!(vec![deactivated], tx.verify_tables_active(&rpc).await?);
+
+        let tx = tx.with_lookup_keys([closed]);
+        assert_eq!(
+            vec![deactivated, closed],
+            tx.verify_tables_active(&rpc).await?
+        );
+
+        let tx = TransactionBuilder::default().with_lookup_keys([active]);
+        assert!(tx.verify_tables_active(&rpc).await?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_full_metadata() -> anyhow::Result<()> {
+        let accounts = vec![
+            Some(convert(INITIALIZED_DATA)?),
+            Some(convert(NOT_INITIALIZED_DATA)?),
+            None,
+        ];
+        let keys = [INITIALIZED, NOT_INITIALIZED, Pubkey::new_unique()];
+        let result = process_lookup_tables_full(&keys, accounts)?;
+        // the missing account is skipped, the empty table is kept
+        assert_eq!(2, result.len());
+        assert_eq!(NOT_INITIALIZED, result[1].key);
+        assert!(result[1].addresses.is_empty());
+        let state = &result[0];
+        assert_eq!(INITIALIZED, state.key);
+        assert_eq!(u64::MAX, state.meta.deactivation_slot);
+        assert!(state.is_active());
+        assert_eq!(415_261_125, state.meta.last_extended_slot);
+        assert_eq!(0, state.meta.last_extended_slot_start_index);
+        assert_eq!(
+            Some(solana_pubkey::pubkey!(
+                "215r9xfTFVYcE9g3fAUGowauM84egyUvFCbSo3LKNaep"
+            )),
+            state.meta.authority
+        );
+        assert_eq!(EXPECTED_TABLE.to_vec(), state.addresses);
+
+        let account: AddressLookupTableAccount = state.clone().into();
+        assert_eq!(EXPECTED_TABLE.to_vec(), account.addresses);
+        Ok(())
+    }
+
     #[test]
     fn test_mixed() -> anyhow::Result<()> {
         let accounts: Vec<Option<Account>> = vec![
@@ -163,4 +511,42 @@ mod tests {
         assert_eq!(result[0].addresses, EXPECTED_TABLE);
         Ok(())
     }
+
+    #[test]
+    fn test_optimize_lookup_tables() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let destinations: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let lone = Pubkey::new_unique();
+        let mut instructions: Vec<_> = destinations
+            .iter()
+            .map(|to| solana_system_interface::instruction::transfer(&payer, to, 1))
+            .collect();
+        instructions.push(solana_system_interface::instruction::transfer(
+            &payer, &lone, 1,
+        ));
+        let table = |addresses: Vec<Pubkey>| AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses,
+        };
+        let useful = table(destinations.clone());
+        // costs more than the single address it saves
+        let single = table(vec![lone, Pubkey::new_unique()]);
+        let unrelated = table(vec![Pubkey::new_unique()]);
+        let with_payer = table(vec![payer, solana_system_interface::program::ID]);
+
+        let tx = TransactionBuilder::from(instructions)
+            .with_lookup_keys([Pubkey::new_unique()])
+            .optimize_lookup_tables(&payer, &[
+                unrelated,
+                single,
+                useful.clone(),
+                with_payer.clone(),
+            ])?;
+        assert_eq!(Some(vec![useful]), tx.address_lookup_tables);
+        assert!(tx.lookup_tables_keys.is_none());
+
+        let tx = tx.optimize_lookup_tables(&payer, &[with_payer])?;
+        assert!(tx.address_lookup_tables.is_none());
+        Ok(())
+    }
 }
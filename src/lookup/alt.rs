@@ -0,0 +1,454 @@
+use {
+    super::process_lookup_tables_full,
+    crate::{Error, Result, SendOutcome, TransactionBuilder, TransactionRpcProvider},
+    solana_address_lookup_table_interface::instruction,
+    solana_hash::Hash,
+    solana_message::AddressLookupTableAccount,
+    solana_pubkey::Pubkey,
+    solana_signer::{Signer, signers::Signers},
+    std::time::{Duration, Instant},
+    tracing::debug,
+};
+
+/// Most addresses added by one extend instruction, a full transaction when
+/// the payer is the authority
+pub const MAX_EXTEND_ADDRESSES: usize = 30;
+/// Approximate slot duration, tables serve new addresses from the next slot
+const SLOT_DURATION: Duration = Duration::from_millis(400);
+const SLOT_HASHES_SYSVAR: Pubkey =
+    solana_pubkey::pubkey!("SysvarS1otHashes111111111111111111111111111");
+/// Slots kept by the `SlotHashes` sysvar, the deactivation cooldown
+const SLOT_HASHES_MAX_ENTRIES: usize = 512;
+/// How long [`TransactionBuilder::send_with_auto_alt`] waits for its table
+const AUTO_ALT_WARMUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Derived address of the table and a [`TransactionBuilder`] creating it.
+///
+/// `recent_slot` seeds the address and must be one of the slots still in
+/// the `SlotHashes` sysvar (the last 512), e.g. a recently confirmed slot.
+/// `payer` funds the rent, `authority` does not need to sign.
+pub fn create_lookup_table(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    recent_slot: u64,
+) -> (Pubkey, TransactionBuilder) {
+    let (ix, address) = instruction::create_lookup_table(*authority, *payer, recent_slot);
+    (address, TransactionBuilder::from(vec![ix]))
+}
+
+/// [`TransactionBuilder`]s adding `addresses` to `table`, one extend
+/// instruction each, to be sent in order.
+///
+/// Each transaction carries as many addresses as fit the packet, at most
+/// [`MAX_EXTEND_ADDRESSES`]. `payer` funds the rent of the larger table, and
+/// `authority` must sign every transaction.
+pub fn extend_lookup_table(
+    table: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    addresses: &[Pubkey],
+) -> Vec<TransactionBuilder> {
+    let extend = |chunk: &[Pubkey]| {
+        TransactionBuilder::from(vec![instruction::extend_lookup_table(
+            *table,
+            *authority,
+            Some(*payer),
+            chunk.to_vec(),
+        )])
+    };
+    let per_tx = (1..=MAX_EXTEND_ADDRESSES)
+        .rev()
+        .find(|n| {
+            let sample = vec![Pubkey::default(); *n];
+            extend(&sample)
+                .compile_offline(payer, Hash::default(), &[])
+                .is_ok()
+        })
+        .unwrap_or(1);
+    addresses.chunks(per_tx).map(extend).collect()
+}
+
+/// Waits until `table` holds all of `addresses`, then one more slot for
+/// them to become usable in transactions.
+///
+/// Fails when the addresses are still missing after `timeout`.
+pub async fn wait_for_warmup<T: TransactionRpcProvider>(
+    rpc: &T,
+    table: &Pubkey,
+    addresses: &[Pubkey],
+    timeout: Duration,
+) -> Result<()> {
+    let start = Instant::now();
+    loop {
+        let tables = rpc.get_lookup_table_accounts(&[*table]).await?;
+        let extended = tables.iter().find(|t| t.key == *table).is_some_and(|t| {
+            addresses
+                .iter()
+                .all(|address| t.addresses.contains(address))
+        });
+        if extended {
+            crate::time::sleep(SLOT_DURATION).await;
+            return Ok(());
+        }
+        if start.elapsed() >= timeout {
+            return Err(Error::CustomError(format!(
+                "lookup table {table} is missing addresses after {timeout:?}"
+            )));
+        }
+        crate::time::sleep(SLOT_DURATION).await;
+    }
+}
+
+/// [`TransactionBuilder`] deactivating `table`, the first step to reclaim
+/// its rent, see [`close_when_ready`]. A deactivated table cannot be extended
+/// or used by new transactions.
+pub fn deactivate_lookup_table(table: &Pubkey, authority: &Pubkey) -> TransactionBuilder {
+    TransactionBuilder::from(vec![instruction::deactivate_lookup_table(
+        *table, *authority,
+    )])
+}
+
+/// [`TransactionBuilder`] closing the deactivated `table` and sending its
+/// rent to `recipient`. The program rejects it until the deactivation
+/// cooldown ends, see [`close_when_ready`].
+pub fn close_lookup_table(
+    table: &Pubkey,
+    authority: &Pubkey,
+    recipient: &Pubkey,
+) -> TransactionBuilder {
+    TransactionBuilder::from(vec![instruction::close_lookup_table(
+        *table, *authority, *recipient,
+    )])
+}
+
+/// [`close_lookup_table`] once the deactivation slot of `table` left the
+/// `SlotHashes` sysvar, `None` while it is still cooling down (about 512
+/// slots).
+///
+/// Both accounts are read with [`TransactionRpcProvider::get_account`].
+/// Fails when the table is missing or not deactivated.
+pub async fn close_when_ready<T: TransactionRpcProvider>(
+    rpc: &T,
+    table: &Pubkey,
+    authority: &Pubkey,
+    recipient: &Pubkey,
+) -> Result<Option<TransactionBuilder>> {
+    let (account, slot_hashes) =
+        futures::try_join!(rpc.get_account(table), rpc.get_account(&SLOT_HASHES_SYSVAR))?;
+    let state = process_lookup_tables_full(&[*table], vec![account])?
+        .pop()
+        .ok_or(Error::AccountNotFound(*table))?;
+    if state.is_active() {
+        return Err(Error::CustomError(format!(
+            "lookup table {table} is not deactivated"
+        )));
+    }
+    let slot_hashes = slot_hashes.ok_or(Error::AccountNotFound(SLOT_HASHES_SYSVAR))?;
+    let recent = recent_slots(&slot_hashes.data)?;
+    let deactivation_slot = state.meta.deactivation_slot;
+    let newest = recent.first().copied().unwrap_or_default();
+    if deactivation_slot >= newest {
+        debug!(%table, deactivation_slot, "lookup table deactivated in the current slot");
+        return Ok(None);
+    }
+    if let Some(position) = recent.iter().position(|slot| *slot == deactivation_slot) {
+        debug!(%table, remaining = SLOT_HASHES_MAX_ENTRIES - position, "lookup table cooling down");
+        return Ok(None);
+    }
+    Ok(Some(close_lookup_table(table, authority, recipient)))
+}
+
+/// The newest slot of the `SlotHashes` sysvar, a valid `recent_slot` for
+/// [`create_lookup_table`]
+pub async fn recent_slot<T: TransactionRpcProvider>(rpc: &T) -> Result<u64> {
+    let slot_hashes = rpc
+        .get_account(&SLOT_HASHES_SYSVAR)
+        .await?
+        .ok_or(Error::AccountNotFound(SLOT_HASHES_SYSVAR))?;
+    recent_slots(&slot_hashes.data)?
+        .first()
+        .copied()
+        .ok_or_else(|| Error::CustomError("empty SlotHashes sysvar".to_string()))
+}
+
+impl TransactionBuilder {
+    /// [`TransactionBuilder::send`], moving the accounts into a new lookup
+    /// table first when the transaction exceeds
+    /// [`PACKET_DATA_SIZE`](crate::PACKET_DATA_SIZE).
+    ///
+    /// The table holds every non-signer account of the instructions except
+    /// the program ids. `authority` creates, extends and pays for it, then
+    /// the transaction is sent once the table is warm. The table is returned
+    /// for reuse or cleanup with [`deactivate_lookup_table`] and
+    /// [`close_when_ready`], `None` when the transaction fit without one.
+    /// Configured lookup tables are replaced by the new one.
+    pub async fn send_with_auto_alt<S, T, A>(
+        &self,
+        payer: &Pubkey,
+        signers: &S,
+        rpc: &T,
+        authority: &A,
+    ) -> Result<(SendOutcome, Option<Pubkey>)>
+    where
+        S: Signers + ?Sized,
+        T: TransactionRpcProvider,
+        A: Signer,
+    {
+        match self.create_message(payer, rpc).await {
+            Err(Error::TransactionTooLarge { size, limit }) => {
+                debug!(
+                    size,
+                    limit, "transaction too large, creating a lookup table"
+                );
+            }
+            _ => return Ok((self.send(rpc, payer, signers).await?, None)),
+        }
+
+        let addresses = self.lookup_candidates(payer);
+        let owner = authority.pubkey();
+        let (table, create) = create_lookup_table(&owner, &owner, recent_slot(rpc).await?);
+        create.send(rpc, &owner, &[authority]).await?;
+        for extend in extend_lookup_table(&table, &owner, &owner, &addresses) {
+            extend.send(rpc, &owner, &[authority]).await?;
+        }
+        wait_for_warmup(rpc, &table, &addresses, AUTO_ALT_WARMUP_TIMEOUT).await?;
+
+        let mut tx = self.clone();
+        tx.lookup_tables_keys = None;
+        tx.address_lookup_tables = Some(vec![AddressLookupTableAccount {
+            key: table,
+            addresses,
+        }]);
+        Ok((tx.send(rpc, payer, signers).await?, Some(table)))
+    }
+}
+
+/// Slots of the `SlotHashes` sysvar, newest first: a `u64` count followed by
+/// `(slot, hash)` entries.
+fn recent_slots(data: &[u8]) -> Result<Vec<u64>> {
+    let invalid = || Error::CustomError("invalid SlotHashes sysvar".to_string());
+    let (count, entries) = data.split_first_chunk::<8>().ok_or_else(invalid)?;
+    let count = usize::try_from(u64::from_le_bytes(*count))?;
+    let entries = count
+        .checked_mul(40)
+        .and_then(|len| entries.get(..len))
+        .ok_or_else(invalid)?;
+    Ok(entries
+        .chunks_exact(40)
+        .map(|entry| u64::from_le_bytes(entry[..8].try_into().expect("8 bytes")))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::rpc::mock::MockRpc,
+        solana_account::Account,
+        solana_address_lookup_table_interface::state::{AddressLookupTable, LookupTableMeta},
+        solana_keypair::Keypair,
+    };
+
+    #[test]
+    fn test_create_lookup_table() {
+        let authority = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let (address, tx) = create_lookup_table(&authority, &payer, 42);
+        assert_eq!(
+            instruction::derive_lookup_table_address(&authority, 42).0,
+            address
+        );
+        assert_eq!(1, tx.instructions.len());
+        let ix = &tx.instructions[0];
+        assert_eq!(
+            solana_address_lookup_table_interface::program::ID,
+            ix.program_id
+        );
+        assert_eq!(address, ix.accounts[0].pubkey);
+        assert!(!ix.accounts[1].is_signer);
+        assert_eq!(payer, ix.accounts[2].pubkey);
+        assert!(ix.accounts[2].is_signer);
+    }
+
+    #[test]
+    fn test_extend_lookup_table() -> anyhow::Result<()> {
+        let table = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let addresses: Vec<Pubkey> = (0..65).map(|_| Pubkey::new_unique()).collect();
+
+        let chunks = extend_lookup_table(&table, &payer, &payer, &addresses);
+        assert_eq!(3, chunks.len());
+        assert_eq!(
+            MAX_EXTEND_ADDRESSES * 32,
+            chunks[0].instructions[0].data.len() - 12
+        );
+
+        // a separate authority signature leaves room for fewer addresses
+        let authority = Pubkey::new_unique();
+        let chunks = extend_lookup_table(&table, &authority, &payer, &addresses);
+        assert!(chunks[0].instructions[0].data.len() - 12 < MAX_EXTEND_ADDRESSES * 32);
+        let mut extended = Vec::new();
+        for chunk in &chunks {
+            chunk.compile_offline(&payer, Hash::default(), &[])?;
+            let ix = &chunk.instructions[0];
+            assert_eq!(authority, ix.accounts[1].pubkey);
+            assert!(ix.accounts[1].is_signer);
+            extended.extend(
+                ix.data[12..]
+                    .chunks(32)
+                    .map(|key| Pubkey::try_from(key).unwrap()),
+            );
+        }
+        assert_eq!(addresses, extended);
+        assert!(extend_lookup_table(&table, &payer, &payer, &[]).is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_warmup() -> anyhow::Result<()> {
+        let table = Pubkey::new_unique();
+        let addresses = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let rpc = MockRpc {
+            lookup_tables: vec![AddressLookupTableAccount {
+                key: table,
+                addresses: addresses.clone(),
+            }],
+            ..Default::default()
+        };
+        let start = Instant::now();
+        wait_for_warmup(&rpc, &table, &addresses, Duration::from_secs(1)).await?;
+        assert!(start.elapsed() >= SLOT_DURATION);
+
+        let missing = [Pubkey::new_unique()];
+        assert!(
+            wait_for_warmup(&rpc, &table, &missing, Duration::ZERO)
+                .await
+                .is_err()
+        );
+        Ok(())
+    }
+
+    fn table_account(deactivation_slot: u64) -> anyhow::Result<Account> {
+        let table = AddressLookupTable {
+            meta: LookupTableMeta {
+                deactivation_slot,
+                ..LookupTableMeta::default()
+            },
+            addresses: vec![Pubkey::new_unique()].into(),
+        };
+        Ok(Account {
+            lamports: 1,
+            data: table.serialize_for_tests()?,
+            owner: solana_address_lookup_table_interface::program::ID,
+            executable: false,
+            rent_epoch: 0,
+        })
+    }
+
+    fn slot_hashes_account(slots: impl DoubleEndedIterator<Item = u64>) -> Account {
+        let slots: Vec<u64> = slots.rev().collect();
+        let mut data = (slots.len() as u64).to_le_bytes().to_vec();
+        for slot in slots {
+            data.extend(slot.to_le_bytes());
+            data.extend(Hash::new_unique().to_bytes());
+        }
+        Account {
+            data,
+            ..Account::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_close_when_ready() -> anyhow::Result<()> {
+        let table = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let mut rpc = MockRpc::default();
+        rpc.accounts.insert(table, table_account(100)?);
+
+        rpc.accounts
+            .insert(SLOT_HASHES_SYSVAR, slot_hashes_account(90..300));
+        assert!(
+            close_when_ready(&rpc, &table, &authority, &authority)
+                .await?
+                .is_none()
+        );
+
+        rpc.accounts
+            .insert(SLOT_HASHES_SYSVAR, slot_hashes_account(101..613));
+        let close = close_when_ready(&rpc, &table, &authority, &authority)
+            .await?
+            .expect("cooldown over");
+        assert!(close.instructions_eq(&close_lookup_table(&table, &authority, &authority)));
+
+        rpc.accounts.insert(table, table_account(u64::MAX)?);
+        assert!(
+            close_when_ready(&rpc, &table, &authority, &authority)
+                .await
+                .is_err()
+        );
+        let missing = Pubkey::new_unique();
+        assert!(matches!(
+            close_when_ready(&rpc, &missing, &authority, &authority).await,
+            Err(Error::AccountNotFound(key)) if key == missing
+        ));
+
+        let deactivate = deactivate_lookup_table(&table, &authority);
+        assert!(deactivate.instructions[0].accounts[1].is_signer);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_with_auto_alt() -> anyhow::Result<()> {
+        let payer = Keypair::new();
+        let authority = Keypair::new();
+        let destinations: Vec<Pubkey> = (0..40).map(|_| Pubkey::new_unique()).collect();
+        let tx = TransactionBuilder::from(
+            destinations
+                .iter()
+                .map(|to| solana_system_interface::instruction::transfer(&payer.pubkey(), to, 1))
+                .collect::<Vec<_>>(),
+        );
+        let (table, _) = instruction::derive_lookup_table_address(&authority.pubkey(), 299);
+        let mut rpc = MockRpc {
+            lookup_tables: vec![AddressLookupTableAccount {
+                key: table,
+                addresses: destinations.clone(),
+            }],
+            ..Default::default()
+        };
+        rpc.accounts
+            .insert(SLOT_HASHES_SYSVAR, slot_hashes_account(0..300));
+
+        let (_, created) = tx
+            .send_with_auto_alt(&payer.pubkey(), &[&payer], &rpc, &authority)
+            .await?;
+        assert_eq!(Some(table), created);
+        let sent = rpc.sent();
+        // create, two extends, then the transaction itself
+        assert_eq!(4, sent.len());
+        assert_eq!(
+            authority.pubkey(),
+            sent[0].0.message.static_account_keys()[0]
+        );
+        let lookups = sent[3]
+            .0
+            .message
+            .address_table_lookups()
+            .unwrap_or_default();
+        assert_eq!(table, lookups[0].account_key);
+        assert_eq!(destinations.len(), lookups[0].writable_indexes.len());
+
+        // small enough, sent as is
+        let small = TransactionBuilder::from(vec![solana_system_interface::instruction::transfer(
+            &payer.pubkey(),
+            &destinations[0],
+            1,
+        )]);
+        let (_, created) = small
+            .send_with_auto_alt(&payer.pubkey(), &[&payer], &rpc, &authority)
+            .await?;
+        assert!(created.is_none());
+        assert_eq!(5, rpc.sent().len());
+        Ok(())
+    }
+}
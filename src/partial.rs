@@ -0,0 +1,178 @@
+use {
+    super::{Error, Result, TransactionBuilder},
+    crate::TransactionRpcProvider,
+    solana_message::VersionedMessage,
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    solana_signer::{SignerError, signers::Signers},
+    solana_transaction::versioned::VersionedTransaction,
+};
+
+/// A compiled transaction collecting the signatures of several parties, see
+/// [`TransactionBuilder::sign_partial`].
+///
+/// Missing signatures are [`Signature::default`]. Signatures are not verified
+/// here, the RPC rejects a transaction with a wrong one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartiallySignedTransaction {
+    tx: VersionedTransaction,
+}
+
+impl PartiallySignedTransaction {
+    /// Wraps `message` without any signature
+    pub fn new(message: VersionedMessage) -> Self {
+        let num_sigs = usize::from(message.header().num_required_signatures);
+        Self {
+            tx: VersionedTransaction {
+                signatures: vec![Signature::default(); num_sigs],
+                message,
+            },
+        }
+    }
+
+    pub fn message(&self) -> &VersionedMessage {
+        &self.tx.message
+    }
+
+    /// The accounts which must sign, in signature order
+    pub fn required_signers(&self) -> &[Pubkey] {
+        let num_sigs = usize::from(self.tx.message.header().num_required_signatures);
+        let keys = self.tx.message.static_account_keys();
+        &keys[..num_sigs.min(keys.len())]
+    }
+
+    /// The required signers without a signature yet
+    pub fn missing_signers(&self) -> Vec<Pubkey> {
+        self.required_signers()
+            .iter()
+            .zip(&self.tx.signatures)
+            .filter(|(_, signature)| **signature == Signature::default())
+            .map(|(pubkey, _)| *pubkey)
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.tx
+            .signatures
+            .iter()
+            .all(|signature| *signature != Signature::default())
+    }
+
+    /// Signs with `signers`, each of which must be a required signer.
+    /// Signatures already present are replaced.
+    pub fn sign<S: Signers + ?Sized>(&mut self, signers: &S) -> Result<()> {
+        let pubkeys = signers.try_pubkeys()?;
+        let signatures = signers.try_sign_message(&self.tx.message.serialize())?;
+        for (pubkey, signature) in pubkeys.iter().zip(signatures) {
+            self.add_signature(pubkey, signature)?;
+        }
+        Ok(())
+    }
+
+    /// Adds the signature of `pubkey`, e.g. received from another party
+    pub fn add_signature(&mut self, pubkey: &Pubkey, signature: Signature) -> Result<()> {
+        let index = self
+            .required_signers()
+            .iter()
+            .position(|signer| signer == pubkey)
+            .ok_or(SignerError::KeypairPubkeyMismatch)?;
+        self.tx.signatures[index] = signature;
+        Ok(())
+    }
+
+    /// Copies the signatures `other` collected for the same message.
+    ///
+    /// Fails when the messages differ, e.g. when the other party compiled
+    /// the builder with another blockhash.
+    pub fn merge(&mut self, other: &PartiallySignedTransaction) -> Result<()> {
+        if self.tx.message != other.tx.message {
+            return Err(Error::CustomError(
+                "cannot merge signatures of a different message".to_string(),
+            ));
+        }
+        for (signature, theirs) in self.tx.signatures.iter_mut().zip(&other.tx.signatures) {
+            if *theirs != Signature::default() {
+                *signature = *theirs;
+            }
+        }
+        Ok(())
+    }
+
+    /// The signed transaction, failing with [`SignerError::NotEnoughSigners`]
+    /// while a signature is missing.
+    pub fn finalize(self) -> Result<VersionedTransaction> {
+        if !self.is_complete() {
+            return Err(SignerError::NotEnoughSigners.into());
+        }
+        Ok(self.tx)
+    }
+}
+
+impl From<VersionedTransaction> for PartiallySignedTransaction {
+    fn from(mut tx: VersionedTransaction) -> Self {
+        let num_sigs = usize::from(tx.message.header().num_required_signatures);
+        tx.signatures.resize(num_sigs, Signature::default());
+        Self { tx }
+    }
+}
+
+impl TransactionBuilder {
+    /// Compiles the message and signs it with `signers`, which may be only
+    /// some of the required signers.
+    ///
+    /// Share the result (or its message) with the other parties, then
+    /// [`PartiallySignedTransaction::merge`] their signatures and
+    /// [`PartiallySignedTransaction::finalize`] it. The blockhash expires
+    /// meanwhile, use [`TransactionBuilder::with_durable_nonce`] when
+    /// collecting takes longer.
+    pub async fn sign_partial<S: Signers + ?Sized, T: TransactionRpcProvider>(
+        &self,
+        payer: &Pubkey,
+        signers: &S,
+        rpc: &T,
+    ) -> Result<PartiallySignedTransaction> {
+        let mut partial = PartiallySignedTransaction::new(self.create_message(payer, rpc).await?);
+        partial.sign(signers)?;
+        Ok(partial)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::rpc::mock::MockRpc, solana_keypair::Keypair, solana_signer::Signer};
+
+    #[tokio::test]
+    async fn test_sign_partial() -> anyhow::Result<()> {
+        let rpc = MockRpc::default();
+        let payer = Keypair::new();
+        let cosigner = Keypair::new();
+        let tx = TransactionBuilder::default()
+            .with_memo("multisig", &[&payer.pubkey(), &cosigner.pubkey()]);
+
+        let mut partial = tx.sign_partial(&payer.pubkey(), &[&payer], &rpc).await?;
+        assert_eq!(
+            &[payer.pubkey(), cosigner.pubkey()],
+            partial.required_signers()
+        );
+        assert_eq!(vec![cosigner.pubkey()], partial.missing_signers());
+        assert!(partial.clone().finalize().is_err());
+
+        let mut theirs = PartiallySignedTransaction::new(partial.message().clone());
+        theirs.sign(&[&cosigner])?;
+        partial.merge(&theirs)?;
+        assert!(partial.is_complete());
+
+        let expected =
+            VersionedTransaction::try_new(partial.message().clone(), &[&payer, &cosigner])?;
+        assert_eq!(expected, partial.clone().finalize()?);
+
+        let stranger = Keypair::new();
+        assert!(partial.sign(&[&stranger]).is_err());
+        let other = TransactionBuilder::default()
+            .with_memo("other", &[&payer.pubkey(), &cosigner.pubkey()])
+            .sign_partial(&payer.pubkey(), &[&payer], &rpc)
+            .await?;
+        assert!(partial.merge(&other).is_err());
+        Ok(())
+    }
+}
@@ -1,8 +1,23 @@
+mod audit;
 mod blockhash;
+mod boxed;
+mod broadcast;
+#[cfg(feature = "testing")]
+mod chaos;
 mod counter;
+mod fee_cache;
+mod file_lookup;
 mod lookup;
+#[cfg(test)]
+pub(crate) mod mock;
 mod native;
+mod rate_limit;
+mod retry;
+mod round_robin;
 mod simple;
+mod simulation_cache;
+mod single_flight;
+mod slow;
 mod trace;
 use {
     crate::TransactionRpcProvider,
@@ -12,11 +27,18 @@ use {
     solana_message::AddressLookupTableAccount,
     solana_pubkey::Pubkey,
     solana_rpc_client::nonblocking::rpc_client::RpcClient,
+    solana_rpc_client_api::response::{RpcPrioritizationFee, RpcSimulateTransactionResult},
+    solana_transaction_status_client_types::UiTransactionEncoding,
     std::{
         fmt::{Debug, Display},
-        sync::Arc,
+        sync::{
+            Arc,
+            atomic::{AtomicU64, AtomicUsize, Ordering},
+        },
+        time::Duration,
     },
 };
+pub use {native::get_transaction_error, retry::is_transient};
 
 /// Combined cache provider with lookup table and blockhash caching.
 ///
@@ -53,7 +75,7 @@ use {
 ///
 /// This implementation is intended for demonstration and testing purposes only.
 /// It lacks production-ready features such as:
-/// - Request retries on failures
+/// - Request retries on failures, wrap `inner` in a [`RetryRpcProvider`]
 /// - Rate limiting and throttling
 /// - Circuit breaker patterns
 /// - Comprehensive error handling
@@ -121,19 +143,395 @@ impl<T: AsRef<RpcClient> + Clone, L: TransactionRpcProvider, B: TransactionRpcPr
 
 /// Provider with lookup table caching.
 ///
-/// This uses [`moka::future::Cache`] for efficient caching of lookup tables.
-/// See their documentation for more details.
+/// By default this uses [`moka::future::Cache`] for efficient caching of
+/// lookup tables, see their documentation for more details. Any
+/// [`LookupCacheStore`] can replace it.
 #[derive(bon::Builder)]
-pub struct LookupTableCacheProvider<T: TransactionRpcProvider> {
+pub struct LookupTableCacheProvider<
+    T: TransactionRpcProvider,
+    L: LookupCacheStore<AddressLookupTableAccount> = Cache<Pubkey, AddressLookupTableAccount>,
+    N: LookupCacheStore<std::time::Instant> = Cache<Pubkey, std::time::Instant>,
+> {
+    inner: T,
+    lookup_cache: L,
+    /// Lookup tables not found, with the time of the miss
+    negative_cache: N,
+    /// Included in the `lookup-resolver` span, to tell several caches apart
+    #[builder(into)]
+    name: Option<String>,
+    /// Commitment of the lookup table fetches on cache misses, instead of the
+    /// inner provider's. Use `processed` to cache tables created moments ago,
+    /// which are not yet visible at `finalized`.
+    commitment: Option<solana_commitment_config::CommitmentConfig>,
+    #[builder(skip)]
+    stats: Arc<CacheCounters>,
+    #[builder(skip)]
+    in_flight: lookup::InFlight,
+}
+
+/// Records every transaction in an append-only audit log before sending it,
+/// see [`AuditSink`].
+///
+/// The record is written before the transaction is broadcast, so sends that
+/// time out or fail are logged too. A failing write aborts the send with its
+/// error: nothing is sent without a record. All other methods delegate to the
+/// inner provider.
+#[derive(Clone)]
+pub struct AuditSendProvider<T: TransactionRpcProvider> {
+    inner: T,
+    sink: AuditSink,
+}
+
+/// Called with every [`AuditRecord`] by [`AuditSink::Callback`]
+pub type AuditCallback = Arc<dyn Fn(&AuditRecord) -> crate::Result<()> + Send + Sync>;
+
+/// Destination of the [`AuditRecord`]s of an [`AuditSendProvider`]
+#[derive(Clone)]
+pub enum AuditSink {
+    /// Appends one JSON object per line to the file, creating it if needed
+    File(std::path::PathBuf),
+    /// Called with every record, an error aborts the send
+    Callback(AuditCallback),
+}
+
+/// A transaction submitted through an [`AuditSendProvider`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditRecord {
+    pub signature: solana_signature::Signature,
+    pub timestamp: std::time::SystemTime,
+    /// Base64 encoded bincode serialization of the signed transaction
+    pub transaction: String,
+}
+
+/// Serves lookup tables from a file instead of the RPC, for offline (e.g.
+/// air-gapped) transaction compilation.
+///
+/// Unknown keys resolve to no table. All other methods delegate to the inner
+/// provider.
+#[derive(Clone)]
+pub struct FileLookupProvider<T: TransactionRpcProvider> {
     inner: T,
-    lookup_cache: Cache<Pubkey, AddressLookupTableAccount>,
-    negative_cache: Cache<Pubkey, ()>,
+    tables: Arc<std::collections::HashMap<Pubkey, AddressLookupTableAccount>>,
+}
+
+/// Storage behind [`LookupTableCacheProvider`], implemented for
+/// [`moka::future::Cache`].
+///
+/// Implement it to back the provider with another cache, such as an LRU or a
+/// store shared between instances. Entry expiry is up to the store.
+#[async_trait::async_trait]
+pub trait LookupCacheStore<V: Clone + Send + Sync + 'static>: Send + Sync {
+    async fn get(&self, key: &Pubkey) -> Option<V>;
+
+    async fn insert(&self, key: Pubkey, value: V);
+
+    /// The cached `key`, or the value of `init` inserted on a miss. Errors
+    /// are not cached.
+    ///
+    /// Concurrent calls for the same missing key should share one `init`,
+    /// as the moka implementation does with `try_get_with`. This default
+    /// does not, every caller runs its own `init`.
+    async fn try_get_with(
+        &self,
+        key: Pubkey,
+        init: futures::future::BoxFuture<'_, crate::Result<V>>,
+    ) -> crate::Result<V> {
+        if let Some(value) = self.get(&key).await {
+            return Ok(value);
+        }
+        let value = init.await?;
+        self.insert(key, value.clone()).await;
+        Ok(value)
+    }
+
+    /// Removes `key`, returning whether it was present
+    async fn invalidate(&self, key: &Pubkey) -> bool;
+
+    async fn invalidate_all(&self);
+
+    /// Number of entries, may lag behind recent changes until
+    /// [`LookupCacheStore::run_pending_tasks`]
+    fn entry_count(&self) -> u64;
+
+    /// Applies pending maintenance, e.g. evicting expired entries
+    async fn run_pending_tasks(&self);
+}
+
+/// Counts returned by [`LookupTableCacheProvider::prewarm`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrewarmResult {
+    /// Lookup tables resolved and inserted into the lookup cache
+    pub hits: usize,
+    /// Lookup tables not found, inserted into the negative cache
+    pub misses: usize,
+}
+
+/// Cache counters of [`LookupTableCacheProvider::stats`] and
+/// [`BlockHashCacheProvider::stats`], to tune TTLs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups served from the cache
+    pub hits: u64,
+    /// Lookups fetched from the inner provider
+    pub misses: u64,
+    /// Misses of lookup tables already recorded as not found
+    pub negative_hits: u64,
+    /// Entries removed before their next lookup
+    pub evictions: u64,
+}
+
+/// Shared atomic counters behind [`CacheStats`]
+#[derive(Debug, Default)]
+pub(crate) struct CacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    negative_hits: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl CacheCounters {
+    pub(crate) fn hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn negative_hit(&self) {
+        self.negative_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn evicted(&self, entries: u64) {
+        self.evictions.fetch_add(entries, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            negative_hits: self.negative_hits.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
 }
 
 #[derive(bon::Builder)]
 pub struct BlockHashCacheProvider<T: TransactionRpcProvider> {
     inner: T,
     blockhash: Cache<(), Hash>,
+    #[builder(skip)]
+    stats: Arc<CacheCounters>,
+    #[builder(skip)]
+    stale: Option<Arc<blockhash::StaleBlockhash>>,
+}
+
+/// Background task keeping a [`BlockHashCacheProvider`] warm, see
+/// [`BlockHashCacheProvider::spawn_refresher`].
+///
+/// The task stops on [`BlockhashRefresher::shutdown`], when the handle is
+/// dropped, or once the provider itself is dropped.
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct BlockhashRefresher {
+    task: tokio::task::JoinHandle<()>,
+}
+
+/// Caches `get_recent_prioritization_fees` results per account set, so
+/// transactions touching the same accounts within the TTL share one RPC call.
+///
+/// The key is a hash of the sorted, deduplicated accounts: fees of a hot
+/// account are never served to a transaction on other accounts. Failed
+/// calls are not cached.
+#[derive(Clone)]
+pub struct FeeCacheProvider<T: TransactionRpcProvider> {
+    inner: T,
+    fees: Cache<Hash, Vec<RpcPrioritizationFee>>,
+}
+
+/// Caches `simulate_transaction` results for identical transactions and
+/// configs, so repeated simulations within a request (fee estimation, then
+/// the send gate) hit the RPC once.
+///
+/// Keep the TTL short, around a second: results go stale with the chain
+/// state. Signatures are part of the key only when the config verifies them.
+/// Failed simulations are not cached.
+#[derive(Clone)]
+pub struct SimulationCacheProvider<T: TransactionRpcProvider> {
+    inner: T,
+    simulations: Cache<Hash, RpcSimulateTransactionResult>,
+}
+
+/// Spreads calls across several providers, rotating on every request.
+///
+/// Reads and writes both rotate, so consecutive calls (of any method) are
+/// handled by consecutive providers.
+#[derive(Clone)]
+pub struct RoundRobinRpcProvider<T: TransactionRpcProvider> {
+    providers: Vec<T>,
+    next: Arc<AtomicUsize>,
+}
+
+/// Sends every transaction through all providers at once to improve the
+/// chance of landing it during congestion.
+///
+/// `send_and_confirm_transaction` returns with the first provider that
+/// confirms, duplicate errors ("already processed") from the others are
+/// ignored. Unlike failover, the sends are concurrent. All other methods use
+/// the first provider.
+#[derive(Clone)]
+pub struct BroadcastSendProvider<T: TransactionRpcProvider> {
+    providers: Vec<T>,
+}
+
+/// Fault injector for resilience tests, configured per [`RpcMethod`] with a
+/// [`Fault`]. Methods without a fault pass through.
+///
+/// **NOTE**: not meant for production use
+#[cfg(feature = "testing")]
+#[derive(Clone)]
+pub struct ChaosRpcProvider<T: TransactionRpcProvider> {
+    inner: T,
+    faults: std::collections::HashMap<RpcMethod, Fault>,
+}
+
+/// Latency and failures injected by [`ChaosRpcProvider`].
+///
+/// Failures are spread evenly instead of randomly: with a rate of 25 percent
+/// every fourth call fails, which keeps tests reproducible. Clones share the
+/// call count.
+#[cfg(feature = "testing")]
+#[derive(Clone, Debug, Default)]
+pub struct Fault {
+    latency: Duration,
+    failure_percent: u8,
+    message: String,
+    calls: Arc<std::sync::atomic::AtomicU64>,
+}
+
+/// Retries transient RPC failures (rate limits, timeouts, lagging nodes, see
+/// [`is_transient`]) with exponential backoff and jitter, per
+/// [`RetryConfig`].
+///
+/// Transaction and simulation failures are returned as is. Sends are not
+/// retried by default.
+#[derive(Clone)]
+pub struct RetryRpcProvider<T: TransactionRpcProvider> {
+    inner: T,
+    config: RetryConfig,
+}
+
+/// Limits the request rate with a token bucket, across all methods and
+/// optionally per [`RpcMethod`], for endpoints which punish bursts.
+///
+/// Calls wait for a token instead of failing. Clones share the limits.
+#[derive(Clone)]
+pub struct RateLimitedRpcProvider<T: TransactionRpcProvider> {
+    inner: T,
+    limit: Arc<rate_limit::TokenBucket>,
+    method_limits: std::collections::HashMap<RpcMethod, Arc<rate_limit::TokenBucket>>,
+}
+
+/// Attempts and backoff of the [`RetryRpcProvider`].
+///
+/// The `n`th retry waits `initial_backoff * 2^n`, capped at `max_backoff`,
+/// plus up to `jitter` of that at random so concurrent clients do not retry
+/// in lockstep.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryConfig {
+    /// Attempts per call, including the first
+    pub max_attempts: u32,
+    /// Overrides `max_attempts` per method
+    pub method_attempts: std::collections::HashMap<RpcMethod, u32>,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Share of the backoff added at random, `0.0..=1.0`
+    pub jitter: f64,
+}
+
+/// Polling settings used while confirming a sent transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfirmationConfig {
+    /// Delay between signature status requests
+    pub poll_interval: Duration,
+    /// Give up confirming after this long
+    pub timeout: Duration,
+}
+
+impl Default for ConfirmationConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_millis(400),
+            timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Provider over the native [`RpcClient`] with configurable confirmation
+/// polling, see [`ConfirmationConfig`].
+///
+/// Tune the polling to trade confirmation latency against RPC credits.
+/// Polling runs in the caller's future, dropping it stops polling.
+#[derive(Clone, bon::Builder)]
+pub struct NativeRpcWrapper {
+    #[builder(into)]
+    client: Arc<RpcClient>,
+    #[builder(default)]
+    confirmation: ConfirmationConfig,
+    /// Wire encoding of simulated and sent transactions, unless the call's
+    /// config sets one. Only `Base64` and `Base58` are accepted by the RPC.
+    #[builder(default = UiTransactionEncoding::Base64)]
+    encoding: UiTransactionEncoding,
+    /// Commitment for blockhash, simulation and confirmation requests,
+    /// overriding the client's, see [`NativeRpcWrapper::with_commitment`]
+    commitment: Option<solana_commitment_config::CommitmentConfig>,
+    /// When a sent transaction lands but fails, fetch it with
+    /// `getTransaction` and attach its logs to
+    /// [`crate::Error::TransactionFailed`].
+    #[builder(default = true)]
+    fetch_failure_logs: bool,
+}
+
+impl AsRef<RpcClient> for NativeRpcWrapper {
+    fn as_ref(&self) -> &RpcClient {
+        self.client.as_ref()
+    }
+}
+
+impl From<Arc<RpcClient>> for NativeRpcWrapper {
+    fn from(client: Arc<RpcClient>) -> Self {
+        Self::builder().client(client).build()
+    }
+}
+
+impl From<RpcClient> for NativeRpcWrapper {
+    fn from(client: RpcClient) -> Self {
+        Self::builder().client(client).build()
+    }
+}
+
+/// Logs a `WARN` event for every call slower than `threshold`.
+///
+/// A lighter alternative to [`TraceTransactionProvider`] when only slow calls
+/// are of interest.
+#[derive(Clone)]
+pub struct SlowLogRpcProvider<T: TransactionRpcProvider> {
+    inner: T,
+    threshold: Duration,
+}
+
+/// Coalesces concurrent identical `get_recent_prioritization_fees` and
+/// `simulate_transaction` calls into a single inner call whose result is
+/// shared.
+///
+/// Calls are keyed by their serialized arguments. Results are only shared
+/// while the inner call is in flight, nothing is cached afterwards. Other
+/// methods delegate to the inner provider.
+#[derive(Clone)]
+pub struct SingleFlightRpcProvider<T: TransactionRpcProvider> {
+    inner: T,
+    fees: Cache<Vec<u8>, Vec<RpcPrioritizationFee>>,
+    simulations: Cache<Vec<u8>, RpcSimulateTransactionResult>,
 }
 
 pub type TraceTransactionArcProvider = TraceTransactionProvider<Arc<RpcClient>>;
@@ -154,13 +552,18 @@ impl<T: AsRef<RpcClient> + Clone> From<T> for TraceTransactionProvider<T> {
 }
 
 /// Convenient definitions for the [`CounterRpcProvider`]
-#[derive(Eq, std::hash::Hash, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Eq, std::hash::Hash, PartialEq, PartialOrd)]
 pub enum RpcMethod {
     Blockhash,
     Lookup,
     Simulate,
     Send,
     Fees,
+    Balance,
+    Account,
+    Accounts,
+    ProgramAccounts,
+    PerfSamples,
 }
 
 impl RpcMethod {
@@ -171,6 +574,11 @@ impl RpcMethod {
             RpcMethod::Simulate => "simulate",
             RpcMethod::Send => "send",
             RpcMethod::Fees => "fees",
+            RpcMethod::Balance => "balance",
+            RpcMethod::Account => "account",
+            RpcMethod::Accounts => "accounts",
+            RpcMethod::ProgramAccounts => "program_accounts",
+            RpcMethod::PerfSamples => "perf_samples",
         }
     }
 }
@@ -220,13 +628,18 @@ impl<T: TransactionRpcProvider + AsRef<RpcClient> + Clone> CounterRpcProvider<T>
         counters.insert(RpcMethod::Simulate, 0);
         counters.insert(RpcMethod::Send, 0);
         counters.insert(RpcMethod::Fees, 0);
+        counters.insert(RpcMethod::Balance, 0);
+        counters.insert(RpcMethod::Account, 0);
+        counters.insert(RpcMethod::Accounts, 0);
+        counters.insert(RpcMethod::ProgramAccounts, 0);
+        counters.insert(RpcMethod::PerfSamples, 0);
         Self { inner, counters }
     }
 }
 
 #[cfg(test)]
 #[allow(unused_variables)]
-mod noop {
+pub(crate) mod noop {
     use {
         super::*,
         crate::Result,
@@ -296,6 +709,14 @@ mod noop {
             Ok(vec![])
         }
 
+        async fn get_lookup_table_accounts_with_commitment(
+            &self,
+            _pubkeys: &[Pubkey],
+            _commitment: solana_commitment_config::CommitmentConfig,
+        ) -> Result<Vec<AddressLookupTableAccount>> {
+            Ok(vec![])
+        }
+
         async fn get_latest_blockhash(&self) -> Result<Hash> {
             Ok(Hash::new_unique())
         }
@@ -330,6 +751,36 @@ mod noop {
         ) -> Result<Signature> {
             Ok(Signature::default())
         }
+
+        async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+            Ok(0)
+        }
+
+        async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+            Ok(None)
+        }
+
+        async fn get_multiple_accounts(
+            &self,
+            pubkeys: &[Pubkey],
+        ) -> Result<Vec<Option<solana_account::Account>>> {
+            Ok(vec![None; pubkeys.len()])
+        }
+
+        async fn get_program_accounts(
+            &self,
+            _program: &Pubkey,
+            _filters: Vec<solana_rpc_client_api::filter::RpcFilterType>,
+        ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+            Ok(vec![])
+        }
+
+        async fn get_recent_performance_samples(
+            &self,
+            limit: usize,
+        ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+            Ok(vec![])
+        }
     }
 
     #[test]
@@ -0,0 +1,244 @@
+use {
+    super::{AuditCallback, AuditRecord, AuditSendProvider, AuditSink},
+    crate::{Error, Result, TransactionRpcProvider},
+    base64::prelude::*,
+    solana_hash::Hash,
+    solana_message::AddressLookupTableAccount,
+    solana_pubkey::Pubkey,
+    solana_rpc_client::nonblocking::rpc_client::RpcClient,
+    solana_rpc_client_api::response::RpcPrioritizationFee,
+    solana_signature::Signature,
+    solana_transaction::versioned::VersionedTransaction,
+    std::{io::Write, path::PathBuf, sync::Arc, time::SystemTime},
+};
+
+impl AuditSink {
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self::File(path.into())
+    }
+
+    pub fn callback<F>(callback: F) -> Self
+    where
+        F: Fn(&AuditRecord) -> Result<()> + Send + Sync + 'static,
+    {
+        let callback: AuditCallback = Arc::new(callback);
+        Self::Callback(callback)
+    }
+
+    async fn write(&self, record: &AuditRecord) -> Result<()> {
+        match self {
+            Self::File(path) => {
+                let timestamp = record
+                    .timestamp
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .map_err(|e| Error::CustomError(format!("invalid audit timestamp: {e}")))?;
+                let mut line = serde_json::json!({
+                    "signature": record.signature.to_string(),
+                    "timestamp_ms": timestamp.as_millis() as u64,
+                    "transaction": record.transaction,
+                })
+                .to_string();
+                line.push('\n');
+                append(path.clone(), line).await
+            }
+            Self::Callback(callback) => callback(record),
+        }
+    }
+}
+
+/// Appends `line` to `path` off the async executor: on the tokio blocking
+/// pool when a runtime is running, on a helper thread otherwise
+async fn append(path: PathBuf, line: String) -> Result<()> {
+    let write = move || -> Result<()> {
+        // a single append write keeps concurrent records on separate lines
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?;
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+        Ok(())
+    };
+    #[cfg(feature = "tokio")]
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return tokio::task::spawn_blocking(write)
+            .await
+            .map_err(|e| Error::CustomError(format!("audit write failed: {e}")))?;
+    }
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(write());
+    });
+    receiver
+        .await
+        .map_err(|_| Error::CustomError("audit write failed: writer thread panicked".to_string()))?
+}
+
+impl AuditRecord {
+    pub fn new(tx: &VersionedTransaction) -> Result<Self> {
+        Ok(Self {
+            signature: tx.signatures.first().copied().unwrap_or_default(),
+            timestamp: SystemTime::now(),
+            transaction: BASE64_STANDARD.encode(bincode::serialize(tx)?),
+        })
+    }
+}
+
+impl<T: TransactionRpcProvider> AuditSendProvider<T> {
+    pub fn new(inner: T, sink: AuditSink) -> Self {
+        Self { inner, sink }
+    }
+}
+
+impl<T: TransactionRpcProvider + AsRef<RpcClient>> AsRef<RpcClient> for AuditSendProvider<T> {
+    fn as_ref(&self) -> &RpcClient {
+        self.inner.as_ref()
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: TransactionRpcProvider> TransactionRpcProvider for AuditSendProvider<T> {
+    async fn get_recent_prioritization_fees(
+        &self,
+        accounts: &[Pubkey],
+    ) -> Result<Vec<RpcPrioritizationFee>> {
+        self.inner.get_recent_prioritization_fees(accounts).await
+    }
+
+    async fn get_lookup_table_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.inner.get_lookup_table_accounts(pubkeys).await
+    }
+
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: solana_commitment_config::CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.inner
+            .get_lookup_table_accounts_with_commitment(pubkeys, commitment)
+            .await
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash> {
+        self.inner.get_latest_blockhash().await
+    }
+
+    async fn simulate_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        config: solana_rpc_client_api::config::RpcSimulateTransactionConfig,
+    ) -> Result<solana_rpc_client_api::response::RpcSimulateTransactionResult> {
+        self.inner.simulate_transaction(tx, config).await
+    }
+
+    async fn send_and_confirm_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        config: Option<solana_rpc_client_api::config::RpcSendTransactionConfig>,
+    ) -> Result<Signature> {
+        self.sink.write(&AuditRecord::new(tx)?).await?;
+        self.inner.send_and_confirm_transaction(tx, config).await
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        self.inner.get_balance(pubkey).await
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        self.inner.get_account(pubkey).await
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        self.inner.get_multiple_accounts(pubkeys).await
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<solana_rpc_client_api::filter::RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        self.inner.get_program_accounts(program, filters).await
+    }
+
+    async fn get_recent_performance_samples(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+        self.inner.get_recent_performance_samples(limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{TransactionBuilder, rpc::mock::MockRpc},
+        solana_keypair::Keypair,
+        solana_signer::Signer,
+        std::sync::Mutex,
+    };
+
+    #[tokio::test]
+    async fn test_audit_records_send() -> anyhow::Result<()> {
+        let payer = Keypair::new();
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let sink = {
+            let records = records.clone();
+            AuditSink::callback(move |record| {
+                records.lock().unwrap().push(record.clone());
+                Ok(())
+            })
+        };
+        let mock = MockRpc::default();
+        let rpc = AuditSendProvider::new(mock.clone(), sink);
+
+        let signature = TransactionBuilder::default()
+            .with_memo("audit", &[&payer.pubkey()])
+            .send(&rpc, &payer.pubkey(), &[&payer])
+            .await?;
+        let records = records.lock().unwrap().clone();
+        assert_eq!(1, records.len());
+        assert_eq!(signature.signature(), records[0].signature);
+        let tx: VersionedTransaction =
+            bincode::deserialize(&BASE64_STANDARD.decode(&records[0].transaction)?)?;
+        assert_eq!(mock.sent()[0].0, tx);
+
+        // nothing is sent without a record
+        let rpc = AuditSendProvider::new(
+            mock.clone(),
+            AuditSink::callback(|_| Err(Error::CustomError("disk full".to_string()))),
+        );
+        let result = TransactionBuilder::default()
+            .with_memo("audit", &[&payer.pubkey()])
+            .send(&rpc, &payer.pubkey(), &[&payer])
+            .await;
+        assert!(result.is_err());
+        assert_eq!(1, mock.sent().len());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_audit_file_sink() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join(format!("soly-audit-{}.jsonl", Pubkey::new_unique()));
+        let sink = AuditSink::file(&path);
+        let tx = VersionedTransaction::default();
+        sink.write(&AuditRecord::new(&tx)?).await?;
+        sink.write(&AuditRecord::new(&tx)?).await?;
+
+        let content = std::fs::read_to_string(&path)?;
+        std::fs::remove_file(&path)?;
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<std::result::Result<_, _>>()?;
+        assert_eq!(2, lines.len());
+        assert_eq!(Signature::default().to_string(), lines[0]["signature"]);
+        Ok(())
+    }
+}
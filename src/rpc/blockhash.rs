@@ -1,30 +1,209 @@
 use {
-    super::BlockHashCacheProvider,
+    super::{BlockHashCacheProvider, CacheCounters, CacheStats},
     crate::{Result, TransactionRpcProvider},
+    futures::future::BoxFuture,
     moka::future::Cache,
     solana_hash::Hash,
     solana_message::AddressLookupTableAccount,
     solana_pubkey::Pubkey,
     solana_rpc_client_api::response::RpcPrioritizationFee,
     solana_signature::Signature,
-    std::time::Duration,
+    std::{
+        sync::{Arc, Mutex, atomic::AtomicBool},
+        time::{Duration, Instant},
+    },
     tracing::{Level, event},
 };
 
+#[cfg(feature = "tokio")]
+use super::BlockhashRefresher;
+
+type Refresh = Box<dyn Fn() -> BoxFuture<'static, Result<Hash>> + Send + Sync>;
+
+/// The last fetched blockhash, served past the TTL while a background task
+/// fetches the next one
+pub(crate) struct StaleBlockhash {
+    max_age: Duration,
+    last: Mutex<Option<(Hash, Instant)>>,
+    #[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+    refreshing: AtomicBool,
+    #[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+    refresh: Refresh,
+}
+
+impl StaleBlockhash {
+    fn record(&self, blockhash: Hash) {
+        *self.last.lock().expect("stale blockhash lock") = Some((blockhash, Instant::now()));
+    }
+
+    fn within_max_age(&self) -> Option<Hash> {
+        self.last
+            .lock()
+            .expect("stale blockhash lock")
+            .filter(|(_, fetched)| fetched.elapsed() < self.max_age)
+            .map(|(blockhash, _)| blockhash)
+    }
+
+    /// Spawns a single refresh at a time, false without a tokio runtime to
+    /// spawn on
+    #[cfg(feature = "tokio")]
+    fn revalidate(self: &Arc<Self>, cache: &Cache<(), Hash>) -> bool {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return false;
+        };
+        if self
+            .refreshing
+            .swap(true, std::sync::atomic::Ordering::AcqRel)
+        {
+            return true;
+        }
+        let stale = self.clone();
+        let cache = cache.clone();
+        let refresh = (self.refresh)();
+        runtime.spawn(async move {
+            event!(Level::DEBUG, "revalidating stale blockhash");
+            match refresh.await {
+                Ok(blockhash) => {
+                    cache.insert((), blockhash).await;
+                    stale.record(blockhash);
+                }
+                Err(e) => event!(Level::WARN, "blockhash revalidation failed: {e}"),
+            }
+            stale
+                .refreshing
+                .store(false, std::sync::atomic::Ordering::Release);
+        });
+        true
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    fn revalidate(self: &Arc<Self>, _cache: &Cache<(), Hash>) -> bool {
+        false
+    }
+}
+
 impl<T: TransactionRpcProvider> BlockHashCacheProvider<T> {
     pub fn new(client: T, ttl: Duration) -> Self {
+        let stats = Arc::new(CacheCounters::default());
+        let evictions = stats.clone();
         Self {
             inner: client,
-            blockhash: Cache::builder().max_capacity(1).time_to_live(ttl).build(),
+            blockhash: Cache::builder()
+                .max_capacity(1)
+                .time_to_live(ttl)
+                .eviction_listener(move |_, _, cause| {
+                    if cause.was_evicted() {
+                        evictions.evicted(1);
+                    }
+                })
+                .build(),
+            stats,
+            stale: None,
         }
     }
+
+    /// Serves the last blockhash past the TTL, up to `max_age` after it was
+    /// fetched, while a background task fetches the next one.
+    ///
+    /// A burst of sends at the TTL boundary then gets an answer at once
+    /// instead of all waiting on the same RPC call. Keep `max_age` well
+    /// below the ~60 seconds a blockhash stays valid. Without a tokio
+    /// runtime, or the `tokio` feature, the expired hash is fetched inline
+    /// as before.
+    pub fn with_stale_while_revalidate(mut self, max_age: Duration) -> Self
+    where
+        T: Clone + 'static,
+    {
+        let inner = self.inner.clone();
+        self.stale = Some(Arc::new(StaleBlockhash {
+            max_age,
+            last: Mutex::new(None),
+            refreshing: AtomicBool::new(false),
+            refresh: Box::new(move || {
+                let inner = inner.clone();
+                Box::pin(async move { inner.get_latest_blockhash().await })
+            }),
+        }));
+        self
+    }
+
+    #[cfg(feature = "tokio")]
+    async fn cache_blockhash(&self, blockhash: Hash) {
+        self.blockhash.insert((), blockhash).await;
+        if let Some(stale) = &self.stale {
+            stale.record(blockhash);
+        }
+    }
+
+    /// Refreshes the cached blockhash every `interval` in a spawned tokio
+    /// task, so [`TransactionRpcProvider::get_latest_blockhash`] never waits
+    /// on the RPC.
+    ///
+    /// Pick an `interval` below the cache TTL, e.g. half of it. Failed
+    /// refreshes are logged and retried at the next tick, the cached hash
+    /// expires as usual meanwhile. Must be called within a tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub fn spawn_refresher(self: &Arc<Self>, interval: Duration) -> BlockhashRefresher
+    where
+        T: 'static,
+    {
+        let weak = Arc::downgrade(self);
+        let task = tokio::spawn(async move {
+            while let Some(provider) = weak.upgrade() {
+                match provider.inner.get_latest_blockhash().await {
+                    Ok(blockhash) => provider.cache_blockhash(blockhash).await,
+                    Err(e) => event!(Level::WARN, "blockhash refresh failed: {e}"),
+                }
+                drop(provider);
+                crate::time::sleep(interval).await;
+            }
+        });
+        BlockhashRefresher { task }
+    }
+
+    /// Hits and misses of the blockhash cache since creation. Expired hashes
+    /// count as evictions when the cache was built by
+    /// [`BlockHashCacheProvider::new`].
+    pub fn stats(&self) -> CacheStats {
+        self.stats.snapshot()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl BlockhashRefresher {
+    /// Stops the refresh task, the cached blockhash stays until it expires
+    pub fn shutdown(self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Drop for BlockhashRefresher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
 }
 
 #[async_trait::async_trait]
 impl<T: TransactionRpcProvider + Send + Sync> TransactionRpcProvider for BlockHashCacheProvider<T> {
     async fn get_latest_blockhash(&self) -> Result<Hash> {
-        self.blockhash
+        if let Some(stale) = &self.stale {
+            if let Some(blockhash) = self.blockhash.get(&()).await {
+                self.stats.hit();
+                return Ok(blockhash);
+            }
+            if let Some(blockhash) = stale.within_max_age()
+                && stale.revalidate(&self.blockhash)
+            {
+                self.stats.hit();
+                return Ok(blockhash);
+            }
+        }
+        let mut missed = false;
+        let blockhash = self
+            .blockhash
             .try_get_with((), async {
+                missed = true;
                 event!(Level::DEBUG, "blockhash cache miss");
                 self.inner.get_latest_blockhash().await
             })
@@ -32,7 +211,16 @@ impl<T: TransactionRpcProvider + Send + Sync> TransactionRpcProvider for BlockHa
             .map_err(|arc_err| match std::sync::Arc::try_unwrap(arc_err) {
                 Ok(e) => e,
                 Err(arc) => crate::Error::MokaCacheError(arc.to_string()),
-            })
+            });
+        if missed {
+            self.stats.miss();
+            if let (Some(stale), Ok(blockhash)) = (&self.stale, &blockhash) {
+                stale.record(*blockhash);
+            }
+        } else if blockhash.is_ok() {
+            self.stats.hit();
+        }
+        blockhash
     }
 
     async fn get_recent_prioritization_fees(
@@ -49,6 +237,16 @@ impl<T: TransactionRpcProvider + Send + Sync> TransactionRpcProvider for BlockHa
         self.inner.get_lookup_table_accounts(pubkeys).await
     }
 
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: solana_commitment_config::CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.inner
+            .get_lookup_table_accounts_with_commitment(pubkeys, commitment)
+            .await
+    }
+
     async fn simulate_transaction(
         &self,
         tx: &solana_transaction::versioned::VersionedTransaction,
@@ -64,6 +262,36 @@ impl<T: TransactionRpcProvider + Send + Sync> TransactionRpcProvider for BlockHa
     ) -> Result<Signature> {
         self.inner.send_and_confirm_transaction(tx, config).await
     }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        self.inner.get_balance(pubkey).await
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        self.inner.get_account(pubkey).await
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        self.inner.get_multiple_accounts(pubkeys).await
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<solana_rpc_client_api::filter::RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        self.inner.get_program_accounts(program, filters).await
+    }
+
+    async fn get_recent_performance_samples(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+        self.inner.get_recent_performance_samples(limit).await
+    }
 }
 
 #[cfg(test)]
@@ -76,11 +304,16 @@ mod tests {
             TransactionRpcProvider,
             rpc::{
                 CounterRpcProvider,
+                mock::MockRpc,
                 noop::{NoopRpc, NoopRpcNative},
             },
         },
         solana_keypair::Keypair,
-        solana_rpc_client_api::config::RpcSimulateTransactionConfig,
+        solana_rpc_client_api::{
+            config::RpcSimulateTransactionConfig,
+            filter::{Memcmp, RpcFilterType},
+            response::RpcPerfSample,
+        },
         solana_signer::Signer,
         tokio::time::sleep,
     };
@@ -124,8 +357,10 @@ mod tests {
             .await?;
 
         let _ = hash_cache.send_and_confirm_transaction(&tx, None).await?;
+        let _ = hash_cache.get_balance(&kp.pubkey()).await?;
 
         assert_eq!(1, counter.get_counter(&crate::RpcMethod::Fees));
+        assert_eq!(1, counter.get_counter(&crate::RpcMethod::Balance));
         assert_eq!(1, counter.get_counter(&crate::RpcMethod::Lookup));
         assert_eq!(1, counter.get_counter(&crate::RpcMethod::Simulate));
         assert_eq!(1, counter.get_counter(&crate::RpcMethod::Send));
@@ -135,6 +370,152 @@ mod tests {
         assert_eq!(0, counter.get_counter(&crate::RpcMethod::Lookup));
         assert_eq!(0, counter.get_counter(&crate::RpcMethod::Simulate));
         assert_eq!(0, counter.get_counter(&crate::RpcMethod::Send));
+        assert_eq!(0, counter.get_counter(&crate::RpcMethod::Balance));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_performance_samples_delegation() -> anyhow::Result<()> {
+        let sample = |slot| RpcPerfSample {
+            slot,
+            num_transactions: 3_000,
+            num_non_vote_transactions: Some(1_000),
+            num_slots: 150,
+            sample_period_secs: 60,
+        };
+        let mock = MockRpc {
+            performance_samples: vec![sample(3), sample(2), sample(1)],
+            ..Default::default()
+        };
+        let counter = CounterRpcProvider::new(mock);
+        let hash_cache = BlockHashCacheProvider::new(counter.clone(), Duration::from_secs(1));
+
+        let samples = hash_cache.get_recent_performance_samples(2).await?;
+        assert_eq!(vec![sample(3), sample(2)], samples);
+        assert_eq!(1, counter.get_counter(&crate::RpcMethod::PerfSamples));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_program_accounts_delegation() -> anyhow::Result<()> {
+        let program = Pubkey::new_unique();
+        let account = |owner, data: &[u8]| solana_account::Account {
+            owner,
+            data: data.to_vec(),
+            ..Default::default()
+        };
+        let matching = Pubkey::new_unique();
+        let mock = MockRpc {
+            accounts: [
+                (matching, account(program, &[1, 2, 3])),
+                (Pubkey::new_unique(), account(program, &[1, 9, 3])),
+                (Pubkey::new_unique(), account(program, &[1, 2])),
+                (
+                    Pubkey::new_unique(),
+                    account(Pubkey::new_unique(), &[1, 2, 3]),
+                ),
+            ]
+            .into(),
+            ..Default::default()
+        };
+        let counter = CounterRpcProvider::new(mock);
+        let hash_cache = BlockHashCacheProvider::new(counter.clone(), Duration::from_secs(1));
+
+        let filters = vec![
+            RpcFilterType::DataSize(3),
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(1, vec![2])),
+        ];
+        let accounts = hash_cache.get_program_accounts(&program, filters).await?;
+        assert_eq!(vec![(matching, account(program, &[1, 2, 3]))], accounts);
+        assert_eq!(
+            3,
+            hash_cache
+                .get_program_accounts(&program, vec![])
+                .await?
+                .len()
+        );
+        assert_eq!(2, counter.get_counter(&crate::RpcMethod::ProgramAccounts));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_blockhash_stats() -> anyhow::Result<()> {
+        let rpc = BlockHashCacheProvider::new(MockRpc::default(), Duration::from_millis(100));
+        rpc.get_latest_blockhash().await?;
+        rpc.get_latest_blockhash().await?;
+        assert_eq!(
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                ..Default::default()
+            },
+            rpc.stats()
+        );
+
+        sleep(Duration::from_millis(200)).await;
+        rpc.blockhash.run_pending_tasks().await;
+        rpc.get_latest_blockhash().await?;
+        assert_eq!(
+            CacheStats {
+                hits: 1,
+                misses: 2,
+                negative_hits: 0,
+                evictions: 1,
+            },
+            rpc.stats()
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_blockhash_refresher() -> anyhow::Result<()> {
+        let counter = CounterRpcProvider::new(MockRpc::default());
+        let rpc = Arc::new(BlockHashCacheProvider::new(
+            counter.clone(),
+            Duration::from_secs(60),
+        ));
+        let refresher = rpc.spawn_refresher(Duration::from_millis(20));
+        sleep(Duration::from_millis(110)).await;
+        let refreshed = counter.get_counter(&crate::RpcMethod::Blockhash);
+        assert!(refreshed >= 3, "refreshed {refreshed} times");
+
+        rpc.get_latest_blockhash().await?;
+        assert_eq!(0, rpc.stats().misses);
+
+        refresher.shutdown();
+        sleep(Duration::from_millis(10)).await;
+        let stopped = counter.get_counter(&crate::RpcMethod::Blockhash);
+        sleep(Duration::from_millis(60)).await;
+        assert_eq!(stopped, counter.get_counter(&crate::RpcMethod::Blockhash));
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_stale_while_revalidate() -> anyhow::Result<()> {
+        let counter = CounterRpcProvider::new(MockRpc::default());
+        let rpc = BlockHashCacheProvider::new(counter.clone(), Duration::from_millis(50))
+            .with_stale_while_revalidate(Duration::from_millis(500));
+        let blockhash = rpc.get_latest_blockhash().await?;
+        assert_eq!(1, counter.get_counter(&crate::RpcMethod::Blockhash));
+
+        sleep(Duration::from_millis(80)).await;
+        // expired, served stale while one refresh runs in the background
+        for _ in 0..5 {
+            assert_eq!(blockhash, rpc.get_latest_blockhash().await?);
+        }
+        sleep(Duration::from_millis(20)).await;
+        assert_eq!(2, counter.get_counter(&crate::RpcMethod::Blockhash));
+        rpc.get_latest_blockhash().await?;
+        assert_eq!(2, counter.get_counter(&crate::RpcMethod::Blockhash));
+        assert_eq!(1, rpc.stats().misses);
+
+        // past the max age the caller waits on the RPC again
+        sleep(Duration::from_millis(600)).await;
+        rpc.get_latest_blockhash().await?;
+        assert_eq!(3, counter.get_counter(&crate::RpcMethod::Blockhash));
+        assert_eq!(2, rpc.stats().misses);
         Ok(())
     }
 }
@@ -0,0 +1,134 @@
+use {
+    crate::{Result, TransactionRpcProvider},
+    solana_hash::Hash,
+    solana_message::AddressLookupTableAccount,
+    solana_pubkey::Pubkey,
+    solana_rpc_client_api::response::RpcPrioritizationFee,
+    solana_signature::Signature,
+    std::sync::Arc,
+};
+
+/// Delegates every method to the pointee, so `Box<dyn TransactionRpcProvider>`
+/// and `Arc<dyn TransactionRpcProvider>` can be passed wherever a provider is
+/// expected.
+macro_rules! impl_deref_provider {
+    ($($pointer:ident),+) => {$(
+        #[async_trait::async_trait]
+        impl<T: TransactionRpcProvider + ?Sized> TransactionRpcProvider for $pointer<T> {
+            async fn get_recent_prioritization_fees(
+                &self,
+                accounts: &[Pubkey],
+            ) -> Result<Vec<RpcPrioritizationFee>> {
+                (**self).get_recent_prioritization_fees(accounts).await
+            }
+
+            async fn get_lookup_table_accounts(
+                &self,
+                pubkeys: &[Pubkey],
+            ) -> Result<Vec<AddressLookupTableAccount>> {
+                (**self).get_lookup_table_accounts(pubkeys).await
+            }
+
+            async fn get_lookup_table_accounts_with_commitment(
+                &self,
+                pubkeys: &[Pubkey],
+                commitment: solana_commitment_config::CommitmentConfig,
+            ) -> Result<Vec<AddressLookupTableAccount>> {
+                (**self).get_lookup_table_accounts_with_commitment(pubkeys, commitment).await
+            }
+
+            async fn get_latest_blockhash(&self) -> Result<Hash> {
+                (**self).get_latest_blockhash().await
+            }
+
+            async fn simulate_transaction(
+                &self,
+                tx: &solana_transaction::versioned::VersionedTransaction,
+                config: solana_rpc_client_api::config::RpcSimulateTransactionConfig,
+            ) -> Result<solana_rpc_client_api::response::RpcSimulateTransactionResult> {
+                (**self).simulate_transaction(tx, config).await
+            }
+
+            async fn send_and_confirm_transaction(
+                &self,
+                tx: &solana_transaction::versioned::VersionedTransaction,
+                config: Option<solana_rpc_client_api::config::RpcSendTransactionConfig>,
+            ) -> Result<Signature> {
+                (**self).send_and_confirm_transaction(tx, config).await
+            }
+
+            async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+                (**self).get_balance(pubkey).await
+            }
+
+            async fn get_account(
+                &self,
+                pubkey: &Pubkey,
+            ) -> Result<Option<solana_account::Account>> {
+                (**self).get_account(pubkey).await
+            }
+
+            async fn get_multiple_accounts(
+                &self,
+                pubkeys: &[Pubkey],
+            ) -> Result<Vec<Option<solana_account::Account>>> {
+                (**self).get_multiple_accounts(pubkeys).await
+            }
+
+            async fn get_program_accounts(
+                &self,
+                program: &Pubkey,
+                filters: Vec<solana_rpc_client_api::filter::RpcFilterType>,
+            ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+                (**self).get_program_accounts(program, filters).await
+            }
+
+            async fn get_recent_performance_samples(
+                &self,
+                limit: usize,
+            ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+                (**self).get_recent_performance_samples(limit).await
+            }
+        }
+    )+};
+}
+
+impl_deref_provider!(Box, Arc);
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{CounterRpcProvider, RpcMethod, TransactionBuilder, rpc::mock::MockRpc},
+    };
+
+    #[tokio::test]
+    async fn test_dyn_providers() -> anyhow::Result<()> {
+        let counter = CounterRpcProvider::new(MockRpc {
+            balance: 7,
+            ..Default::default()
+        });
+        let providers: Vec<Arc<dyn TransactionRpcProvider>> = vec![
+            Arc::new(counter.clone()),
+            Arc::new(MockRpc {
+                balance: 9,
+                ..Default::default()
+            }),
+        ];
+        let mut balances = vec![];
+        for provider in &providers {
+            balances.push(provider.get_balance(&Pubkey::new_unique()).await?);
+        }
+        assert_eq!(vec![7, 9], balances);
+        assert_eq!(1, counter.get_counter(&RpcMethod::Balance));
+
+        let payer = Pubkey::new_unique();
+        let boxed: Box<dyn TransactionRpcProvider> = Box::new(counter.clone());
+        TransactionBuilder::default()
+            .with_memo("dyn", &[&payer])
+            .unsigned_tx(&payer, &boxed)
+            .await?;
+        assert_eq!(1, counter.get_counter(&RpcMethod::Blockhash));
+        Ok(())
+    }
+}
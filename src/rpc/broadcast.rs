@@ -0,0 +1,215 @@
+use {
+    super::BroadcastSendProvider,
+    crate::{Error, Result, TransactionRpcProvider},
+    futures::stream::{FuturesUnordered, StreamExt},
+    solana_hash::Hash,
+    solana_message::AddressLookupTableAccount,
+    solana_pubkey::Pubkey,
+    solana_rpc_client_api::response::{RpcPrioritizationFee, TransactionError},
+    solana_signature::Signature,
+    tracing::debug,
+};
+
+impl<T: TransactionRpcProvider> BroadcastSendProvider<T> {
+    pub fn new(providers: Vec<T>) -> Self {
+        Self { providers }
+    }
+
+    /// The wrapped providers, the first one serves all reads
+    pub fn providers(&self) -> &[T] {
+        &self.providers
+    }
+
+    fn first(&self) -> Result<&T> {
+        self.providers
+            .first()
+            .ok_or_else(|| Error::CustomError("BroadcastSendProvider has no providers".to_string()))
+    }
+
+    /// Another provider already landed the same transaction
+    fn is_duplicate(error: &Error) -> bool {
+        match error {
+            Error::TransactionFailed { error, .. } | Error::SolanaSimulateError { error, .. } => {
+                *error == TransactionError::AlreadyProcessed
+            }
+            Error::SolanaRpcError(message) => message.contains("already been processed"),
+            _ => false,
+        }
+    }
+}
+
+impl<T: TransactionRpcProvider> From<Vec<T>> for BroadcastSendProvider<T> {
+    fn from(providers: Vec<T>) -> Self {
+        Self::new(providers)
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: TransactionRpcProvider> TransactionRpcProvider for BroadcastSendProvider<T> {
+    async fn get_recent_prioritization_fees(
+        &self,
+        accounts: &[Pubkey],
+    ) -> Result<Vec<RpcPrioritizationFee>> {
+        self.first()?.get_recent_prioritization_fees(accounts).await
+    }
+
+    async fn get_lookup_table_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.first()?.get_lookup_table_accounts(pubkeys).await
+    }
+
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: solana_commitment_config::CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.first()?
+            .get_lookup_table_accounts_with_commitment(pubkeys, commitment)
+            .await
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash> {
+        self.first()?.get_latest_blockhash().await
+    }
+
+    async fn simulate_transaction(
+        &self,
+        tx: &solana_transaction::versioned::VersionedTransaction,
+        config: solana_rpc_client_api::config::RpcSimulateTransactionConfig,
+    ) -> Result<solana_rpc_client_api::response::RpcSimulateTransactionResult> {
+        self.first()?.simulate_transaction(tx, config).await
+    }
+
+    async fn send_and_confirm_transaction(
+        &self,
+        tx: &solana_transaction::versioned::VersionedTransaction,
+        config: Option<solana_rpc_client_api::config::RpcSendTransactionConfig>,
+    ) -> Result<Signature> {
+        self.first()?;
+        let mut sends: FuturesUnordered<_> = self
+            .providers
+            .iter()
+            .map(|provider| provider.send_and_confirm_transaction(tx, config))
+            .collect();
+        let mut failure = None;
+        while let Some(result) = sends.next().await {
+            match result {
+                Ok(signature) => return Ok(signature),
+                Err(e) if Self::is_duplicate(&e) => {
+                    debug!("ignoring duplicate broadcast: {e}");
+                    failure.get_or_insert(e);
+                }
+                // keep the first real failure over duplicates
+                Err(e) => match failure {
+                    Some(ref f) if !Self::is_duplicate(f) => {}
+                    _ => failure = Some(e),
+                },
+            }
+        }
+        Err(failure.expect("at least one provider"))
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        self.first()?.get_balance(pubkey).await
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        self.first()?.get_account(pubkey).await
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        self.first()?.get_multiple_accounts(pubkeys).await
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<solana_rpc_client_api::filter::RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        self.first()?.get_program_accounts(program, filters).await
+    }
+
+    async fn get_recent_performance_samples(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+        self.first()?.get_recent_performance_samples(limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{CounterRpcProvider, RpcMethod, TransactionBuilder, rpc::mock::MockRpc},
+        std::time::Duration,
+    };
+
+    #[tokio::test]
+    async fn test_broadcast_sends_to_all() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let duplicate = MockRpc {
+            send_error: Some(TransactionError::AlreadyProcessed),
+            ..Default::default()
+        };
+        let providers = vec![
+            CounterRpcProvider::new(duplicate.clone()),
+            // confirms last, after the duplicates were ignored
+            CounterRpcProvider::new(MockRpc {
+                latency: Duration::from_millis(20),
+                ..Default::default()
+            }),
+            CounterRpcProvider::new(duplicate),
+        ];
+        let rpc = BroadcastSendProvider::new(providers.clone());
+        let tx = TransactionBuilder::default()
+            .with_memo("broadcast", &[&payer])
+            .unsigned_tx(&payer, &rpc)
+            .await?;
+
+        rpc.send_and_confirm_transaction(&tx, None).await?;
+        for provider in &providers {
+            assert_eq!(1, provider.get_counter(&RpcMethod::Send));
+        }
+        // reads are not broadcast
+        assert_eq!(1, providers[0].get_counter(&RpcMethod::Blockhash));
+        assert_eq!(0, providers[1].get_counter(&RpcMethod::Blockhash));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_failure() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let failed = MockRpc {
+            send_error: Some(TransactionError::AccountNotFound),
+            ..Default::default()
+        };
+        let duplicate = MockRpc {
+            send_error: Some(TransactionError::AlreadyProcessed),
+            ..Default::default()
+        };
+        let rpc = BroadcastSendProvider::new(vec![duplicate, failed]);
+        let tx = TransactionBuilder::default()
+            .with_memo("broadcast", &[&payer])
+            .unsigned_tx(&payer, &rpc)
+            .await?;
+        let result = rpc.send_and_confirm_transaction(&tx, None).await;
+        assert!(matches!(
+            result,
+            Err(Error::TransactionFailed {
+                error: TransactionError::AccountNotFound,
+                ..
+            })
+        ));
+
+        let empty: BroadcastSendProvider<MockRpc> = BroadcastSendProvider::new(vec![]);
+        assert!(empty.get_latest_blockhash().await.is_err());
+        assert!(empty.send_and_confirm_transaction(&tx, None).await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,258 @@
+use {
+    super::{ChaosRpcProvider, Fault, RpcMethod},
+    crate::{Error, Result, TransactionRpcProvider},
+    solana_hash::Hash,
+    solana_message::AddressLookupTableAccount,
+    solana_pubkey::Pubkey,
+    solana_rpc_client::nonblocking::rpc_client::RpcClient,
+    solana_rpc_client_api::response::RpcPrioritizationFee,
+    solana_signature::Signature,
+    std::{future::Future, sync::atomic::Ordering, time::Duration},
+};
+
+impl Fault {
+    /// Delays every call by `latency`
+    pub fn latency(latency: Duration) -> Self {
+        Self {
+            latency,
+            ..Default::default()
+        }
+    }
+
+    /// Fails `percent` of the calls (capped at 100) with an
+    /// [`Error::SolanaRpcError`] carrying `message`
+    pub fn fail(percent: u8, message: impl Into<String>) -> Self {
+        Self {
+            failure_percent: percent.min(100),
+            message: message.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds `latency` to every call, failing or not
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    fn should_fail(&self) -> bool {
+        let rate = u64::from(self.failure_percent);
+        let n = self.calls.fetch_add(1, Ordering::Relaxed);
+        (n + 1) * rate / 100 > n * rate / 100
+    }
+}
+
+impl<T: TransactionRpcProvider> ChaosRpcProvider<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            faults: Default::default(),
+        }
+    }
+
+    /// Injects `fault` into every call of `method`, replacing a previous one
+    pub fn with_fault(mut self, method: RpcMethod, fault: Fault) -> Self {
+        self.faults.insert(method, fault);
+        self
+    }
+
+    async fn inject<R>(
+        &self,
+        method: RpcMethod,
+        call: impl Future<Output = Result<R>>,
+    ) -> Result<R> {
+        if let Some(fault) = self.faults.get(&method) {
+            if !fault.latency.is_zero() {
+                crate::time::sleep(fault.latency).await;
+            }
+            if fault.should_fail() {
+                return Err(Error::SolanaRpcError(format!(
+                    "chaos {method}: {}",
+                    fault.message
+                )));
+            }
+        }
+        call.await
+    }
+}
+
+impl<T: TransactionRpcProvider + AsRef<RpcClient>> AsRef<RpcClient> for ChaosRpcProvider<T> {
+    fn as_ref(&self) -> &RpcClient {
+        self.inner.as_ref()
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: TransactionRpcProvider> TransactionRpcProvider for ChaosRpcProvider<T> {
+    async fn get_recent_prioritization_fees(
+        &self,
+        accounts: &[Pubkey],
+    ) -> Result<Vec<RpcPrioritizationFee>> {
+        self.inject(
+            RpcMethod::Fees,
+            self.inner.get_recent_prioritization_fees(accounts),
+        )
+        .await
+    }
+
+    async fn get_lookup_table_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.inject(
+            RpcMethod::Lookup,
+            self.inner.get_lookup_table_accounts(pubkeys),
+        )
+        .await
+    }
+
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: solana_commitment_config::CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.inject(
+            RpcMethod::Lookup,
+            self.inner
+                .get_lookup_table_accounts_with_commitment(pubkeys, commitment),
+        )
+        .await
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash> {
+        self.inject(RpcMethod::Blockhash, self.inner.get_latest_blockhash())
+            .await
+    }
+
+    async fn simulate_transaction(
+        &self,
+        tx: &solana_transaction::versioned::VersionedTransaction,
+        config: solana_rpc_client_api::config::RpcSimulateTransactionConfig,
+    ) -> Result<solana_rpc_client_api::response::RpcSimulateTransactionResult> {
+        self.inject(
+            RpcMethod::Simulate,
+            self.inner.simulate_transaction(tx, config),
+        )
+        .await
+    }
+
+    async fn send_and_confirm_transaction(
+        &self,
+        tx: &solana_transaction::versioned::VersionedTransaction,
+        config: Option<solana_rpc_client_api::config::RpcSendTransactionConfig>,
+    ) -> Result<Signature> {
+        self.inject(
+            RpcMethod::Send,
+            self.inner.send_and_confirm_transaction(tx, config),
+        )
+        .await
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        self.inject(RpcMethod::Balance, self.inner.get_balance(pubkey))
+            .await
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        self.inject(RpcMethod::Account, self.inner.get_account(pubkey))
+            .await
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        self.inject(
+            RpcMethod::Accounts,
+            self.inner.get_multiple_accounts(pubkeys),
+        )
+        .await
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<solana_rpc_client_api::filter::RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        self.inject(
+            RpcMethod::ProgramAccounts,
+            self.inner.get_program_accounts(program, filters),
+        )
+        .await
+    }
+
+    async fn get_recent_performance_samples(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+        self.inject(
+            RpcMethod::PerfSamples,
+            self.inner.get_recent_performance_samples(limit),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{CounterRpcProvider, rpc::noop::NoopRpcNative},
+        std::time::Instant,
+    };
+
+    #[tokio::test]
+    async fn test_chaos_always_fails() {
+        let counter = CounterRpcProvider::new(NoopRpcNative::default());
+        let fault = Fault::fail(100, "down");
+        let rpc = [
+            RpcMethod::Blockhash,
+            RpcMethod::Lookup,
+            RpcMethod::Fees,
+            RpcMethod::Balance,
+            RpcMethod::Account,
+            RpcMethod::PerfSamples,
+        ]
+        .into_iter()
+        .fold(ChaosRpcProvider::new(counter.clone()), |rpc, method| {
+            rpc.with_fault(method, fault.clone())
+        });
+
+        for _ in 0..5 {
+            assert!(rpc.get_latest_blockhash().await.is_err());
+            assert!(rpc.get_lookup_table_accounts(&[]).await.is_err());
+            assert!(rpc.get_recent_prioritization_fees(&[]).await.is_err());
+            assert!(rpc.get_balance(&Pubkey::default()).await.is_err());
+            assert!(rpc.get_account(&Pubkey::default()).await.is_err());
+            assert!(rpc.get_recent_performance_samples(1).await.is_err());
+        }
+        // failed calls never reach the inner provider
+        assert_eq!(0, counter.get_counter(&RpcMethod::Blockhash));
+        assert_eq!(0, counter.get_counter(&RpcMethod::Fees));
+    }
+
+    #[tokio::test]
+    async fn test_chaos_rate_and_latency() -> anyhow::Result<()> {
+        let rpc = ChaosRpcProvider::new(NoopRpcNative::default())
+            .with_fault(RpcMethod::Blockhash, Fault::fail(25, "flaky"))
+            .with_fault(
+                RpcMethod::Balance,
+                Fault::latency(Duration::from_millis(20)),
+            );
+
+        let mut failures = 0;
+        for _ in 0..8 {
+            if rpc.get_latest_blockhash().await.is_err() {
+                failures += 1;
+            }
+        }
+        assert_eq!(2, failures);
+
+        let started = Instant::now();
+        rpc.get_balance(&Pubkey::default()).await?;
+        assert!(started.elapsed() >= Duration::from_millis(20));
+        // no fault configured
+        rpc.get_recent_prioritization_fees(&[]).await?;
+        Ok(())
+    }
+}
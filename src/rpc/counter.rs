@@ -37,6 +37,24 @@ impl<T: TransactionRpcProvider + AsRef<RpcClient> + Clone> CounterRpcProvider<T>
             *counter.value_mut() = 0;
         }
     }
+
+    /// Multi-line table of the call count per method and their total, for
+    /// CLI output. See the [`Display`] impl for a single line.
+    pub fn report(&self) -> String {
+        let mut counters: Vec<(RpcMethod, u64)> = self
+            .counters
+            .iter()
+            .map(|entry| (*entry.key(), *entry.value()))
+            .collect();
+        counters.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        let total: u64 = counters.iter().map(|(_, count)| count).sum();
+        let mut report = format!("{:<18}{:>10}\n", "method", "calls");
+        for (method, count) in counters {
+            report.push_str(&format!("{:<18}{count:>10}\n", method.to_string()));
+        }
+        report.push_str(&format!("{:<18}{total:>10}\n", "total"));
+        report
+    }
 }
 
 #[async_trait::async_trait]
@@ -59,6 +77,17 @@ impl<T: TransactionRpcProvider + AsRef<RpcClient> + Clone> TransactionRpcProvide
         self.inner.get_lookup_table_accounts(pubkeys).await
     }
 
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: solana_commitment_config::CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        *self.counters.get_mut(&RpcMethod::Lookup).unwrap() += 1;
+        self.inner
+            .get_lookup_table_accounts_with_commitment(pubkeys, commitment)
+            .await
+    }
+
     async fn get_latest_blockhash(&self) -> Result<Hash> {
         *self.counters.get_mut(&RpcMethod::Blockhash).unwrap() += 1;
         self.inner.get_latest_blockhash().await
@@ -81,4 +110,61 @@ impl<T: TransactionRpcProvider + AsRef<RpcClient> + Clone> TransactionRpcProvide
         *self.counters.get_mut(&RpcMethod::Send).unwrap() += 1;
         self.inner.send_and_confirm_transaction(tx, config).await
     }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        *self.counters.get_mut(&RpcMethod::Balance).unwrap() += 1;
+        self.inner.get_balance(pubkey).await
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        *self.counters.get_mut(&RpcMethod::Account).unwrap() += 1;
+        self.inner.get_account(pubkey).await
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        *self.counters.get_mut(&RpcMethod::Accounts).unwrap() += 1;
+        self.inner.get_multiple_accounts(pubkeys).await
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<solana_rpc_client_api::filter::RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        *self.counters.get_mut(&RpcMethod::ProgramAccounts).unwrap() += 1;
+        self.inner.get_program_accounts(program, filters).await
+    }
+
+    async fn get_recent_performance_samples(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+        *self.counters.get_mut(&RpcMethod::PerfSamples).unwrap() += 1;
+        self.inner.get_recent_performance_samples(limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::rpc::noop::NoopRpcNative};
+
+    #[tokio::test]
+    async fn test_report() -> anyhow::Result<()> {
+        let rpc = CounterRpcProvider::new(NoopRpcNative::default());
+        rpc.get_latest_blockhash().await?;
+        rpc.get_latest_blockhash().await?;
+        rpc.get_balance(&Pubkey::new_unique()).await?;
+
+        let report = rpc.report();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(12, lines.len(), "{report}");
+        assert!(lines.contains(&format!("{:<18}{:>10}", "blockhash", 2).as_str()));
+        assert!(lines.contains(&format!("{:<18}{:>10}", "balance", 1).as_str()));
+        assert!(lines.contains(&format!("{:<18}{:>10}", "send", 0).as_str()));
+        assert_eq!(format!("{:<18}{:>10}", "total", 3), lines[11]);
+        Ok(())
+    }
 }
@@ -0,0 +1,174 @@
+use {
+    super::FeeCacheProvider,
+    crate::{Result, TransactionRpcProvider},
+    moka::future::Cache,
+    solana_hash::Hash,
+    solana_message::AddressLookupTableAccount,
+    solana_pubkey::Pubkey,
+    solana_rpc_client::nonblocking::rpc_client::RpcClient,
+    solana_rpc_client_api::{
+        config::RpcSimulateTransactionConfig,
+        response::{RpcPrioritizationFee, RpcSimulateTransactionResult},
+    },
+    solana_signature::Signature,
+    solana_transaction::versioned::VersionedTransaction,
+    std::time::Duration,
+    tracing::{Level, event},
+};
+
+impl<T: TransactionRpcProvider> FeeCacheProvider<T> {
+    /// Caches up to 1,000 account sets, see
+    /// [`FeeCacheProvider::with_capacity`]
+    pub fn new(inner: T, ttl: Duration) -> Self {
+        Self::with_capacity(inner, ttl, 1_000)
+    }
+
+    /// Caches up to `max_capacity` account sets, evicting the least used
+    pub fn with_capacity(inner: T, ttl: Duration, max_capacity: u64) -> Self {
+        Self {
+            inner,
+            fees: Cache::builder()
+                .time_to_live(ttl)
+                .max_capacity(max_capacity)
+                .build(),
+        }
+    }
+
+    /// Hash of the sorted, deduplicated accounts, so the same set in any order
+    /// shares an entry
+    fn key(accounts: &[Pubkey]) -> Hash {
+        let mut sorted = accounts.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        let bytes: Vec<&[u8]> = sorted.iter().map(|pubkey| pubkey.as_ref()).collect();
+        solana_sha256_hasher::hashv(&bytes)
+    }
+}
+
+impl<T: TransactionRpcProvider + AsRef<RpcClient>> AsRef<RpcClient> for FeeCacheProvider<T> {
+    fn as_ref(&self) -> &RpcClient {
+        self.inner.as_ref()
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: TransactionRpcProvider> TransactionRpcProvider for FeeCacheProvider<T> {
+    async fn get_recent_prioritization_fees(
+        &self,
+        accounts: &[Pubkey],
+    ) -> Result<Vec<RpcPrioritizationFee>> {
+        self.fees
+            .try_get_with(Self::key(accounts), async {
+                event!(Level::DEBUG, accounts = accounts.len(), "fee cache miss");
+                self.inner.get_recent_prioritization_fees(accounts).await
+            })
+            .await
+            .map_err(|arc_err| match std::sync::Arc::try_unwrap(arc_err) {
+                Ok(e) => e,
+                Err(arc) => crate::Error::MokaCacheError(arc.to_string()),
+            })
+    }
+
+    async fn get_lookup_table_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.inner.get_lookup_table_accounts(pubkeys).await
+    }
+
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: solana_commitment_config::CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.inner
+            .get_lookup_table_accounts_with_commitment(pubkeys, commitment)
+            .await
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash> {
+        self.inner.get_latest_blockhash().await
+    }
+
+    async fn simulate_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        config: RpcSimulateTransactionConfig,
+    ) -> Result<RpcSimulateTransactionResult> {
+        self.inner.simulate_transaction(tx, config).await
+    }
+
+    async fn send_and_confirm_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        config: Option<solana_rpc_client_api::config::RpcSendTransactionConfig>,
+    ) -> Result<Signature> {
+        self.inner.send_and_confirm_transaction(tx, config).await
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        self.inner.get_balance(pubkey).await
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        self.inner.get_account(pubkey).await
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        self.inner.get_multiple_accounts(pubkeys).await
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<solana_rpc_client_api::filter::RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        self.inner.get_program_accounts(program, filters).await
+    }
+
+    async fn get_recent_performance_samples(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+        self.inner.get_recent_performance_samples(limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{CounterRpcProvider, RpcMethod, rpc::mock::MockRpc},
+    };
+
+    #[tokio::test]
+    async fn test_fee_cache_account_sets() -> anyhow::Result<()> {
+        let counter = CounterRpcProvider::new(MockRpc {
+            prioritization_fees: vec![100, 200],
+            ..Default::default()
+        });
+        let rpc = FeeCacheProvider::new(counter.clone(), Duration::from_secs(5));
+        let (hot, cold, other) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+
+        let fees = rpc.get_recent_prioritization_fees(&[hot, cold]).await?;
+        assert_eq!(2, fees.len());
+        // same set, in another order and with duplicates
+        rpc.get_recent_prioritization_fees(&[cold, hot, cold])
+            .await?;
+        assert_eq!(1, counter.get_counter(&RpcMethod::Fees));
+
+        rpc.get_recent_prioritization_fees(&[cold]).await?;
+        rpc.get_recent_prioritization_fees(&[cold, other]).await?;
+        assert_eq!(3, counter.get_counter(&RpcMethod::Fees));
+        rpc.fees.run_pending_tasks().await;
+        assert_eq!(3, rpc.fees.entry_count());
+        Ok(())
+    }
+}
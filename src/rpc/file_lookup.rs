@@ -0,0 +1,210 @@
+use {
+    super::FileLookupProvider,
+    crate::{Result, TransactionRpcProvider},
+    serde::Deserialize,
+    solana_hash::Hash,
+    solana_message::AddressLookupTableAccount,
+    solana_pubkey::Pubkey,
+    solana_rpc_client::nonblocking::rpc_client::RpcClient,
+    solana_rpc_client_api::response::RpcPrioritizationFee,
+    solana_signature::Signature,
+    std::{path::Path, str::FromStr, sync::Arc},
+};
+
+/// A table in the JSON file, keys are base58 encoded
+#[derive(Deserialize)]
+struct JsonLookupTable {
+    key: String,
+    addresses: Vec<String>,
+}
+
+impl TryFrom<JsonLookupTable> for AddressLookupTableAccount {
+    type Error = crate::Error;
+
+    fn try_from(table: JsonLookupTable) -> Result<Self> {
+        Ok(Self {
+            key: Pubkey::from_str(&table.key)?,
+            addresses: table
+                .addresses
+                .iter()
+                .map(|address| Pubkey::from_str(address))
+                .collect::<std::result::Result<_, _>>()?,
+        })
+    }
+}
+
+impl<T: TransactionRpcProvider> FileLookupProvider<T> {
+    pub fn new(inner: T, tables: impl IntoIterator<Item = AddressLookupTableAccount>) -> Self {
+        Self {
+            inner,
+            tables: Arc::new(tables.into_iter().map(|t| (t.key, t)).collect()),
+        }
+    }
+
+    /// Loads a JSON array of `{"key": "...", "addresses": ["..."]}` objects
+    /// with base58 encoded keys.
+    pub fn from_json_file(inner: T, path: impl AsRef<Path>) -> Result<Self> {
+        let tables: Vec<JsonLookupTable> = serde_json::from_slice(&std::fs::read(path)?)?;
+        let tables = tables
+            .into_iter()
+            .map(AddressLookupTableAccount::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::new(inner, tables))
+    }
+
+    /// Loads a bincode serialized `Vec<(Pubkey, Vec<Pubkey>)>` of table keys
+    /// and their addresses.
+    pub fn from_bincode_file(inner: T, path: impl AsRef<Path>) -> Result<Self> {
+        let tables: Vec<(Pubkey, Vec<Pubkey>)> = bincode::deserialize(&std::fs::read(path)?)?;
+        Ok(Self::new(
+            inner,
+            tables
+                .into_iter()
+                .map(|(key, addresses)| AddressLookupTableAccount { key, addresses }),
+        ))
+    }
+
+    /// Number of tables loaded
+    pub fn len(&self) -> usize {
+        self.tables.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+}
+
+impl<T: TransactionRpcProvider + AsRef<RpcClient>> AsRef<RpcClient> for FileLookupProvider<T> {
+    fn as_ref(&self) -> &RpcClient {
+        self.inner.as_ref()
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: TransactionRpcProvider> TransactionRpcProvider for FileLookupProvider<T> {
+    async fn get_recent_prioritization_fees(
+        &self,
+        accounts: &[Pubkey],
+    ) -> Result<Vec<RpcPrioritizationFee>> {
+        self.inner.get_recent_prioritization_fees(accounts).await
+    }
+
+    async fn get_lookup_table_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        Ok(pubkeys
+            .iter()
+            .filter_map(|key| self.tables.get(key))
+            .cloned()
+            .collect())
+    }
+
+    /// The tables are read from the file, `commitment` does not apply
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        _commitment: solana_commitment_config::CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.get_lookup_table_accounts(pubkeys).await
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash> {
+        self.inner.get_latest_blockhash().await
+    }
+
+    async fn simulate_transaction(
+        &self,
+        tx: &solana_transaction::versioned::VersionedTransaction,
+        config: solana_rpc_client_api::config::RpcSimulateTransactionConfig,
+    ) -> Result<solana_rpc_client_api::response::RpcSimulateTransactionResult> {
+        self.inner.simulate_transaction(tx, config).await
+    }
+
+    async fn send_and_confirm_transaction(
+        &self,
+        tx: &solana_transaction::versioned::VersionedTransaction,
+        config: Option<solana_rpc_client_api::config::RpcSendTransactionConfig>,
+    ) -> Result<Signature> {
+        self.inner.send_and_confirm_transaction(tx, config).await
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        self.inner.get_balance(pubkey).await
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        self.inner.get_account(pubkey).await
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        self.inner.get_multiple_accounts(pubkeys).await
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<solana_rpc_client_api::filter::RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        self.inner.get_program_accounts(program, filters).await
+    }
+
+    async fn get_recent_performance_samples(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+        self.inner.get_recent_performance_samples(limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{CounterRpcProvider, RpcMethod, rpc::noop::NoopRpcNative},
+    };
+
+    #[tokio::test]
+    async fn test_file_lookup_json() -> anyhow::Result<()> {
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        };
+        let json = serde_json::json!([{
+            "key": table.key.to_string(),
+            "addresses": table.addresses.iter().map(Pubkey::to_string).collect::<Vec<_>>(),
+        }]);
+        let path = std::env::temp_dir().join(format!("soly-lookup-{}.json", table.key));
+        std::fs::write(&path, json.to_string())?;
+
+        let counter = CounterRpcProvider::new(NoopRpcNative::default());
+        let rpc = FileLookupProvider::from_json_file(counter.clone(), &path);
+        std::fs::remove_file(&path)?;
+        let rpc = rpc?;
+        assert_eq!(1, rpc.len());
+
+        let unknown = Pubkey::new_unique();
+        let tables = rpc.get_lookup_table_accounts(&[unknown, table.key]).await?;
+        assert_eq!(vec![table], tables);
+        assert!(rpc.get_lookup_table_accounts(&[unknown]).await?.is_empty());
+        assert_eq!(0, counter.get_counter(&RpcMethod::Lookup));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_lookup_bincode() -> anyhow::Result<()> {
+        let key = Pubkey::new_unique();
+        let addresses = vec![Pubkey::new_unique()];
+        let path = std::env::temp_dir().join(format!("soly-lookup-{key}.bin"));
+        std::fs::write(&path, bincode::serialize(&vec![(key, addresses.clone())])?)?;
+
+        let rpc = FileLookupProvider::from_bincode_file(NoopRpcNative::default(), &path);
+        std::fs::remove_file(&path)?;
+        let tables = rpc?.get_lookup_table_accounts(&[key]).await?;
+        assert_eq!(vec![AddressLookupTableAccount { key, addresses }], tables);
+        Ok(())
+    }
+}
@@ -1,28 +1,156 @@
 use {
-    super::LookupTableCacheProvider,
+    super::{CacheStats, LookupCacheStore, LookupTableCacheProvider, PrewarmResult},
     crate::{Result, TransactionRpcProvider},
+    futures::{
+        StreamExt,
+        channel::oneshot,
+        future::{BoxFuture, FutureExt, Shared, join_all},
+        stream,
+    },
     moka::future::Cache,
+    solana_commitment_config::CommitmentConfig,
     solana_hash::Hash,
     solana_message::AddressLookupTableAccount,
     solana_pubkey::Pubkey,
     solana_rpc_client_api::response::RpcPrioritizationFee,
     solana_signature::Signature,
-    tracing::{Level, enabled, event, info_span},
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+    tracing::{Instrument, Level, Span, debug, enabled, event, info_span},
 };
 
-impl<T: TransactionRpcProvider> LookupTableCacheProvider<T> {
-    pub fn new(
-        client: T,
-        lookup_cache: Cache<Pubkey, AddressLookupTableAccount>,
-        negative_cache: Cache<Pubkey, ()>,
-    ) -> Self {
+#[async_trait::async_trait]
+impl<V: Clone + Send + Sync + 'static> LookupCacheStore<V> for Cache<Pubkey, V> {
+    async fn get(&self, key: &Pubkey) -> Option<V> {
+        Cache::get(self, key).await
+    }
+
+    async fn insert(&self, key: Pubkey, value: V) {
+        Cache::insert(self, key, value).await
+    }
+
+    async fn try_get_with(&self, key: Pubkey, init: BoxFuture<'_, Result<V>>) -> Result<V> {
+        Cache::try_get_with(self, key, init)
+            .await
+            .map_err(|arc_err| match Arc::try_unwrap(arc_err) {
+                Ok(e) => e,
+                // every caller sharing a miss gets it
+                Err(arc) => match *arc {
+                    crate::Error::LookupTableMiss => crate::Error::LookupTableMiss,
+                    _ => crate::Error::MokaCacheError(arc.to_string()),
+                },
+            })
+    }
+
+    async fn invalidate(&self, key: &Pubkey) -> bool {
+        self.remove(key).await.is_some()
+    }
+
+    async fn invalidate_all(&self) {
+        Cache::invalidate_all(self)
+    }
+
+    fn entry_count(&self) -> u64 {
+        Cache::entry_count(self)
+    }
+
+    async fn run_pending_tasks(&self) {
+        Cache::run_pending_tasks(self).await
+    }
+}
+
+type Flight = Shared<oneshot::Receiver<Option<AddressLookupTableAccount>>>;
+
+/// Lookup tables being fetched by a [`LookupTableCacheProvider`] call, which
+/// concurrent calls missing the same table wait for instead of fetching it
+#[derive(Default)]
+pub(crate) struct InFlight(Mutex<HashMap<Pubkey, Flight>>);
+
+impl InFlight {
+    /// Splits `misses` into the tables already in flight and the ones the
+    /// caller now fetches, registered until the returned [`Leading`] drops
+    fn join(&self, misses: &[Pubkey]) -> (Vec<(Pubkey, Flight)>, Leading<'_>) {
+        let mut flights = self.0.lock().expect("in flight lock");
+        let mut joined = Vec::new();
+        let mut leading = Leading {
+            in_flight: self,
+            senders: Vec::new(),
+        };
+        for pubkey in misses {
+            match flights.get(pubkey) {
+                Some(flight) => joined.push((*pubkey, flight.clone())),
+                None => {
+                    let (sender, receiver) = oneshot::channel();
+                    flights.insert(*pubkey, receiver.shared());
+                    leading.senders.push((*pubkey, sender));
+                }
+            }
+        }
+        (joined, leading)
+    }
+}
+
+/// Tables fetched by this call. Dropping it unregisters them, waiters of a
+/// table without a result fetch it themselves.
+struct Leading<'a> {
+    in_flight: &'a InFlight,
+    senders: Vec<(Pubkey, oneshot::Sender<Option<AddressLookupTableAccount>>)>,
+}
+
+impl Leading<'_> {
+    fn pubkeys(&self) -> Vec<Pubkey> {
+        self.senders.iter().map(|(pubkey, _)| *pubkey).collect()
+    }
+
+    /// Hands the fetched tables to the waiters, after they were cached
+    fn complete(mut self, fetched: &[AddressLookupTableAccount]) {
+        self.unregister();
+        for (pubkey, sender) in self.senders.drain(..) {
+            let table = fetched.iter().find(|table| table.key == pubkey);
+            let _ = sender.send(table.cloned());
+        }
+    }
+
+    fn unregister(&self) {
+        let mut flights = self.in_flight.0.lock().expect("in flight lock");
+        for (pubkey, _) in &self.senders {
+            flights.remove(pubkey);
+        }
+    }
+}
+
+impl Drop for Leading<'_> {
+    fn drop(&mut self) {
+        self.unregister();
+    }
+}
+
+impl<T, L, N> LookupTableCacheProvider<T, L, N>
+where
+    T: TransactionRpcProvider,
+    L: LookupCacheStore<AddressLookupTableAccount>,
+    N: LookupCacheStore<Instant>,
+{
+    pub fn new(client: T, lookup_cache: L, negative_cache: N) -> Self {
         Self {
             inner: client,
             lookup_cache,
             negative_cache,
+            name: None,
+            commitment: None,
+            stats: Default::default(),
+            in_flight: Default::default(),
         }
     }
 
+    /// The name set with the builder, if any
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
     /// Checks if the lookup table cache is empty.
     ///
     /// **Note:** This method does not run pending tasks on the caches.
@@ -57,6 +185,18 @@ impl<T: TransactionRpcProvider> LookupTableCacheProvider<T> {
         self.negative_cache.entry_count()
     }
 
+    /// Time since `pubkey` was last recorded as a miss, `None` when it is not
+    /// in the negative cache (never missed, or expired).
+    ///
+    /// Lets callers re-query misses older than their own threshold, even
+    /// within the cache TTL.
+    pub async fn negative_age(&self, pubkey: &Pubkey) -> Option<Duration> {
+        self.negative_cache
+            .get(pubkey)
+            .await
+            .map(|recorded| recorded.elapsed())
+    }
+
     /// Returns the total number of entries in both lookup table and negative
     /// caches.
     ///
@@ -74,15 +214,54 @@ impl<T: TransactionRpcProvider> LookupTableCacheProvider<T> {
     }
 
     pub async fn clear_lookups(&self) {
-        self.lookup_cache.invalidate_all();
+        self.lookup_cache.run_pending_tasks().await;
+        self.stats.evicted(self.lookup_cache.entry_count());
+        self.lookup_cache.invalidate_all().await;
         self.lookup_cache.run_pending_tasks().await;
     }
 
     pub async fn clear_negative(&self) {
-        self.negative_cache.invalidate_all();
+        self.negative_cache.run_pending_tasks().await;
+        self.stats.evicted(self.negative_cache.entry_count());
+        self.negative_cache.invalidate_all().await;
         self.negative_cache.run_pending_tasks().await;
     }
 
+    /// Evicts the cached `pubkey` table, e.g. after extending it, so the next
+    /// lookup fetches it again
+    pub async fn invalidate(&self, pubkey: &Pubkey) {
+        if self.lookup_cache.invalidate(pubkey).await {
+            self.stats.evicted(1);
+        }
+    }
+
+    /// Forgets the recorded miss of `pubkey`, e.g. after creating the table
+    pub async fn invalidate_negative(&self, pubkey: &Pubkey) {
+        if self.negative_cache.invalidate(pubkey).await {
+            self.stats.evicted(1);
+        }
+    }
+
+    /// Hits and misses of the lookup cache since creation.
+    ///
+    /// The caches are built by the caller, so only entries removed with
+    /// [`LookupTableCacheProvider::invalidate`],
+    /// [`LookupTableCacheProvider::invalidate_negative`] or the `clear_*`
+    /// methods count as evictions, not TTL expirations.
+    pub fn stats(&self) -> CacheStats {
+        self.stats.snapshot()
+    }
+
+    /// Whether the `pubkey` table is cached
+    pub async fn contains(&self, pubkey: &Pubkey) -> bool {
+        self.lookup_cache.get(pubkey).await.is_some()
+    }
+
+    /// The cached `pubkey` table, without fetching it on a miss
+    pub async fn get_cached(&self, pubkey: &Pubkey) -> Option<AddressLookupTableAccount> {
+        self.lookup_cache.get(pubkey).await
+    }
+
     /// Runs pending tasks on both caches to ensure counts are accurate.
     /// This is needed because moka cache uses eventual consistency for
     /// entry_count.
@@ -90,58 +269,218 @@ impl<T: TransactionRpcProvider> LookupTableCacheProvider<T> {
         self.lookup_cache.run_pending_tasks().await;
         self.negative_cache.run_pending_tasks().await;
     }
+
+    /// Warms the caches by resolving `pubkeys` with at most `concurrency`
+    /// requests in flight.
+    ///
+    /// Each table is inserted into the lookup (or negative) cache as soon as
+    /// it resolves. A `concurrency` of `0` is treated as `1`.
+    pub async fn prewarm(&self, pubkeys: &[Pubkey], concurrency: usize) -> Result<PrewarmResult> {
+        let mut resolving = stream::iter(pubkeys.iter().copied())
+            .map(|pubkey| async move {
+                (
+                    pubkey,
+                    self.try_get_lookup_account(pubkey, self.commitment).await,
+                )
+            })
+            .buffer_unordered(concurrency.max(1));
+
+        let mut result = PrewarmResult::default();
+        while let Some((pubkey, resolved)) = resolving.next().await {
+            match resolved {
+                Ok(_) => result.hits += 1,
+                Err(crate::Error::LookupTableMiss) => {
+                    self.negative_cache.insert(pubkey, Instant::now()).await;
+                    result.misses += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        debug!(
+            name = self.name(),
+            hits = result.hits,
+            misses = result.misses,
+            "prewarm complete"
+        );
+        Ok(result)
+    }
 }
 
-impl<T: TransactionRpcProvider> LookupTableCacheProvider<T> {
-    /// Helper function to fetch a single lookup table account with proper error
-    /// handling
-    async fn try_get_lookup_account(&self, pubkey: Pubkey) -> Result<AddressLookupTableAccount> {
-        let span = if enabled!(Level::TRACE) {
+impl<T, L, N> LookupTableCacheProvider<T, L, N>
+where
+    T: TransactionRpcProvider,
+    L: LookupCacheStore<AddressLookupTableAccount>,
+    N: LookupCacheStore<Instant>,
+{
+    /// The `lookup-resolver` span, with cache stats at TRACE level
+    async fn resolver_span(&self, lookups: &[Pubkey]) -> Span {
+        if enabled!(Level::TRACE) {
             self.sync().await; // to get accurate cache stats
             let cached_lookups = self.len();
             let cached_negatives = self.len_negative();
-            info_span!("lookup-resolver", lookup = ?pubkey, cached_lookups, cached_negatives)
+            info_span!(
+                "lookup-resolver",
+                name = self.name(),
+                lookups = ?lookups,
+                cached_lookups,
+                cached_negatives
+            )
         } else {
-            info_span!("lookup-resolver", lookup = ?pubkey)
-        };
-        let _guard = span.enter();
-
-        self.lookup_cache
-            .try_get_with(pubkey, async {
-                event!(Level::INFO, "cache-miss");
-                let results = self.inner.get_lookup_table_accounts(&[pubkey]).await?;
-                if results.is_empty() {
-                    event!(Level::INFO, "no-lookup-table");
-                    Err(crate::Error::LookupTableMiss)
-                } else {
-                    Ok(results[0].to_owned())
-                }
-            })
-            .await
-            .map_err(Self::handle_cache_error)
+            info_span!("lookup-resolver", name = self.name(), lookups = ?lookups)
+        }
     }
 
-    /// Converts moka cache Arc errors to application errors
-    fn handle_cache_error(arc_err: std::sync::Arc<crate::Error>) -> crate::Error {
-        match std::sync::Arc::try_unwrap(arc_err) {
-            Ok(err) => {
-                event!(Level::ERROR, "cache error: {err}");
-                err
+    /// Fetches `pubkeys` from the inner provider at `commitment`, or its own
+    /// when `None`
+    async fn fetch(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: Option<CommitmentConfig>,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        match commitment {
+            Some(commitment) => {
+                self.inner
+                    .get_lookup_table_accounts_with_commitment(pubkeys, commitment)
+                    .await
             }
-            Err(arc) => {
-                // Arc couldn't be unwrapped, extract the error type
-                match &*arc {
-                    crate::Error::LookupTableMiss => crate::Error::LookupTableMiss,
-                    _ => crate::Error::MokaCacheError(arc.to_string()),
+            None => self.inner.get_lookup_table_accounts(pubkeys).await,
+        }
+    }
+
+    /// Helper function to fetch a single lookup table account with proper error
+    /// handling
+    async fn try_get_lookup_account(
+        &self,
+        pubkey: Pubkey,
+        commitment: Option<CommitmentConfig>,
+    ) -> Result<AddressLookupTableAccount> {
+        let span = self.resolver_span(&[pubkey]).await;
+        async {
+            let mut missed = false;
+            let account = self
+                .lookup_cache
+                .try_get_with(
+                    pubkey,
+                    Box::pin(async {
+                        missed = true;
+                        event!(Level::INFO, "cache-miss");
+                        self.record_miss(&pubkey).await;
+                        match self.fetch(&[pubkey], commitment).await?.into_iter().next() {
+                            Some(account) => Ok(account),
+                            None => {
+                                event!(Level::INFO, "no-lookup-table");
+                                Err(crate::Error::LookupTableMiss)
+                            }
+                        }
+                    }),
+                )
+                .await;
+            if !missed && account.is_ok() {
+                self.stats.hit();
+            }
+            account
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Counts a fetch, and a negative hit when `pubkey` already missed
+    async fn record_miss(&self, pubkey: &Pubkey) {
+        self.stats.miss();
+        if self.negative_cache.get(pubkey).await.is_some() {
+            self.stats.negative_hit();
+        }
+    }
+
+    /// Resolves `pubkeys` through the caches, fetching all misses in one
+    /// inner call at `commitment`, or the inner provider's when `None`.
+    /// Misses already being fetched by a concurrent call are awaited instead.
+    async fn resolve(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: Option<CommitmentConfig>,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        let mut resolved = Vec::with_capacity(pubkeys.len());
+        let mut misses = Vec::new();
+        for pubkey in pubkeys {
+            match self.lookup_cache.get(pubkey).await {
+                Some(account) => {
+                    self.stats.hit();
+                    resolved.push(account)
                 }
+                None if !misses.contains(pubkey) => {
+                    self.record_miss(pubkey).await;
+                    misses.push(*pubkey)
+                }
+                None => {}
             }
         }
+        if misses.is_empty() {
+            return Ok(resolved);
+        }
+
+        let span = self.resolver_span(&misses).await;
+        async {
+            let (joined, leading) = self.in_flight.join(&misses);
+            let fetching = leading.pubkeys();
+            if !fetching.is_empty() {
+                event!(Level::INFO, misses = fetching.len(), "cache-miss");
+                let fetched = self.fetch_and_cache(&fetching, commitment).await?;
+                leading.complete(&fetched);
+                resolved.extend(fetched);
+            }
+
+            let mut refetch = Vec::new();
+            let (pubkeys, flights): (Vec<_>, Vec<_>) = joined.into_iter().unzip();
+            for (pubkey, landed) in pubkeys.into_iter().zip(join_all(flights).await) {
+                match landed {
+                    Ok(table) => resolved.extend(table),
+                    // the fetching call failed or was dropped
+                    Err(oneshot::Canceled) => refetch.push(pubkey),
+                }
+            }
+            if !refetch.is_empty() {
+                resolved.extend(self.fetch_and_cache(&refetch, commitment).await?);
+            }
+            Ok::<_, crate::Error>(())
+        }
+        .instrument(span)
+        .await?;
+
+        Ok(resolved)
+    }
+
+    /// Fetches `pubkeys`, caching the found tables and recording the others
+    /// as misses
+    async fn fetch_and_cache(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: Option<CommitmentConfig>,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        let fetched = self.fetch(pubkeys, commitment).await?;
+        let mut found = Vec::with_capacity(fetched.len());
+        for pubkey in pubkeys {
+            match fetched.iter().find(|table| table.key == *pubkey) {
+                Some(table) => {
+                    self.lookup_cache.insert(*pubkey, table.clone()).await;
+                    found.push(table.clone());
+                }
+                None => {
+                    event!(Level::INFO, lookup = ?pubkey, "no-lookup-table");
+                    self.negative_cache.insert(*pubkey, Instant::now()).await
+                }
+            }
+        }
+        Ok(found)
     }
 }
 
 #[async_trait::async_trait]
-impl<T: TransactionRpcProvider + Send + Sync> TransactionRpcProvider
-    for LookupTableCacheProvider<T>
+impl<T, L, N> TransactionRpcProvider for LookupTableCacheProvider<T, L, N>
+where
+    T: TransactionRpcProvider + Send + Sync,
+    L: LookupCacheStore<AddressLookupTableAccount>,
+    N: LookupCacheStore<Instant>,
 {
     async fn get_recent_prioritization_fees(
         &self,
@@ -161,17 +500,17 @@ impl<T: TransactionRpcProvider + Send + Sync> TransactionRpcProvider
         &self,
         pubkeys: &[Pubkey],
     ) -> Result<Vec<AddressLookupTableAccount>> {
-        let mut resolved = Vec::with_capacity(pubkeys.len());
-
-        for &pubkey in pubkeys {
-            match self.try_get_lookup_account(pubkey).await {
-                Ok(account) => resolved.push(account),
-                Err(crate::Error::LookupTableMiss) => self.negative_cache.insert(pubkey, ()).await,
-                Err(err) => return Err(err),
-            }
-        }
+        self.resolve(pubkeys, self.commitment).await
+    }
 
-        Ok(resolved)
+    /// Like [`LookupTableCacheProvider::get_lookup_table_accounts`], fetching
+    /// misses at `commitment` instead of the configured one
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.resolve(pubkeys, Some(commitment)).await
     }
 
     async fn get_latest_blockhash(&self) -> Result<Hash> {
@@ -193,6 +532,36 @@ impl<T: TransactionRpcProvider + Send + Sync> TransactionRpcProvider
     ) -> Result<Signature> {
         self.inner.send_and_confirm_transaction(tx, config).await
     }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        self.inner.get_balance(pubkey).await
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        self.inner.get_account(pubkey).await
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        self.inner.get_multiple_accounts(pubkeys).await
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<solana_rpc_client_api::filter::RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        self.inner.get_program_accounts(program, filters).await
+    }
+
+    async fn get_recent_performance_samples(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+        self.inner.get_recent_performance_samples(limit).await
+    }
 }
 
 #[cfg(test)]
@@ -201,20 +570,33 @@ mod tests {
     use {
         super::*,
         crate::{
+            CounterRpcProvider,
+            RpcMethod,
             TransactionRpcProvider,
-            rpc::noop::{NoopRpc, NoopRpcNative},
+            rpc::{
+                mock::{MockRpc, capture_logs},
+                noop::NoopRpcNative,
+            },
         },
         dashmap::DashMap,
         solana_keypair::Keypair,
         solana_signer::Signer,
-        std::{fmt::Debug, sync::Arc, time::Duration},
+        std::{
+            fmt::Debug,
+            sync::{
+                Arc,
+                atomic::{AtomicUsize, Ordering},
+            },
+        },
         tokio::time::sleep,
     };
 
-    #[derive(Clone)]
+    #[derive(Clone, Default)]
     struct MockRpcProvider {
         inner: NoopRpcNative,
         lookups: Arc<DashMap<Pubkey, AddressLookupTableAccount>>,
+        in_flight: Arc<AtomicUsize>,
+        max_in_flight: Arc<AtomicUsize>,
     }
 
     impl Debug for MockRpcProvider {
@@ -236,15 +618,27 @@ mod tests {
             &self,
             pubkeys: &[Pubkey],
         ) -> Result<Vec<AddressLookupTableAccount>> {
+            let current = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(current, Ordering::SeqCst);
+            sleep(Duration::from_millis(5)).await;
             let mut result = Vec::new();
             for pubkey in pubkeys {
                 if let Some(lookup) = self.lookups.get(pubkey) {
                     result.push(lookup.clone());
                 }
             }
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
             Ok(result)
         }
 
+        async fn get_lookup_table_accounts_with_commitment(
+            &self,
+            pubkeys: &[Pubkey],
+            _commitment: CommitmentConfig,
+        ) -> Result<Vec<AddressLookupTableAccount>> {
+            self.get_lookup_table_accounts(pubkeys).await
+        }
+
         async fn get_latest_blockhash(&self) -> Result<Hash> {
             self.inner.get_latest_blockhash().await
         }
@@ -264,15 +658,42 @@ mod tests {
         ) -> Result<Signature> {
             self.inner.send_and_confirm_transaction(tx, config).await
         }
+
+        async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+            self.inner.get_balance(pubkey).await
+        }
+
+        async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+            self.inner.get_account(pubkey).await
+        }
+
+        async fn get_multiple_accounts(
+            &self,
+            pubkeys: &[Pubkey],
+        ) -> Result<Vec<Option<solana_account::Account>>> {
+            self.inner.get_multiple_accounts(pubkeys).await
+        }
+
+        async fn get_program_accounts(
+            &self,
+            program: &Pubkey,
+            filters: Vec<solana_rpc_client_api::filter::RpcFilterType>,
+        ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+            self.inner.get_program_accounts(program, filters).await
+        }
+
+        async fn get_recent_performance_samples(
+            &self,
+            limit: usize,
+        ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+            self.inner.get_recent_performance_samples(limit).await
+        }
     }
 
     #[tokio::test]
     #[test_log::test]
     async fn test_lookup_cache() -> anyhow::Result<()> {
-        let mock = MockRpcProvider {
-            inner: NoopRpc::default(),
-            lookups: Arc::new(DashMap::new()),
-        };
+        let mock = MockRpcProvider::default();
 
         let lookup_cache = LookupTableCacheProvider::new(
             mock.clone(),
@@ -328,4 +749,385 @@ mod tests {
         assert!(lookup_cache.is_empty_negative());
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_negative_age() -> anyhow::Result<()> {
+        let mock = MockRpcProvider::default();
+        let hit = Pubkey::new_unique();
+        let miss = Pubkey::new_unique();
+        mock.lookups.insert(hit, AddressLookupTableAccount {
+            addresses: vec![hit],
+            key: hit,
+        });
+        let lookup_cache =
+            LookupTableCacheProvider::new(mock, Cache::builder().build(), Cache::builder().build());
+        assert_eq!(None, lookup_cache.negative_age(&miss).await);
+
+        lookup_cache.get_lookup_table_accounts(&[hit, miss]).await?;
+        sleep(Duration::from_millis(50)).await;
+        let age = lookup_cache
+            .negative_age(&miss)
+            .await
+            .expect("miss recorded");
+        assert!(age >= Duration::from_millis(50), "age {age:?}");
+        assert!(age < Duration::from_secs(5), "age {age:?}");
+        assert_eq!(None, lookup_cache.negative_age(&hit).await);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_prewarm_concurrency() -> anyhow::Result<()> {
+        let mock = MockRpcProvider::default();
+        let keys: Vec<Pubkey> = (0..50).map(|_| Pubkey::new_unique()).collect();
+        for key in keys.iter().take(40) {
+            mock.lookups.insert(*key, AddressLookupTableAccount {
+                addresses: vec![*key],
+                key: *key,
+            });
+        }
+
+        let lookup_cache = LookupTableCacheProvider::new(
+            mock.clone(),
+            Cache::builder().build(),
+            Cache::builder().build(),
+        );
+        let result = lookup_cache.prewarm(&keys, 4).await?;
+        lookup_cache.sync().await;
+
+        assert_eq!(
+            PrewarmResult {
+                hits: 40,
+                misses: 10
+            },
+            result
+        );
+        assert_eq!(40, lookup_cache.len());
+        assert_eq!(10, lookup_cache.len_negative());
+        let max_in_flight = mock.max_in_flight.load(Ordering::SeqCst);
+        assert!(max_in_flight <= 4, "max in flight {max_in_flight}");
+        assert!(max_in_flight > 1, "prewarm did not run concurrently");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_prewarm_single_flight() -> anyhow::Result<()> {
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![Pubkey::new_unique()],
+        };
+        let missing = Pubkey::new_unique();
+        let inner = CounterRpcProvider::new(MockRpc {
+            lookup_tables: vec![table.clone()],
+            latency: Duration::from_millis(20),
+            ..Default::default()
+        });
+        let lookup_cache = LookupTableCacheProvider::new(
+            inner.clone(),
+            Cache::builder().build(),
+            Cache::builder().build(),
+        );
+        let keys = [table.key, missing];
+        let (first, second) = tokio::join!(
+            lookup_cache.prewarm(&keys, 2),
+            lookup_cache.prewarm(&keys, 2)
+        );
+        let expected = PrewarmResult { hits: 1, misses: 1 };
+        assert_eq!(expected, first?);
+        assert_eq!(expected, second?);
+        // one fetch per key, shared by both warmups
+        assert_eq!(2, inner.get_counter(&RpcMethod::Lookup));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_name_in_span() -> anyhow::Result<()> {
+        let (_guard, logs) = capture_logs();
+        let key = Pubkey::new_unique();
+        let lookup_cache = LookupTableCacheProvider::builder()
+            .inner(MockRpcProvider::default())
+            .lookup_cache(Cache::builder().build())
+            .negative_cache(Cache::builder().build())
+            .name("orca")
+            .build();
+        assert_eq!(Some("orca"), lookup_cache.name());
+        lookup_cache.get_lookup_table_accounts(&[key]).await?;
+        let logs = logs.contents();
+        let line = logs
+            .lines()
+            .find(|line| line.contains("cache-miss"))
+            .expect("cache miss logged");
+        assert!(line.contains(r#"lookup-resolver{name="orca""#), "{line}");
+
+        let (_guard, logs) = capture_logs();
+        let unnamed = LookupTableCacheProvider::new(
+            MockRpcProvider::default(),
+            Cache::builder().build(),
+            Cache::builder().build(),
+        );
+        unnamed.get_lookup_table_accounts(&[key]).await?;
+        assert!(!logs.contents().contains("name="));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lookup_commitment() -> anyhow::Result<()> {
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![Pubkey::new_unique()],
+        };
+        let mock = MockRpc {
+            lookup_tables: vec![table.clone()],
+            ..Default::default()
+        };
+        let lookup_cache = LookupTableCacheProvider::builder()
+            .inner(mock.clone())
+            .lookup_cache(Cache::builder().build())
+            .negative_cache(Cache::builder().build())
+            .commitment(CommitmentConfig::processed())
+            .build();
+        assert_eq!(
+            vec![table.clone()],
+            lookup_cache.get_lookup_table_accounts(&[table.key]).await?
+        );
+        lookup_cache.prewarm(&[Pubkey::new_unique()], 1).await?;
+        assert_eq!(
+            vec![CommitmentConfig::processed(); 2],
+            *mock.lookup_commitments.lock().unwrap()
+        );
+
+        // without a commitment the inner provider's default is used
+        let mock = MockRpc {
+            lookup_tables: vec![table.clone()],
+            ..Default::default()
+        };
+        let lookup_cache = LookupTableCacheProvider::new(
+            mock.clone(),
+            Cache::builder().build(),
+            Cache::builder().build(),
+        );
+        lookup_cache.get_lookup_table_accounts(&[table.key]).await?;
+        assert!(mock.lookup_commitments.lock().unwrap().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_batch_misses() -> anyhow::Result<()> {
+        let tables: Vec<AddressLookupTableAccount> = (0..3)
+            .map(|_| AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: vec![Pubkey::new_unique()],
+            })
+            .collect();
+        let keys: Vec<Pubkey> = tables.iter().map(|table| table.key).collect();
+        let inner = CounterRpcProvider::new(MockRpc {
+            lookup_tables: tables.clone(),
+            ..Default::default()
+        });
+        let lookup_cache = LookupTableCacheProvider::new(
+            inner.clone(),
+            Cache::builder().build(),
+            Cache::builder().build(),
+        );
+        lookup_cache.get_lookup_table_accounts(&keys[..1]).await?;
+        assert_eq!(1, inner.get_counter(&RpcMethod::Lookup));
+
+        let missing = Pubkey::new_unique();
+        let query = [keys[0], keys[1], missing, keys[2], keys[1]];
+        let resolved = lookup_cache.get_lookup_table_accounts(&query).await?;
+        assert_eq!(tables, resolved);
+        // the two uncached tables and the miss in one call
+        assert_eq!(2, inner.get_counter(&RpcMethod::Lookup));
+        assert!(lookup_cache.negative_age(&missing).await.is_some());
+
+        lookup_cache.get_lookup_table_accounts(&keys).await?;
+        assert_eq!(2, inner.get_counter(&RpcMethod::Lookup));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_misses() -> anyhow::Result<()> {
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![Pubkey::new_unique()],
+        };
+        let inner = CounterRpcProvider::new(MockRpc {
+            lookup_tables: vec![table.clone()],
+            latency: Duration::from_millis(20),
+            ..Default::default()
+        });
+        let lookup_cache = LookupTableCacheProvider::new(
+            inner.clone(),
+            Cache::builder().build(),
+            Cache::builder().build(),
+        );
+        let resolved = join_all(
+            (0..8)
+                .map(|_| lookup_cache.get_lookup_table_accounts(std::slice::from_ref(&table.key))),
+        )
+        .await;
+        for tables in resolved {
+            assert_eq!(vec![table.clone()], tables?);
+        }
+        assert_eq!(1, inner.get_counter(&RpcMethod::Lookup));
+
+        // a miss already in flight is awaited, not fetched again
+        let other = Pubkey::new_unique();
+        let (missing, batch) = ([other], [other, table.key]);
+        let (first, second) = tokio::join!(
+            lookup_cache.get_lookup_table_accounts(&missing),
+            lookup_cache.get_lookup_table_accounts(&batch)
+        );
+        assert!(first?.is_empty());
+        assert_eq!(vec![table], second?);
+        assert_eq!(2, inner.get_counter(&RpcMethod::Lookup));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalidate() -> anyhow::Result<()> {
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![Pubkey::new_unique()],
+        };
+        let missing = Pubkey::new_unique();
+        let inner = CounterRpcProvider::new(MockRpc {
+            lookup_tables: vec![table.clone()],
+            ..Default::default()
+        });
+        let lookup_cache = LookupTableCacheProvider::new(
+            inner.clone(),
+            Cache::builder().build(),
+            Cache::builder().build(),
+        );
+        assert!(!lookup_cache.contains(&table.key).await);
+        assert_eq!(None, lookup_cache.get_cached(&table.key).await);
+        assert_eq!(0, inner.get_counter(&RpcMethod::Lookup));
+
+        lookup_cache
+            .get_lookup_table_accounts(&[table.key, missing])
+            .await?;
+        assert!(lookup_cache.contains(&table.key).await);
+        assert_eq!(
+            Some(table.clone()),
+            lookup_cache.get_cached(&table.key).await
+        );
+
+        lookup_cache.invalidate(&table.key).await;
+        assert!(!lookup_cache.contains(&table.key).await);
+        lookup_cache.get_lookup_table_accounts(&[table.key]).await?;
+        assert_eq!(2, inner.get_counter(&RpcMethod::Lookup));
+
+        assert!(lookup_cache.negative_age(&missing).await.is_some());
+        lookup_cache.invalidate_negative(&missing).await;
+        assert_eq!(None, lookup_cache.negative_age(&missing).await);
+        assert!(lookup_cache.contains(&table.key).await);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lookup_stats() -> anyhow::Result<()> {
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![Pubkey::new_unique()],
+        };
+        let missing = Pubkey::new_unique();
+        let lookup_cache = LookupTableCacheProvider::new(
+            MockRpc {
+                lookup_tables: vec![table.clone()],
+                ..Default::default()
+            },
+            Cache::builder().build(),
+            Cache::builder().build(),
+        );
+        assert_eq!(CacheStats::default(), lookup_cache.stats());
+
+        lookup_cache
+            .get_lookup_table_accounts(&[table.key, missing])
+            .await?;
+        lookup_cache
+            .get_lookup_table_accounts(&[table.key, missing])
+            .await?;
+        lookup_cache.prewarm(&[table.key], 1).await?;
+        lookup_cache.invalidate(&table.key).await;
+        lookup_cache.invalidate(&table.key).await;
+        assert_eq!(
+            CacheStats {
+                hits: 2,
+                misses: 3,
+                negative_hits: 1,
+                evictions: 1,
+            },
+            lookup_cache.stats()
+        );
+
+        lookup_cache.clear_negative().await;
+        assert_eq!(2, lookup_cache.stats().evictions);
+        Ok(())
+    }
+
+    /// A plain map without expiry, standing in for a custom store
+    struct MapStore<V>(DashMap<Pubkey, V>);
+
+    impl<V> Default for MapStore<V> {
+        fn default() -> Self {
+            Self(DashMap::new())
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl<V: Clone + Send + Sync + 'static> LookupCacheStore<V> for MapStore<V> {
+        async fn get(&self, key: &Pubkey) -> Option<V> {
+            self.0.get(key).map(|value| value.clone())
+        }
+
+        async fn insert(&self, key: Pubkey, value: V) {
+            self.0.insert(key, value);
+        }
+
+        async fn invalidate(&self, key: &Pubkey) -> bool {
+            self.0.remove(key).is_some()
+        }
+
+        async fn invalidate_all(&self) {
+            self.0.clear();
+        }
+
+        fn entry_count(&self) -> u64 {
+            self.0.len() as u64
+        }
+
+        async fn run_pending_tasks(&self) {}
+    }
+
+    #[tokio::test]
+    async fn test_custom_store() -> anyhow::Result<()> {
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![Pubkey::new_unique()],
+        };
+        let missing = Pubkey::new_unique();
+        let inner = CounterRpcProvider::new(MockRpc {
+            lookup_tables: vec![table.clone()],
+            ..Default::default()
+        });
+        let lookup_cache =
+            LookupTableCacheProvider::new(inner.clone(), MapStore::default(), MapStore::default());
+        let query = [table.key, missing];
+        assert_eq!(
+            vec![table.clone()],
+            lookup_cache.get_lookup_table_accounts(&query).await?
+        );
+        assert_eq!(
+            vec![table.clone()],
+            lookup_cache.get_lookup_table_accounts(&query).await?
+        );
+        assert_eq!(1, lookup_cache.len());
+        assert_eq!(1, lookup_cache.len_negative());
+        assert_eq!(1, lookup_cache.stats().hits);
+
+        lookup_cache.clear_all().await;
+        assert_eq!(0, lookup_cache.total().await);
+        assert_eq!(None, lookup_cache.get_cached(&table.key).await);
+        Ok(())
+    }
 }
@@ -0,0 +1,282 @@
+use {
+    super::noop::NoopRpcNative,
+    crate::{Error, Result, TransactionRpcProvider},
+    base64::prelude::*,
+    solana_account::Account,
+    solana_commitment_config::CommitmentConfig,
+    solana_hash::Hash,
+    solana_message::AddressLookupTableAccount,
+    solana_pubkey::Pubkey,
+    solana_rpc_client::nonblocking::rpc_client::RpcClient,
+    solana_rpc_client_api::{
+        config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig},
+        filter::RpcFilterType,
+        response::{
+            RpcPerfSample,
+            RpcPrioritizationFee,
+            RpcSimulateTransactionResult,
+            TransactionError,
+        },
+    },
+    solana_signature::Signature,
+    solana_transaction::versioned::VersionedTransaction,
+    std::{
+        collections::HashMap,
+        io::Write,
+        sync::{
+            Arc,
+            Mutex,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::Duration,
+    },
+    tracing::subscriber::DefaultGuard,
+};
+
+/// Log output captured by [`capture_logs`]
+#[derive(Clone, Default)]
+pub struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl LogBuffer {
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).to_string()
+    }
+}
+
+impl Write for LogBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Captures all tracing output of the current thread until the guard is
+/// dropped. Use with a current thread runtime.
+pub fn capture_logs() -> (DefaultGuard, LogBuffer) {
+    let buffer = LogBuffer::default();
+    let writer = buffer.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::TRACE)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    (tracing::subscriber::set_default(subscriber), buffer)
+}
+
+/// A transaction passed to `send_and_confirm_transaction` with its config
+pub type Sent = (VersionedTransaction, Option<RpcSendTransactionConfig>);
+
+/// Test double with configurable simulation and fee results, which records
+/// every simulated and sent transaction.
+///
+/// Methods which are not configurable delegate to [`super::noop::NoopRpc`].
+#[derive(Clone, Default)]
+pub struct MockRpc {
+    pub(crate) inner: NoopRpcNative,
+    /// Blockhash returned by `get_latest_blockhash`
+    pub blockhash: Hash,
+    /// When set, `simulate_transaction` fails like the native provider does
+    pub simulate_error: Option<TransactionError>,
+    pub units_consumed: Option<u64>,
+    /// When set, `send_and_confirm_transaction` fails as if the transaction
+    /// landed with this error
+    pub send_error: Option<TransactionError>,
+    /// When set, `send_and_confirm_transaction` broadcasts but times out
+    /// confirming
+    pub unconfirmed: bool,
+    /// Each fee becomes an [`RpcPrioritizationFee`] at consecutive slots
+    pub prioritization_fees: Vec<u64>,
+    /// Tables returned by `get_lookup_table_accounts`, once per requested key
+    pub lookup_tables: Vec<AddressLookupTableAccount>,
+    /// Lamports returned by `get_balance` for every account
+    pub balance: u64,
+    /// Accounts returned by `get_account`, missing keys return `None`.
+    /// `get_program_accounts` returns those owned by the program which match
+    /// the filters.
+    pub accounts: HashMap<Pubkey, Account>,
+    /// Returned by `get_recent_performance_samples`, newest first
+    pub performance_samples: Vec<RpcPerfSample>,
+    /// Added to every call
+    pub latency: Duration,
+    /// Calls currently waiting out `latency`, and the most seen at once
+    pub(crate) in_flight: Arc<AtomicUsize>,
+    pub(crate) max_in_flight: Arc<AtomicUsize>,
+    /// Commitments passed to `get_lookup_table_accounts_with_commitment`
+    pub lookup_commitments: Arc<Mutex<Vec<CommitmentConfig>>>,
+    pub simulated: Arc<Mutex<Vec<RpcSimulateTransactionConfig>>>,
+    pub sent: Arc<Mutex<Vec<Sent>>>,
+}
+
+impl MockRpc {
+    async fn delay(&self) {
+        if !self.latency.is_zero() {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            crate::time::sleep(self.latency).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Most calls which overlapped while waiting out `latency`
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight.load(Ordering::SeqCst)
+    }
+
+    pub fn simulated(&self) -> Vec<RpcSimulateTransactionConfig> {
+        self.simulated.lock().unwrap().clone()
+    }
+
+    pub fn sent(&self) -> Vec<Sent> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+impl AsRef<RpcClient> for MockRpc {
+    fn as_ref(&self) -> &RpcClient {
+        self.inner.as_ref()
+    }
+}
+
+#[async_trait::async_trait]
+impl TransactionRpcProvider for MockRpc {
+    async fn get_recent_prioritization_fees(
+        &self,
+        _accounts: &[Pubkey],
+    ) -> Result<Vec<RpcPrioritizationFee>> {
+        self.delay().await;
+        Ok(self
+            .prioritization_fees
+            .iter()
+            .enumerate()
+            .map(|(slot, fee)| RpcPrioritizationFee {
+                slot: slot as u64,
+                prioritization_fee: *fee,
+            })
+            .collect())
+    }
+
+    async fn get_lookup_table_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.delay().await;
+        Ok(pubkeys
+            .iter()
+            .filter_map(|key| self.lookup_tables.iter().find(|t| t.key == *key))
+            .cloned()
+            .collect())
+    }
+
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.lookup_commitments.lock().unwrap().push(commitment);
+        self.get_lookup_table_accounts(pubkeys).await
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash> {
+        self.delay().await;
+        Ok(self.blockhash)
+    }
+
+    async fn simulate_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        config: RpcSimulateTransactionConfig,
+    ) -> Result<RpcSimulateTransactionResult> {
+        self.delay().await;
+        self.simulated.lock().unwrap().push(config.clone());
+        if let Some(error) = &self.simulate_error {
+            return Err(Error::SolanaSimulateError {
+                error: error.clone(),
+                logs: vec![],
+                transaction_base64: BASE64_STANDARD.encode(bincode::serialize(&tx)?),
+            });
+        }
+        let mut result = self.inner.simulate_transaction(tx, config).await?;
+        result.units_consumed = self.units_consumed;
+        Ok(result)
+    }
+
+    async fn send_and_confirm_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        config: Option<RpcSendTransactionConfig>,
+    ) -> Result<Signature> {
+        self.delay().await;
+        self.sent.lock().unwrap().push((tx.clone(), config));
+        let signature = tx.signatures.first().copied().unwrap_or_default();
+        if self.unconfirmed {
+            return Err(Error::Unconfirmed(signature));
+        }
+        match &self.send_error {
+            Some(error) => Err(Error::TransactionFailed {
+                signature,
+                error: error.clone(),
+                logs: vec![],
+            }),
+            None => Ok(signature),
+        }
+    }
+
+    async fn get_balance(&self, _pubkey: &Pubkey) -> Result<u64> {
+        self.delay().await;
+        Ok(self.balance)
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        self.delay().await;
+        Ok(self.accounts.get(pubkey).cloned())
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        self.delay().await;
+        Ok(pubkeys
+            .iter()
+            .map(|pubkey| self.accounts.get(pubkey).cloned())
+            .collect())
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        self.delay().await;
+        let matches = |account: &Account| {
+            filters.iter().all(|filter| match filter {
+                RpcFilterType::DataSize(size) => account.data.len() as u64 == *size,
+                RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(&account.data),
+                RpcFilterType::TokenAccountState => true,
+            })
+        };
+        Ok(self
+            .accounts
+            .iter()
+            .filter(|(_, account)| account.owner == *program && matches(account))
+            .map(|(pubkey, account)| (*pubkey, account.clone()))
+            .collect())
+    }
+
+    async fn get_recent_performance_samples(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+        self.delay().await;
+        Ok(self
+            .performance_samples
+            .iter()
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+}
@@ -1,15 +1,182 @@
 use {
-    crate::{Error, Result, TransactionRpcProvider},
+    super::{ConfirmationConfig, NativeRpcWrapper},
+    crate::{
+        Cluster,
+        Error,
+        Result,
+        TransactionRpcProvider,
+        UiTransactionEncoding,
+        cluster_from_url,
+    },
     base64::prelude::*,
+    solana_account_decoder::UiAccountEncoding,
+    solana_commitment_config::CommitmentConfig,
     solana_hash::Hash,
     solana_message::AddressLookupTableAccount,
     solana_pubkey::Pubkey,
     solana_rpc_client::nonblocking::rpc_client::RpcClient,
-    solana_rpc_client_api::response::RpcPrioritizationFee,
+    solana_rpc_client_api::{
+        config::{
+            RpcAccountInfoConfig,
+            RpcProgramAccountsConfig,
+            RpcSendTransactionConfig,
+            RpcSimulateTransactionConfig,
+            RpcTransactionConfig,
+        },
+        filter::RpcFilterType,
+        request::MAX_MULTIPLE_ACCOUNTS,
+        response::{RpcPrioritizationFee, TransactionError, TransactionResult},
+    },
     solana_signature::Signature,
-    tracing::{debug, trace},
+    solana_transaction_status_client_types::UiTransactionStatusMeta,
+    std::{future::Future, time::Instant},
+    tracing::{debug, trace, warn},
 };
 
+/// Polls `status` every [`ConfirmationConfig::poll_interval`] until the
+/// transaction is confirmed, fails, or [`ConfirmationConfig::timeout`] passes
+/// ([`Error::Unconfirmed`]).
+///
+/// Cancellation safe: the loop runs inside the caller's future and spawns no
+/// task, so dropping it stops polling at the next `.await`.
+pub(crate) async fn poll_confirmation<F, Fut>(
+    signature: &Signature,
+    confirmation: &ConfirmationConfig,
+    mut status: F,
+) -> Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Option<TransactionResult<()>>>>,
+{
+    let started = Instant::now();
+    loop {
+        match status().await? {
+            Some(Ok(())) => return Ok(()),
+            Some(Err(error)) => {
+                return Err(Error::TransactionFailed {
+                    signature: *signature,
+                    error,
+                    logs: vec![],
+                });
+            }
+            None => trace!(signature = %signature, "not confirmed yet"),
+        }
+        if started.elapsed() + confirmation.poll_interval > confirmation.timeout {
+            return Err(Error::Unconfirmed(*signature));
+        }
+        crate::time::sleep(confirmation.poll_interval).await;
+    }
+}
+
+/// Fills the logs of a [`Error::TransactionFailed`] from `logs`. Failing to
+/// fetch them keeps the original error.
+pub(crate) async fn with_failure_logs<F, Fut>(result: Result<()>, logs: F) -> Result<()>
+where
+    F: FnOnce(Signature) -> Fut,
+    Fut: Future<Output = Result<Vec<String>>>,
+{
+    match result {
+        Err(Error::TransactionFailed {
+            signature,
+            error,
+            logs: _,
+        }) => {
+            let logs = logs(signature).await.unwrap_or_else(|e| {
+                warn!(signature = %signature, "failed to fetch logs of failed transaction: {e}");
+                vec![]
+            });
+            Err(Error::TransactionFailed {
+                signature,
+                error,
+                logs,
+            })
+        }
+        other => other,
+    }
+}
+
+/// Status metadata of a landed transaction, `None` when the RPC has none
+async fn transaction_meta(
+    client: &RpcClient,
+    signature: &Signature,
+    commitment: CommitmentConfig,
+) -> Result<Option<UiTransactionStatusMeta>> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(commitment),
+        max_supported_transaction_version: Some(0),
+    };
+    let tx = client
+        .get_transaction_with_config(signature, config)
+        .await
+        .map_err(|e| {
+            Error::SolanaRpcError(format!("failed to get transaction {signature}: {e}"))
+        })?;
+    Ok(tx.transaction.meta)
+}
+
+/// Log messages of a landed transaction
+async fn transaction_logs(
+    client: &RpcClient,
+    signature: Signature,
+    commitment: CommitmentConfig,
+) -> Result<Vec<String>> {
+    Ok(transaction_meta(client, &signature, commitment)
+        .await?
+        .and_then(|meta| Option::from(meta.log_messages))
+        .unwrap_or_default())
+}
+
+/// Error of a landed transaction, `None` when it succeeded.
+///
+/// Fails when the transaction is not found at `commitment`.
+pub async fn get_transaction_error<T: AsRef<RpcClient>>(
+    rpc: &T,
+    signature: &Signature,
+    commitment: CommitmentConfig,
+) -> Result<Option<TransactionError>> {
+    Ok(transaction_meta(rpc.as_ref(), signature, commitment)
+        .await?
+        .and_then(|meta| meta.err)
+        .map(TransactionError::from))
+}
+
+async fn send_and_poll(
+    client: &RpcClient,
+    tx: &solana_transaction::versioned::VersionedTransaction,
+    config: Option<RpcSendTransactionConfig>,
+    confirmation: &ConfirmationConfig,
+    commitment: CommitmentConfig,
+    fetch_failure_logs: bool,
+) -> Result<Signature> {
+    let sent = match config {
+        None => client.send_transaction(tx).await,
+        Some(config) => client.send_transaction_with_config(tx, config).await,
+    };
+    let signature =
+        sent.map_err(|e| Error::SolanaRpcError(format!("failed to send transaction: {e}")))?;
+    let confirmed = poll_confirmation(&signature, confirmation, || async move {
+        client
+            .get_signature_status_with_commitment(&signature, commitment)
+            .await
+            .map_err(|e| {
+                Error::SolanaRpcError(format!(
+                    "failed to confirm transaction: {signature} Error:{e}"
+                ))
+            })
+    })
+    .await;
+    if fetch_failure_logs {
+        with_failure_logs(confirmed, |signature| {
+            transaction_logs(client, signature, commitment)
+        })
+        .await?;
+    } else {
+        confirmed?;
+    }
+    Ok(signature)
+}
+
 #[async_trait::async_trait]
 impl TransactionRpcProvider for std::sync::Arc<RpcClient> {
     async fn get_recent_prioritization_fees(
@@ -31,6 +198,15 @@ impl TransactionRpcProvider for std::sync::Arc<RpcClient> {
         crate::lookup::fetch_lookup_tables(pubkeys, &self).await
     }
 
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        debug!(accounts =? pubkeys.len(), commitment =? commitment.commitment, "calling get_lookup_table_accounts");
+        crate::lookup::fetch_lookup_tables_with_commitment(pubkeys, &self, commitment).await
+    }
+
     async fn get_latest_blockhash(&self) -> Result<Hash> {
         debug!("calling get_latest_blockhash");
         self.as_ref()
@@ -50,10 +226,11 @@ impl TransactionRpcProvider for std::sync::Arc<RpcClient> {
             .await
             .map_err(|e| Error::SolanaRpcError(format!("failed to simulate transaction: {e}")))?;
         if let Some(e) = result.value.err {
-            let logs = result.value.logs.unwrap_or(Vec::new());
-            let transaction_base64 = BASE64_STANDARD.encode(bincode::serialize(&tx)?);
-            let msg = format!("{e}\nbase64: {transaction_base64}\n{}", logs.join("\n"));
-            return Err(Error::SolanaSimulateFailure(msg));
+            return Err(Error::SolanaSimulateError {
+                error: e.into(),
+                logs: result.value.logs.unwrap_or(Vec::new()),
+                transaction_base64: BASE64_STANDARD.encode(bincode::serialize(&tx)?),
+            });
         }
         Ok(result.value)
     }
@@ -67,36 +244,705 @@ impl TransactionRpcProvider for std::sync::Arc<RpcClient> {
             let transaction_base64 = BASE64_STANDARD.encode(bincode::serialize(&tx)?);
             trace!(send_tx =? transaction_base64);
         }
-        match config {
-            None => self
-                .as_ref()
-                .send_and_confirm_transaction(tx)
+        send_and_poll(
+            self,
+            tx,
+            config,
+            &ConfirmationConfig::default(),
+            self.commitment(),
+            true,
+        )
+        .await
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        debug!(pubkey =? pubkey, "calling get_balance");
+        self.as_ref()
+            .get_balance(pubkey)
+            .await
+            .map_err(|e| Error::SolanaRpcError(format!("failed to get balance: {e}")))
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        debug!(pubkey =? pubkey, "calling get_account");
+        let client = self.as_ref();
+        client
+            .get_account_with_commitment(pubkey, client.commitment())
+            .await
+            .map(|response| response.value)
+            .map_err(|e| Error::SolanaRpcError(format!("failed to get account: {e}")))
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        debug!(pubkeys = pubkeys.len(), "calling get_multiple_accounts");
+        let client = self.as_ref();
+        let mut accounts = Vec::with_capacity(pubkeys.len());
+        for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let response = client
+                .get_multiple_accounts_with_commitment(chunk, client.commitment())
+                .await
+                .map_err(|e| Error::SolanaRpcError(format!("failed to get accounts: {e}")))?;
+            accounts.extend(response.value);
+        }
+        Ok(accounts)
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        debug!(program =? program, filters = filters.len(), "calling get_program_accounts");
+        let client = self.as_ref();
+        let config = RpcProgramAccountsConfig {
+            filters: (!filters.is_empty()).then_some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(client.commitment()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        client
+            .get_program_ui_accounts_with_config(program, config)
+            .await
+            .map_err(|e| Error::SolanaRpcError(format!("failed to get program accounts: {e}")))?
+            .into_iter()
+            .map(|(pubkey, account)| {
+                let account = account.decode().ok_or_else(|| {
+                    Error::SolanaRpcError(format!("failed to decode program account {pubkey}"))
+                })?;
+                Ok((pubkey, account))
+            })
+            .collect()
+    }
+
+    async fn get_recent_performance_samples(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+        debug!(limit, "calling get_recent_performance_samples");
+        self.as_ref()
+            .get_recent_performance_samples(Some(limit))
+            .await
+            .map_err(|e| Error::SolanaRpcError(format!("failed to get performance samples: {e}")))
+    }
+}
+
+impl NativeRpcWrapper {
+    /// Cluster of the client's URL, see [`cluster_from_url`].
+    pub fn cluster(&self) -> Option<Cluster> {
+        cluster_from_url(&self.client.url())
+    }
+
+    /// Uses `commitment` for the blockhash, simulation (unless the call's
+    /// config sets one) and confirmation requests instead of the client's.
+    ///
+    /// Lets a `confirmed` client force `finalized` on critical paths. Wrap
+    /// the result in a [`crate::TraceTransactionProvider`] to trace it.
+    pub fn with_commitment(mut self, commitment: CommitmentConfig) -> Self {
+        self.commitment = Some(commitment);
+        self
+    }
+
+    /// The override of [`NativeRpcWrapper::with_commitment`], or the
+    /// client's commitment
+    pub fn commitment(&self) -> CommitmentConfig {
+        self.commitment.unwrap_or_else(|| self.client.commitment())
+    }
+}
+
+#[async_trait::async_trait]
+impl TransactionRpcProvider for NativeRpcWrapper {
+    async fn get_recent_prioritization_fees(
+        &self,
+        accounts: &[Pubkey],
+    ) -> Result<Vec<RpcPrioritizationFee>> {
+        TransactionRpcProvider::get_recent_prioritization_fees(&self.client, accounts).await
+    }
+
+    async fn get_lookup_table_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        TransactionRpcProvider::get_lookup_table_accounts(&self.client, pubkeys).await
+    }
+
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        TransactionRpcProvider::get_lookup_table_accounts_with_commitment(
+            &self.client,
+            pubkeys,
+            commitment,
+        )
+        .await
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash> {
+        let Some(commitment) = self.commitment else {
+            return TransactionRpcProvider::get_latest_blockhash(&self.client).await;
+        };
+        debug!(commitment =? commitment.commitment, "calling get_latest_blockhash");
+        self.client
+            .get_latest_blockhash_with_commitment(commitment)
+            .await
+            .map(|(hash, _)| hash)
+            .map_err(|e| Error::SolanaRpcError(format!("failed to get latest blockhash: {e}")))
+    }
+
+    async fn simulate_transaction(
+        &self,
+        tx: &solana_transaction::versioned::VersionedTransaction,
+        config: solana_rpc_client_api::config::RpcSimulateTransactionConfig,
+    ) -> Result<solana_rpc_client_api::response::RpcSimulateTransactionResult> {
+        let config = RpcSimulateTransactionConfig {
+            encoding: config.encoding.or(Some(self.encoding)),
+            commitment: config.commitment.or(self.commitment),
+            ..config
+        };
+        TransactionRpcProvider::simulate_transaction(&self.client, tx, config).await
+    }
+
+    async fn send_and_confirm_transaction(
+        &self,
+        tx: &solana_transaction::versioned::VersionedTransaction,
+        config: Option<solana_rpc_client_api::config::RpcSendTransactionConfig>,
+    ) -> Result<Signature> {
+        // same preflight commitment as `RpcClient::send_transaction`
+        let config = config.unwrap_or_else(|| RpcSendTransactionConfig {
+            preflight_commitment: Some(self.commitment().commitment),
+            ..Default::default()
+        });
+        let config = RpcSendTransactionConfig {
+            encoding: config.encoding.or(Some(self.encoding)),
+            ..config
+        };
+        send_and_poll(
+            &self.client,
+            tx,
+            Some(config),
+            &self.confirmation,
+            self.commitment(),
+            self.fetch_failure_logs,
+        )
+        .await
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        TransactionRpcProvider::get_balance(&self.client, pubkey).await
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        TransactionRpcProvider::get_account(&self.client, pubkey).await
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        TransactionRpcProvider::get_multiple_accounts(&self.client, pubkeys).await
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        TransactionRpcProvider::get_program_accounts(&self.client, program, filters).await
+    }
+
+    async fn get_recent_performance_samples(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+        TransactionRpcProvider::get_recent_performance_samples(&self.client, limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_commitment_config::CommitmentConfig,
+        solana_instruction::error::InstructionError,
+        solana_keypair::Keypair,
+        solana_message::{Message, VersionedMessage},
+        solana_rpc_client::{
+            rpc_client::RpcClientConfig,
+            rpc_sender::{RpcSender, RpcTransportStats},
+        },
+        solana_rpc_client_api::{
+            client_error::ErrorKind as ClientErrorKind,
+            request::RpcRequest,
+            response::TransactionError,
+        },
+        solana_signer::Signer,
+        solana_transaction::versioned::VersionedTransaction,
+        std::{sync::Arc, time::Duration},
+    };
+
+    #[tokio::test]
+    async fn test_poll_confirmation_interval() -> anyhow::Result<()> {
+        let confirmation = ConfirmationConfig {
+            poll_interval: Duration::from_millis(50),
+            timeout: Duration::from_secs(5),
+        };
+        let mut polls = 0;
+        let started = Instant::now();
+        poll_confirmation(&Signature::default(), &confirmation, || {
+            polls += 1;
+            let status = (polls == 3).then_some(Ok(()));
+            async move { Ok(status) }
+        })
+        .await?;
+        assert_eq!(3, polls);
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_poll_confirmation_timeout() {
+        let confirmation = ConfirmationConfig {
+            poll_interval: Duration::from_millis(20),
+            timeout: Duration::from_millis(100),
+        };
+        let mut polls = 0;
+        let result = poll_confirmation(&Signature::default(), &confirmation, || {
+            polls += 1;
+            async { Ok(None) }
+        })
+        .await;
+        assert!(matches!(result, Err(Error::Unconfirmed(_))));
+        assert!(polls <= 5, "polled {polls} times");
+    }
+
+    #[tokio::test]
+    async fn test_failure_logs() {
+        let confirmation = ConfirmationConfig::default();
+        let signature = Signature::from([7; 64]);
+        let error = TransactionError::InstructionError(0, InstructionError::Custom(1));
+        let failed = poll_confirmation(&signature, &confirmation, || {
+            let status = Some(Err(error.clone()));
+            async move { Ok(status) }
+        })
+        .await;
+        let result = with_failure_logs(failed, |sig| async move {
+            Ok(vec![
+                format!("Program log: {sig}"),
+                "Program failed: custom program error: 0x1".to_string(),
+            ])
+        })
+        .await;
+        let Err(Error::TransactionFailed {
+            signature: sig,
+            error: err,
+            logs,
+        }) = result
+        else {
+            panic!("expected TransactionFailed");
+        };
+        assert_eq!(signature, sig);
+        assert_eq!(error, err);
+        assert_eq!(
+            vec![
+                format!("Program log: {signature}"),
+                "Program failed: custom program error: 0x1".to_string(),
+            ],
+            logs
+        );
+
+        // a failing fetch keeps the error without logs
+        let failed = Err(Error::TransactionFailed {
+            signature,
+            error: error.clone(),
+            logs: vec![],
+        });
+        let result = with_failure_logs(failed, |_| async {
+            Err(Error::SolanaRpcError("not found".to_string()))
+        })
+        .await;
+        assert!(matches!(
+            result,
+            Err(Error::TransactionFailed { logs, .. }) if logs.is_empty()
+        ));
+
+        // other results are untouched
+        assert!(
+            with_failure_logs(Ok(()), |_| async { Ok(vec![]) })
                 .await
-                .map_err(|e| Error::SolanaRpcError(format!("failed to send transaction: {e}"))),
-            Some(config) => {
-                let result = self
-                    .as_ref()
-                    .send_transaction_with_config(tx, config)
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_program_accounts() -> anyhow::Result<()> {
+        let client = Arc::new(RpcClient::new_mock("succeeds".to_string()));
+        let accounts =
+            TransactionRpcProvider::get_program_accounts(&client, &Pubkey::new_unique(), vec![])
+                .await?;
+        assert_eq!(1, accounts.len());
+        assert_eq!(1_000_000, accounts[0].1.lamports);
+        assert!(accounts[0].1.data.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_failure_logs() -> anyhow::Result<()> {
+        let payer = Keypair::new();
+        let tx = VersionedTransaction::try_new(
+            VersionedMessage::Legacy(Message::new(
+                &[spl_memo_interface::instruction::build_memo(
+                    &spl_memo_interface::v3::ID,
+                    b"failed",
+                    &[&payer.pubkey()],
+                )],
+                Some(&payer.pubkey()),
+            )),
+            &[&payer],
+        )?;
+        let failed = serde_json::json!({ "InstructionError": [0, { "Custom": 1 }] });
+        let mocks = [
+            (
+                RpcRequest::GetSignatureStatuses,
+                serde_json::json!({
+                    "context": { "slot": 1 },
+                    "value": [{
+                        "slot": 1,
+                        "confirmations": null,
+                        "err": failed,
+                        "status": { "Err": failed },
+                        "confirmationStatus": "finalized",
+                    }],
+                }),
+            ),
+            (
+                RpcRequest::GetTransaction,
+                serde_json::json!({
+                    "slot": 1,
+                    "transaction": ["", "base64"],
+                    "meta": {
+                        "err": failed,
+                        "status": { "Err": failed },
+                        "fee": 5000,
+                        "preBalances": [],
+                        "postBalances": [],
+                        "logMessages": [
+                            "Program log: failing",
+                            "Program failed: custom program error: 0x1",
+                        ],
+                    },
+                    "blockTime": null,
+                }),
+            ),
+        ];
+        let rpc = NativeRpcWrapper::from(RpcClient::new_mock_with_mocks_map(
+            "succeeds",
+            mocks.into_iter().collect(),
+        ));
+
+        let result = rpc.send_and_confirm_transaction(&tx, None).await;
+        let Err(Error::TransactionFailed {
+            signature,
+            error,
+            logs,
+        }) = result
+        else {
+            panic!("expected TransactionFailed");
+        };
+        assert_eq!(tx.signatures[0], signature);
+        assert_eq!(
+            TransactionError::InstructionError(0, InstructionError::Custom(1)),
+            error
+        );
+        assert_eq!(
+            vec![
+                "Program log: failing".to_string(),
+                "Program failed: custom program error: 0x1".to_string(),
+            ],
+            logs
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_unconfirmed() -> anyhow::Result<()> {
+        let payer = Keypair::new();
+        let tx = VersionedTransaction::try_new(
+            VersionedMessage::Legacy(Message::new(
+                &[spl_memo_interface::instruction::build_memo(
+                    &spl_memo_interface::v3::ID,
+                    b"unconfirmed",
+                    &[&payer.pubkey()],
+                )],
+                Some(&payer.pubkey()),
+            )),
+            &[&payer],
+        )?;
+        // the signature status is never found
+        let client = RpcClient::new_mock("sig_not_found".to_string());
+        let confirmation = ConfirmationConfig {
+            poll_interval: Duration::from_millis(10),
+            timeout: Duration::from_millis(50),
+        };
+        let result = send_and_poll(
+            &client,
+            &tx,
+            None,
+            &confirmation,
+            CommitmentConfig::confirmed(),
+            true,
+        )
+        .await;
+        assert!(matches!(result, Err(Error::Unconfirmed(_))));
+
+        // without a send config the client polls too, reporting the failure
+        let client = Arc::new(RpcClient::new_mock("instruction_error".to_string()));
+        let result = TransactionRpcProvider::send_and_confirm_transaction(&client, &tx, None).await;
+        assert!(matches!(
+            result,
+            Err(Error::TransactionFailed {
+                error: TransactionError::InstructionError(
+                    0,
+                    InstructionError::UninitializedAccount
+                ),
+                ..
+            })
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_transaction_error() -> anyhow::Result<()> {
+        let transaction = |err: serde_json::Value| {
+            serde_json::json!({
+                "slot": 2,
+                "transaction": ["", "base64"],
+                "meta": {
+                    "err": err,
+                    "status": if err.is_null() {
+                        serde_json::json!({ "Ok": null })
+                    } else {
+                        serde_json::json!({ "Err": err })
+                    },
+                    "fee": 5000,
+                    "preBalances": [],
+                    "postBalances": [],
+                    "logMessages": ["Program failed: custom program error: 0x1"],
+                },
+                "blockTime": null,
+            })
+        };
+        let failed = serde_json::json!({ "InstructionError": [0, { "Custom": 1 }] });
+        let mocks = [
+            (RpcRequest::GetTransaction, transaction(failed)),
+            (
+                RpcRequest::GetTransaction,
+                transaction(serde_json::Value::Null),
+            ),
+        ];
+        let client = Arc::new(RpcClient::new_mock_with_mocks_map(
+            "succeeds",
+            mocks.into_iter().collect(),
+        ));
+        let signature = Signature::from([7; 64]);
+
+        let error =
+            get_transaction_error(&client, &signature, CommitmentConfig::confirmed()).await?;
+        assert_eq!(
+            Some(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(1)
+            )),
+            error
+        );
+        let error =
+            get_transaction_error(&client, &signature, CommitmentConfig::confirmed()).await?;
+        assert_eq!(None, error);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_poll_confirmation_cancelled() {
+        let confirmation = ConfirmationConfig {
+            poll_interval: Duration::from_millis(10),
+            timeout: Duration::from_secs(60),
+        };
+        // shared with every poll, released once the loop is dropped
+        let polls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let state = polls.clone();
+        let signature = Signature::default();
+        let pending = poll_confirmation(&signature, &confirmation, move || {
+            let state = state.clone();
+            async move {
+                state.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                Ok(None)
+            }
+        });
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), pending)
+                .await
+                .is_err()
+        );
+        let polled = polls.load(std::sync::atomic::Ordering::Relaxed);
+        assert!(polled > 0);
+        assert_eq!(1, Arc::strong_count(&polls));
+
+        // nothing keeps polling in the background
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(polled, polls.load(std::sync::atomic::Ordering::Relaxed));
+    }
+
+    /// Records every request and fails it, so no response has to be faked.
+    /// Only the node version is answered, older clients query it first.
+    #[derive(Clone, Default)]
+    struct RecordingSender(Arc<std::sync::Mutex<Vec<(RpcRequest, serde_json::Value)>>>);
+
+    #[async_trait::async_trait]
+    impl RpcSender for RecordingSender {
+        async fn send(
+            &self,
+            request: RpcRequest,
+            params: serde_json::Value,
+        ) -> solana_rpc_client_api::client_error::Result<serde_json::Value> {
+            if request == RpcRequest::GetVersion {
+                return Ok(serde_json::json!({ "solana-core": "3.0.0" }));
+            }
+            self.0.lock().unwrap().push((request, params));
+            Err(ClientErrorKind::Custom("recorded".to_string()).into())
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+
+        fn url(&self) -> String {
+            "recording".to_string()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_native_wrapper_encoding() -> anyhow::Result<()> {
+        let payer = Keypair::new();
+        let tx = VersionedTransaction::try_new(
+            VersionedMessage::Legacy(Message::new(
+                &[spl_memo_interface::instruction::build_memo(
+                    &spl_memo_interface::v3::ID,
+                    b"encoding",
+                    &[&payer.pubkey()],
+                )],
+                Some(&payer.pubkey()),
+            )),
+            &[&payer],
+        )?;
+
+        for encoding in [UiTransactionEncoding::Base58, UiTransactionEncoding::Base64] {
+            let sender = RecordingSender::default();
+            let client = RpcClient::new_sender(
+                sender.clone(),
+                RpcClientConfig::with_commitment(CommitmentConfig::confirmed()),
+            );
+            let rpc = NativeRpcWrapper::builder()
+                .client(client)
+                .encoding(encoding)
+                .build();
+            assert!(
+                rpc.simulate_transaction(&tx, RpcSimulateTransactionConfig::default())
                     .await
-                    .map_err(|e| {
-                        Error::SolanaRpcError(format!("failed to send transaction: {e}"))
-                    })?;
-
-                match self.as_ref().confirm_transaction(&result).await {
-                    Err(e) => Err(Error::SolanaRpcError(format!(
-                        "failed to confirm transaction: {result} Error:{e}"
-                    ))),
-                    Ok(t) => {
-                        if t {
-                            Ok(result)
-                        } else {
-                            Err(Error::SolanaRpcError(format!(
-                                "Transaction is not confirmed: {result}"
-                            )))
-                        }
-                    }
-                }
+                    .is_err()
+            );
+            assert!(rpc.send_and_confirm_transaction(&tx, None).await.is_err());
+
+            let requests = sender.0.lock().unwrap().clone();
+            assert_eq!(2, requests.len());
+            assert_eq!(RpcRequest::SimulateTransaction, requests[0].0);
+            assert_eq!(RpcRequest::SendTransaction, requests[1].0);
+            for (_, params) in requests {
+                let expected = serde_json::to_value(encoding)?;
+                assert_eq!(expected, params[1]["encoding"]);
+                let wire = params[0].as_str().unwrap();
+                let bytes = match encoding {
+                    UiTransactionEncoding::Base58 => bs58_decode(wire),
+                    _ => BASE64_STANDARD.decode(wire)?,
+                };
+                assert_eq!(bincode::serialize(&tx)?, bytes);
             }
         }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_native_wrapper_commitment() -> anyhow::Result<()> {
+        let payer = Keypair::new();
+        let tx = VersionedTransaction::try_new(
+            VersionedMessage::Legacy(Message::new(
+                &[spl_memo_interface::instruction::build_memo(
+                    &spl_memo_interface::v3::ID,
+                    b"commitment",
+                    &[&payer.pubkey()],
+                )],
+                Some(&payer.pubkey()),
+            )),
+            &[&payer],
+        )?;
+        let sender = RecordingSender::default();
+        let client = RpcClient::new_sender(
+            sender.clone(),
+            RpcClientConfig::with_commitment(CommitmentConfig::confirmed()),
+        );
+        let rpc = NativeRpcWrapper::from(client).with_commitment(CommitmentConfig::finalized());
+        assert_eq!(CommitmentConfig::finalized(), rpc.commitment());
+
+        assert!(rpc.get_latest_blockhash().await.is_err());
+        assert!(
+            rpc.simulate_transaction(&tx, RpcSimulateTransactionConfig::default())
+                .await
+                .is_err()
+        );
+        assert!(rpc.send_and_confirm_transaction(&tx, None).await.is_err());
+
+        let requests = sender.0.lock().unwrap().clone();
+        assert_eq!(3, requests.len());
+        assert_eq!(RpcRequest::GetLatestBlockhash, requests[0].0);
+        assert_eq!("finalized", requests[0].1[0]["commitment"]);
+        assert_eq!(RpcRequest::SimulateTransaction, requests[1].0);
+        assert_eq!("finalized", requests[1].1[1]["commitment"]);
+        assert_eq!(RpcRequest::SendTransaction, requests[2].0);
+        assert_eq!("finalized", requests[2].1[1]["preflightCommitment"]);
+        Ok(())
+    }
+
+    /// Minimal base58 decoder, the test only needs to round trip one value.
+    fn bs58_decode(value: &str) -> Vec<u8> {
+        const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+        let mut bytes: Vec<u8> = vec![];
+        for c in value.bytes() {
+            let mut carry = ALPHABET.iter().position(|a| *a == c).unwrap() as u32;
+            for b in bytes.iter_mut().rev() {
+                carry += *b as u32 * 58;
+                *b = carry as u8;
+                carry >>= 8;
+            }
+            while carry > 0 {
+                bytes.insert(0, carry as u8);
+                carry >>= 8;
+            }
+        }
+        let zeros = value.bytes().take_while(|c| *c == b'1').count();
+        [vec![0; zeros], bytes].concat()
+    }
+
+    #[test]
+    fn test_native_wrapper_default_confirmation() {
+        let rpc: NativeRpcWrapper = RpcClient::new("http://localhost:8899".to_string()).into();
+        assert_eq!(ConfirmationConfig::default(), rpc.confirmation);
+        assert_eq!(UiTransactionEncoding::Base64, rpc.encoding);
+        let _: &RpcClient = rpc.as_ref();
+        assert_eq!(Some(Cluster::Localnet), rpc.cluster());
     }
 }
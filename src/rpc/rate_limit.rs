@@ -0,0 +1,237 @@
+use {
+    super::{RateLimitedRpcProvider, RpcMethod},
+    crate::{Result, TransactionRpcProvider},
+    solana_commitment_config::CommitmentConfig,
+    solana_hash::Hash,
+    solana_message::AddressLookupTableAccount,
+    solana_pubkey::Pubkey,
+    solana_rpc_client::nonblocking::rpc_client::RpcClient,
+    solana_rpc_client_api::{
+        config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig},
+        filter::RpcFilterType,
+        response::{RpcPerfSample, RpcPrioritizationFee, RpcSimulateTransactionResult},
+    },
+    solana_signature::Signature,
+    solana_transaction::versioned::VersionedTransaction,
+    std::{
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+};
+
+/// Token bucket refilled at `rate` tokens per second, holding at most one
+/// second worth of tokens
+pub(crate) struct TokenBucket {
+    rate: f64,
+    /// Available tokens and the time they were counted
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    fn new(requests_per_second: u32) -> Self {
+        let rate = f64::from(requests_per_second.max(1));
+        Self {
+            rate,
+            state: Mutex::new((rate, Instant::now())),
+        }
+    }
+
+    /// Takes a token, waiting for the refill when the bucket is empty.
+    /// Waiters are not queued: under contention any of them may take the
+    /// next token.
+    async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                let (tokens, counted) = *state;
+                let tokens =
+                    (tokens + now.duration_since(counted).as_secs_f64() * self.rate).min(self.rate);
+                if tokens >= 1.0 {
+                    *state = (tokens - 1.0, now);
+                    return;
+                }
+                *state = (tokens, now);
+                Duration::from_secs_f64((1.0 - tokens) / self.rate)
+            };
+            crate::time::sleep(wait).await;
+        }
+    }
+}
+
+impl<T: TransactionRpcProvider> RateLimitedRpcProvider<T> {
+    /// Limits all methods together to `requests_per_second`, allowing bursts
+    /// of as many requests
+    pub fn new(inner: T, requests_per_second: u32) -> Self {
+        Self {
+            inner,
+            limit: Arc::new(TokenBucket::new(requests_per_second)),
+            method_limits: Default::default(),
+        }
+    }
+
+    /// Also limits `method` on its own to `requests_per_second`, within the
+    /// overall limit
+    pub fn with_method_limit(mut self, method: RpcMethod, requests_per_second: u32) -> Self {
+        self.method_limits
+            .insert(method, Arc::new(TokenBucket::new(requests_per_second)));
+        self
+    }
+
+    async fn throttle(&self, method: RpcMethod) {
+        if let Some(bucket) = self.method_limits.get(&method) {
+            bucket.acquire().await;
+        }
+        self.limit.acquire().await;
+    }
+}
+
+impl<T: TransactionRpcProvider + AsRef<RpcClient>> AsRef<RpcClient> for RateLimitedRpcProvider<T> {
+    fn as_ref(&self) -> &RpcClient {
+        self.inner.as_ref()
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: TransactionRpcProvider> TransactionRpcProvider for RateLimitedRpcProvider<T> {
+    async fn get_recent_prioritization_fees(
+        &self,
+        accounts: &[Pubkey],
+    ) -> Result<Vec<RpcPrioritizationFee>> {
+        self.throttle(RpcMethod::Fees).await;
+        self.inner.get_recent_prioritization_fees(accounts).await
+    }
+
+    async fn get_lookup_table_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.throttle(RpcMethod::Lookup).await;
+        self.inner.get_lookup_table_accounts(pubkeys).await
+    }
+
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.throttle(RpcMethod::Lookup).await;
+        self.inner
+            .get_lookup_table_accounts_with_commitment(pubkeys, commitment)
+            .await
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash> {
+        self.throttle(RpcMethod::Blockhash).await;
+        self.inner.get_latest_blockhash().await
+    }
+
+    async fn simulate_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        config: RpcSimulateTransactionConfig,
+    ) -> Result<RpcSimulateTransactionResult> {
+        self.throttle(RpcMethod::Simulate).await;
+        self.inner.simulate_transaction(tx, config).await
+    }
+
+    async fn send_and_confirm_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        config: Option<RpcSendTransactionConfig>,
+    ) -> Result<Signature> {
+        self.throttle(RpcMethod::Send).await;
+        self.inner.send_and_confirm_transaction(tx, config).await
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        self.throttle(RpcMethod::Balance).await;
+        self.inner.get_balance(pubkey).await
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        self.throttle(RpcMethod::Account).await;
+        self.inner.get_account(pubkey).await
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        self.throttle(RpcMethod::Accounts).await;
+        self.inner.get_multiple_accounts(pubkeys).await
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        self.throttle(RpcMethod::ProgramAccounts).await;
+        self.inner.get_program_accounts(program, filters).await
+    }
+
+    async fn get_recent_performance_samples(&self, limit: usize) -> Result<Vec<RpcPerfSample>> {
+        self.throttle(RpcMethod::PerfSamples).await;
+        self.inner.get_recent_performance_samples(limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{CounterRpcProvider, rpc::mock::MockRpc},
+        futures::FutureExt,
+    };
+
+    #[tokio::test]
+    async fn test_rate_limit() -> anyhow::Result<()> {
+        let counter = CounterRpcProvider::new(MockRpc::default());
+        let rpc = RateLimitedRpcProvider::new(counter.clone(), 20);
+
+        // a burst of 20 never waits, then 20 per second
+        let start = Instant::now();
+        for _ in 0..20 {
+            assert!(rpc.get_latest_blockhash().now_or_never().is_some());
+        }
+        for _ in 0..10 {
+            rpc.get_latest_blockhash().await?;
+        }
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(450), "{elapsed:?}");
+        assert_eq!(30, counter.get_counter(&RpcMethod::Blockhash));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_method_limit() -> anyhow::Result<()> {
+        let rpc = RateLimitedRpcProvider::new(MockRpc::default(), 1_000)
+            .with_method_limit(RpcMethod::Balance, 10);
+
+        // other methods are not held to the balance limit
+        for _ in 0..50 {
+            assert!(rpc.get_latest_blockhash().now_or_never().is_some());
+        }
+
+        let start = Instant::now();
+        for _ in 0..10 {
+            assert!(
+                rpc.get_balance(&Pubkey::new_unique())
+                    .now_or_never()
+                    .is_some()
+            );
+        }
+        assert!(
+            rpc.get_balance(&Pubkey::new_unique())
+                .now_or_never()
+                .is_none()
+        );
+        for _ in 0..5 {
+            rpc.get_balance(&Pubkey::new_unique()).await?;
+        }
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(450), "{elapsed:?}");
+        Ok(())
+    }
+}
@@ -0,0 +1,374 @@
+use {
+    super::{RetryConfig, RetryRpcProvider, RpcMethod},
+    crate::{Error, Result, TransactionRpcProvider},
+    solana_commitment_config::CommitmentConfig,
+    solana_hash::Hash,
+    solana_message::AddressLookupTableAccount,
+    solana_pubkey::Pubkey,
+    solana_rpc_client::nonblocking::rpc_client::RpcClient,
+    solana_rpc_client_api::{
+        config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig},
+        filter::RpcFilterType,
+        response::{RpcPerfSample, RpcPrioritizationFee, RpcSimulateTransactionResult},
+    },
+    solana_signature::Signature,
+    solana_transaction::versioned::VersionedTransaction,
+    std::{
+        collections::HashMap,
+        future::Future,
+        hash::{BuildHasher, RandomState},
+        time::Duration,
+    },
+    tracing::warn,
+};
+
+/// Substrings of RPC errors worth retrying: rate limits, timeouts, unhealthy
+/// or lagging nodes and dropped connections
+const TRANSIENT_ERRORS: &[&str] = &[
+    "too many requests",
+    "rate limit",
+    "timed out",
+    "timeout",
+    "node is behind",
+    "node is unhealthy",
+    "minimum context slot has not been reached",
+    "-32005",
+    "connection reset",
+    "connection refused",
+    "connection closed",
+    "error sending request",
+];
+
+/// HTTP statuses worth retrying, only matched as whole words so they never
+/// hit inside pubkeys, signatures or slots
+const TRANSIENT_STATUSES: &[&str] = &["429", "502", "503", "504"];
+
+/// Whether `error` is a transient RPC failure [`RetryRpcProvider`] retries.
+///
+/// Only [`Error::SolanaRpcError`] qualifies: transaction and simulation
+/// failures, and [`Error::Unconfirmed`], would fail the same way again.
+pub fn is_transient(error: &Error) -> bool {
+    let Error::SolanaRpcError(message) = error else {
+        return false;
+    };
+    let message = message.to_lowercase();
+    TRANSIENT_ERRORS
+        .iter()
+        .any(|pattern| message.contains(pattern))
+        || message
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|word| TRANSIENT_STATUSES.contains(&word))
+}
+
+impl Default for RetryConfig {
+    /// 3 attempts from 200ms up to 5s apart with 20% jitter, sends are not
+    /// retried
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            method_attempts: HashMap::from([(RpcMethod::Send, 1)]),
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+            jitter: 0.2,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Overrides `max_attempts` for `method`
+    pub fn with_method_attempts(mut self, method: RpcMethod, attempts: u32) -> Self {
+        self.method_attempts.insert(method, attempts);
+        self
+    }
+
+    /// Attempts for `method`, at least one
+    pub fn attempts(&self, method: RpcMethod) -> u32 {
+        self.method_attempts
+            .get(&method)
+            .copied()
+            .unwrap_or(self.max_attempts)
+            .max(1)
+    }
+
+    /// Delay before the `retry`th retry (from 0), without jitter: doubles from
+    /// `initial_backoff` up to `max_backoff`
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff)
+    }
+
+    fn jittered(&self, retry: u32) -> Duration {
+        let backoff = self.backoff(retry);
+        // uniform in 0..1 from the randomly keyed std hasher
+        let random = RandomState::new().hash_one(retry) as f64 / u64::MAX as f64;
+        backoff + backoff.mul_f64(self.jitter.clamp(0.0, 1.0) * random)
+    }
+}
+
+impl<T: TransactionRpcProvider> RetryRpcProvider<T> {
+    pub fn new(inner: T, config: RetryConfig) -> Self {
+        Self { inner, config }
+    }
+
+    pub fn config(&self) -> &RetryConfig {
+        &self.config
+    }
+
+    /// Runs `call` until it succeeds, fails with a non transient error, or
+    /// runs out of attempts. The last error is returned.
+    async fn retry<R, F, Fut>(&self, method: RpcMethod, mut call: F) -> Result<R>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<R>>,
+    {
+        let attempts = self.config.attempts(method);
+        let mut retry = 0;
+        loop {
+            match call().await {
+                Err(e) if retry + 1 < attempts && is_transient(&e) => {
+                    let delay = self.config.jittered(retry);
+                    warn!(%method, attempt = retry + 1, ?delay, "retrying: {e}");
+                    crate::time::sleep(delay).await;
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl<T: TransactionRpcProvider + AsRef<RpcClient>> AsRef<RpcClient> for RetryRpcProvider<T> {
+    fn as_ref(&self) -> &RpcClient {
+        self.inner.as_ref()
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: TransactionRpcProvider> TransactionRpcProvider for RetryRpcProvider<T> {
+    async fn get_recent_prioritization_fees(
+        &self,
+        accounts: &[Pubkey],
+    ) -> Result<Vec<RpcPrioritizationFee>> {
+        self.retry(RpcMethod::Fees, || {
+            self.inner.get_recent_prioritization_fees(accounts)
+        })
+        .await
+    }
+
+    async fn get_lookup_table_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.retry(RpcMethod::Lookup, || {
+            self.inner.get_lookup_table_accounts(pubkeys)
+        })
+        .await
+    }
+
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.retry(RpcMethod::Lookup, || {
+            self.inner
+                .get_lookup_table_accounts_with_commitment(pubkeys, commitment)
+        })
+        .await
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash> {
+        self.retry(RpcMethod::Blockhash, || self.inner.get_latest_blockhash())
+            .await
+    }
+
+    async fn simulate_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        config: RpcSimulateTransactionConfig,
+    ) -> Result<RpcSimulateTransactionResult> {
+        self.retry(RpcMethod::Simulate, || {
+            self.inner.simulate_transaction(tx, config.clone())
+        })
+        .await
+    }
+
+    /// Retried only when [`RetryConfig::method_attempts`] allows it for
+    /// [`RpcMethod::Send`]. Resending is safe, the transaction keeps its
+    /// signature, but a retry may report a transaction that already landed
+    /// as failed.
+    async fn send_and_confirm_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        config: Option<RpcSendTransactionConfig>,
+    ) -> Result<Signature> {
+        self.retry(RpcMethod::Send, || {
+            self.inner.send_and_confirm_transaction(tx, config)
+        })
+        .await
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        self.retry(RpcMethod::Balance, || self.inner.get_balance(pubkey))
+            .await
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        self.retry(RpcMethod::Account, || self.inner.get_account(pubkey))
+            .await
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        self.retry(RpcMethod::Accounts, || {
+            self.inner.get_multiple_accounts(pubkeys)
+        })
+        .await
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        self.retry(RpcMethod::ProgramAccounts, || {
+            self.inner.get_program_accounts(program, filters.clone())
+        })
+        .await
+    }
+
+    async fn get_recent_performance_samples(&self, limit: usize) -> Result<Vec<RpcPerfSample>> {
+        self.retry(RpcMethod::PerfSamples, || {
+            self.inner.get_recent_performance_samples(limit)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{CounterRpcProvider, rpc::mock::MockRpc},
+        std::sync::atomic::{AtomicU32, Ordering},
+    };
+
+    fn fast() -> RetryConfig {
+        RetryConfig {
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+            ..Default::default()
+        }
+    }
+
+    /// Fails the first `failures` calls with `message`
+    async fn flaky(calls: &AtomicU32, failures: u32, message: &str) -> Result<u32> {
+        let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+        if call <= failures {
+            return Err(Error::SolanaRpcError(message.to_string()));
+        }
+        Ok(call)
+    }
+
+    #[test]
+    fn test_is_transient() {
+        let rpc = |message: &str| Error::SolanaRpcError(message.to_string());
+        assert!(is_transient(&rpc(
+            "HTTP status client error (429 Too Many Requests)"
+        )));
+        assert!(is_transient(&rpc("operation timed out")));
+        assert!(is_transient(&rpc(
+            "RPC response error -32005: Node is behind by 42 slots"
+        )));
+        assert!(is_transient(&rpc("HTTP status server error (503)")));
+        assert!(!is_transient(&rpc("Invalid param: WrongSize")));
+        assert!(!is_transient(&rpc(
+            "AccountNotFound: pubkey=4295HqaDvKdZc7cKoYQbLkR1hKXeDGxxNZtZbsuH9gzr"
+        )));
+        assert!(!is_transient(&rpc(
+            "Slot 150299 was skipped, or missing due to ledger jump"
+        )));
+        assert!(!is_transient(&Error::Unconfirmed(Signature::default())));
+        assert!(!is_transient(&Error::LookupTableMiss));
+    }
+
+    #[test]
+    fn test_backoff() {
+        let config = RetryConfig {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+            jitter: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(Duration::from_millis(100), config.backoff(0));
+        assert_eq!(Duration::from_millis(400), config.backoff(2));
+        assert_eq!(Duration::from_millis(500), config.backoff(3));
+        assert_eq!(Duration::from_millis(500), config.backoff(u32::MAX));
+        for retry in 0..5 {
+            let delay = config.jittered(retry);
+            assert!(delay >= config.backoff(retry), "{delay:?}");
+            assert!(delay <= config.backoff(retry).mul_f64(1.5), "{delay:?}");
+        }
+        assert_eq!(3, config.attempts(RpcMethod::Fees));
+        assert_eq!(1, config.attempts(RpcMethod::Send));
+        let config = config
+            .with_method_attempts(RpcMethod::Send, 2)
+            .with_method_attempts(RpcMethod::Fees, 0);
+        assert_eq!(2, config.attempts(RpcMethod::Send));
+        assert_eq!(1, config.attempts(RpcMethod::Fees));
+    }
+
+    #[tokio::test]
+    async fn test_retry() -> anyhow::Result<()> {
+        let rpc = RetryRpcProvider::new(MockRpc::default(), fast());
+        let calls = AtomicU32::new(0);
+        let result = rpc
+            .retry(RpcMethod::Blockhash, || {
+                flaky(&calls, 2, "429 Too Many Requests")
+            })
+            .await?;
+        assert_eq!(3, result);
+
+        // out of attempts, the last error is returned
+        let calls = AtomicU32::new(0);
+        let result = rpc
+            .retry(RpcMethod::Blockhash, || flaky(&calls, 5, "timed out"))
+            .await;
+        assert!(matches!(result, Err(Error::SolanaRpcError(_))));
+        assert_eq!(3, calls.load(Ordering::SeqCst));
+
+        // not transient, not retried
+        let calls = AtomicU32::new(0);
+        let result = rpc
+            .retry(RpcMethod::Blockhash, || flaky(&calls, 1, "invalid params"))
+            .await;
+        assert!(result.is_err());
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+
+        // sends are not retried by default
+        let calls = AtomicU32::new(0);
+        let result = rpc
+            .retry(RpcMethod::Send, || flaky(&calls, 1, "timed out"))
+            .await;
+        assert!(result.is_err());
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_retry_delegation() -> anyhow::Result<()> {
+        let counter = CounterRpcProvider::new(MockRpc {
+            balance: 42,
+            ..Default::default()
+        });
+        let rpc = RetryRpcProvider::new(counter.clone(), fast());
+        assert_eq!(42, rpc.get_balance(&Pubkey::new_unique()).await?);
+        rpc.get_latest_blockhash().await?;
+        assert_eq!(1, counter.get_counter(&RpcMethod::Balance));
+        assert_eq!(1, counter.get_counter(&RpcMethod::Blockhash));
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use {
+    super::RoundRobinRpcProvider,
+    crate::{Error, Result, TransactionRpcProvider},
+    solana_hash::Hash,
+    solana_message::AddressLookupTableAccount,
+    solana_pubkey::Pubkey,
+    solana_rpc_client_api::response::RpcPrioritizationFee,
+    solana_signature::Signature,
+    std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+impl<T: TransactionRpcProvider> RoundRobinRpcProvider<T> {
+    pub fn new(providers: Vec<T>) -> Self {
+        Self {
+            providers,
+            next: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// The wrapped providers, in rotation order
+    pub fn providers(&self) -> &[T] {
+        &self.providers
+    }
+
+    fn next(&self) -> Result<&T> {
+        if self.providers.is_empty() {
+            return Err(Error::CustomError(
+                "RoundRobinRpcProvider has no providers".to_string(),
+            ));
+        }
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.providers.len();
+        Ok(&self.providers[index])
+    }
+}
+
+impl<T: TransactionRpcProvider> From<Vec<T>> for RoundRobinRpcProvider<T> {
+    fn from(providers: Vec<T>) -> Self {
+        Self::new(providers)
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: TransactionRpcProvider> TransactionRpcProvider for RoundRobinRpcProvider<T> {
+    async fn get_recent_prioritization_fees(
+        &self,
+        accounts: &[Pubkey],
+    ) -> Result<Vec<RpcPrioritizationFee>> {
+        self.next()?.get_recent_prioritization_fees(accounts).await
+    }
+
+    async fn get_lookup_table_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.next()?.get_lookup_table_accounts(pubkeys).await
+    }
+
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: solana_commitment_config::CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.next()?
+            .get_lookup_table_accounts_with_commitment(pubkeys, commitment)
+            .await
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash> {
+        self.next()?.get_latest_blockhash().await
+    }
+
+    async fn simulate_transaction(
+        &self,
+        tx: &solana_transaction::versioned::VersionedTransaction,
+        config: solana_rpc_client_api::config::RpcSimulateTransactionConfig,
+    ) -> Result<solana_rpc_client_api::response::RpcSimulateTransactionResult> {
+        self.next()?.simulate_transaction(tx, config).await
+    }
+
+    async fn send_and_confirm_transaction(
+        &self,
+        tx: &solana_transaction::versioned::VersionedTransaction,
+        config: Option<solana_rpc_client_api::config::RpcSendTransactionConfig>,
+    ) -> Result<Signature> {
+        self.next()?.send_and_confirm_transaction(tx, config).await
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        self.next()?.get_balance(pubkey).await
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        self.next()?.get_account(pubkey).await
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        self.next()?.get_multiple_accounts(pubkeys).await
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<solana_rpc_client_api::filter::RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        self.next()?.get_program_accounts(program, filters).await
+    }
+
+    async fn get_recent_performance_samples(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+        self.next()?.get_recent_performance_samples(limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            CounterRpcProvider,
+            RpcMethod,
+            rpc::noop::{NoopRpc, NoopRpcNative},
+        },
+    };
+
+    #[tokio::test]
+    async fn test_round_robin_alternates() -> anyhow::Result<()> {
+        let first = CounterRpcProvider::new(NoopRpcNative::default());
+        let second = CounterRpcProvider::new(NoopRpc::default());
+        let rpc = RoundRobinRpcProvider::new(vec![first.clone(), second.clone()]);
+
+        rpc.get_latest_blockhash().await?;
+        assert_eq!(1, first.get_counter(&RpcMethod::Blockhash));
+        assert_eq!(0, second.get_counter(&RpcMethod::Blockhash));
+
+        rpc.get_latest_blockhash().await?;
+        assert_eq!(1, first.get_counter(&RpcMethod::Blockhash));
+        assert_eq!(1, second.get_counter(&RpcMethod::Blockhash));
+
+        // the rotation is shared across methods
+        rpc.get_recent_prioritization_fees(&[]).await?;
+        rpc.get_lookup_table_accounts(&[]).await?;
+        assert_eq!(1, first.get_counter(&RpcMethod::Fees));
+        assert_eq!(0, second.get_counter(&RpcMethod::Fees));
+        assert_eq!(0, first.get_counter(&RpcMethod::Lookup));
+        assert_eq!(1, second.get_counter(&RpcMethod::Lookup));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_round_robin_empty() {
+        let rpc: RoundRobinRpcProvider<NoopRpcNative> = RoundRobinRpcProvider::new(vec![]);
+        assert!(rpc.get_latest_blockhash().await.is_err());
+    }
+}
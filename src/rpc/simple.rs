@@ -25,6 +25,16 @@ impl<T: TransactionRpcProvider + Clone, L: TransactionRpcProvider, B: Transactio
         self.lookup_cache.get_lookup_table_accounts(pubkeys).await
     }
 
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: solana_commitment_config::CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.lookup_cache
+            .get_lookup_table_accounts_with_commitment(pubkeys, commitment)
+            .await
+    }
+
     async fn get_latest_blockhash(&self) -> Result<Hash> {
         self.blockhash_cache.get_latest_blockhash().await
     }
@@ -44,4 +54,34 @@ impl<T: TransactionRpcProvider + Clone, L: TransactionRpcProvider, B: Transactio
     ) -> Result<Signature> {
         self.inner.send_and_confirm_transaction(tx, config).await
     }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        self.inner.get_balance(pubkey).await
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        self.inner.get_account(pubkey).await
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        self.inner.get_multiple_accounts(pubkeys).await
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<solana_rpc_client_api::filter::RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        self.inner.get_program_accounts(program, filters).await
+    }
+
+    async fn get_recent_performance_samples(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+        self.inner.get_recent_performance_samples(limit).await
+    }
 }
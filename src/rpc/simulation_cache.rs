@@ -0,0 +1,181 @@
+use {
+    super::SimulationCacheProvider,
+    crate::{Result, TransactionRpcProvider},
+    moka::future::Cache,
+    solana_hash::Hash,
+    solana_message::AddressLookupTableAccount,
+    solana_pubkey::Pubkey,
+    solana_rpc_client::nonblocking::rpc_client::RpcClient,
+    solana_rpc_client_api::{
+        config::RpcSimulateTransactionConfig,
+        response::{RpcPrioritizationFee, RpcSimulateTransactionResult},
+    },
+    solana_signature::Signature,
+    solana_transaction::versioned::VersionedTransaction,
+    std::time::Duration,
+    tracing::{Level, event},
+};
+
+impl<T: TransactionRpcProvider> SimulationCacheProvider<T> {
+    pub fn new(inner: T, ttl: Duration) -> Self {
+        Self {
+            inner,
+            simulations: Cache::builder().time_to_live(ttl).build(),
+        }
+    }
+
+    /// Hash of the message and config, and of the signatures only when they
+    /// are verified
+    fn key(tx: &VersionedTransaction, config: &RpcSimulateTransactionConfig) -> Result<Hash> {
+        let message = bincode::serialize(&tx.message)?;
+        let config_bytes = serde_json::to_vec(config)?;
+        let signatures = if config.sig_verify {
+            bincode::serialize(&tx.signatures)?
+        } else {
+            vec![]
+        };
+        Ok(solana_sha256_hasher::hashv(&[
+            &message,
+            &config_bytes,
+            &signatures,
+        ]))
+    }
+}
+
+impl<T: TransactionRpcProvider + AsRef<RpcClient>> AsRef<RpcClient> for SimulationCacheProvider<T> {
+    fn as_ref(&self) -> &RpcClient {
+        self.inner.as_ref()
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: TransactionRpcProvider> TransactionRpcProvider for SimulationCacheProvider<T> {
+    async fn get_recent_prioritization_fees(
+        &self,
+        accounts: &[Pubkey],
+    ) -> Result<Vec<RpcPrioritizationFee>> {
+        self.inner.get_recent_prioritization_fees(accounts).await
+    }
+
+    async fn get_lookup_table_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.inner.get_lookup_table_accounts(pubkeys).await
+    }
+
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: solana_commitment_config::CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.inner
+            .get_lookup_table_accounts_with_commitment(pubkeys, commitment)
+            .await
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash> {
+        self.inner.get_latest_blockhash().await
+    }
+
+    async fn simulate_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        config: RpcSimulateTransactionConfig,
+    ) -> Result<RpcSimulateTransactionResult> {
+        let key = Self::key(tx, &config)?;
+        self.simulations
+            .try_get_with(key, async {
+                event!(Level::DEBUG, "simulation cache miss");
+                self.inner.simulate_transaction(tx, config).await
+            })
+            .await
+            .map_err(|arc_err| match std::sync::Arc::try_unwrap(arc_err) {
+                Ok(e) => e,
+                Err(arc) => crate::Error::MokaCacheError(arc.to_string()),
+            })
+    }
+
+    async fn send_and_confirm_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        config: Option<solana_rpc_client_api::config::RpcSendTransactionConfig>,
+    ) -> Result<Signature> {
+        self.inner.send_and_confirm_transaction(tx, config).await
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        self.inner.get_balance(pubkey).await
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        self.inner.get_account(pubkey).await
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        self.inner.get_multiple_accounts(pubkeys).await
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<solana_rpc_client_api::filter::RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        self.inner.get_program_accounts(program, filters).await
+    }
+
+    async fn get_recent_performance_samples(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+        self.inner.get_recent_performance_samples(limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            CounterRpcProvider,
+            RpcMethod,
+            SimulateConfigBuilder,
+            TransactionBuilder,
+            rpc::mock::MockRpc,
+        },
+        tokio::time::sleep,
+    };
+
+    #[tokio::test]
+    async fn test_simulation_cache() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let counter = CounterRpcProvider::new(MockRpc::default());
+        let rpc = SimulationCacheProvider::new(counter.clone(), Duration::from_millis(200));
+        let tx = TransactionBuilder::default()
+            .with_memo("simulate", &[&payer])
+            .unsigned_tx(&payer, &rpc)
+            .await?;
+        let config = || SimulateConfigBuilder::new().sig_verify(false).build();
+
+        rpc.simulate_transaction(&tx, config()).await?;
+        rpc.simulate_transaction(&tx, config()).await?;
+        assert_eq!(1, counter.get_counter(&RpcMethod::Simulate));
+
+        // signatures are ignored without sig_verify, other configs miss
+        let mut signed = tx.clone();
+        signed.signatures[0] = Signature::from([1; 64]);
+        rpc.simulate_transaction(&signed, config()).await?;
+        assert_eq!(1, counter.get_counter(&RpcMethod::Simulate));
+        rpc.simulate_transaction(&tx, SimulateConfigBuilder::new().sig_verify(true).build())
+            .await?;
+        assert_eq!(2, counter.get_counter(&RpcMethod::Simulate));
+
+        sleep(Duration::from_millis(400)).await;
+        rpc.simulate_transaction(&tx, config()).await?;
+        assert_eq!(3, counter.get_counter(&RpcMethod::Simulate));
+        Ok(())
+    }
+}
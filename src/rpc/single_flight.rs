@@ -0,0 +1,236 @@
+use {
+    super::SingleFlightRpcProvider,
+    crate::{Error, Result, TransactionRpcProvider},
+    moka::future::Cache,
+    solana_hash::Hash,
+    solana_message::AddressLookupTableAccount,
+    solana_pubkey::Pubkey,
+    solana_rpc_client::nonblocking::rpc_client::RpcClient,
+    solana_rpc_client_api::response::RpcPrioritizationFee,
+    solana_signature::Signature,
+    std::{future::Future, hash::Hash as StdHash, sync::Arc},
+};
+
+impl<T: TransactionRpcProvider> SingleFlightRpcProvider<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            fees: Cache::new(1_000),
+            simulations: Cache::new(1_000),
+        }
+    }
+
+    /// Runs `call` unless an identical call is in flight, in which case its
+    /// result is awaited instead. The entry is dropped once the call
+    /// resolves, so later calls reach the inner provider again.
+    async fn single_flight<K, V>(
+        cache: &Cache<K, V>,
+        key: K,
+        call: impl Future<Output = Result<V>>,
+    ) -> Result<V>
+    where
+        K: StdHash + Eq + Clone + Send + Sync + 'static,
+        V: Clone + Send + Sync + 'static,
+    {
+        let result = cache
+            .try_get_with(key.clone(), call)
+            .await
+            .map_err(Self::handle_shared_error);
+        cache.invalidate(&key).await;
+        result
+    }
+
+    /// Every caller sharing a failed call gets the error. The last one gets
+    /// the original, the others a copy that keeps simulation failures intact.
+    fn handle_shared_error(arc_err: Arc<Error>) -> Error {
+        match Arc::try_unwrap(arc_err) {
+            Ok(err) => err,
+            Err(arc) => match &*arc {
+                Error::SolanaSimulateError {
+                    error,
+                    logs,
+                    transaction_base64,
+                } => Error::SolanaSimulateError {
+                    error: error.clone(),
+                    logs: logs.clone(),
+                    transaction_base64: transaction_base64.clone(),
+                },
+                Error::SolanaRpcError(e) => Error::SolanaRpcError(e.clone()),
+                _ => Error::MokaCacheError(arc.to_string()),
+            },
+        }
+    }
+}
+
+impl<T: TransactionRpcProvider + AsRef<RpcClient>> AsRef<RpcClient> for SingleFlightRpcProvider<T> {
+    fn as_ref(&self) -> &RpcClient {
+        self.inner.as_ref()
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: TransactionRpcProvider> TransactionRpcProvider for SingleFlightRpcProvider<T> {
+    async fn get_recent_prioritization_fees(
+        &self,
+        accounts: &[Pubkey],
+    ) -> Result<Vec<RpcPrioritizationFee>> {
+        let key = bincode::serialize(accounts)?;
+        Self::single_flight(
+            &self.fees,
+            key,
+            self.inner.get_recent_prioritization_fees(accounts),
+        )
+        .await
+    }
+
+    async fn get_lookup_table_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.inner.get_lookup_table_accounts(pubkeys).await
+    }
+
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: solana_commitment_config::CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.inner
+            .get_lookup_table_accounts_with_commitment(pubkeys, commitment)
+            .await
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash> {
+        self.inner.get_latest_blockhash().await
+    }
+
+    async fn simulate_transaction(
+        &self,
+        tx: &solana_transaction::versioned::VersionedTransaction,
+        config: solana_rpc_client_api::config::RpcSimulateTransactionConfig,
+    ) -> Result<solana_rpc_client_api::response::RpcSimulateTransactionResult> {
+        // the config flattens its commitment, which bincode can not serialize
+        let mut key = bincode::serialize(tx)?;
+        key.extend_from_slice(format!("{config:?}").as_bytes());
+        Self::single_flight(
+            &self.simulations,
+            key,
+            self.inner.simulate_transaction(tx, config),
+        )
+        .await
+    }
+
+    async fn send_and_confirm_transaction(
+        &self,
+        tx: &solana_transaction::versioned::VersionedTransaction,
+        config: Option<solana_rpc_client_api::config::RpcSendTransactionConfig>,
+    ) -> Result<Signature> {
+        self.inner.send_and_confirm_transaction(tx, config).await
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        self.inner.get_balance(pubkey).await
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        self.inner.get_account(pubkey).await
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        self.inner.get_multiple_accounts(pubkeys).await
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<solana_rpc_client_api::filter::RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        self.inner.get_program_accounts(program, filters).await
+    }
+
+    async fn get_recent_performance_samples(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+        self.inner.get_recent_performance_samples(limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{CounterRpcProvider, RpcMethod, TransactionBuilder, rpc::mock::MockRpc},
+        futures::future::join_all,
+        solana_rpc_client_api::{config::RpcSimulateTransactionConfig, response::TransactionError},
+        std::time::Duration,
+    };
+
+    fn slow_mock() -> MockRpc {
+        MockRpc {
+            latency: Duration::from_millis(50),
+            prioritization_fees: vec![100, 200],
+            units_consumed: Some(1_000),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_single_flight_fees() -> anyhow::Result<()> {
+        let counter = CounterRpcProvider::new(slow_mock());
+        let rpc = SingleFlightRpcProvider::new(counter.clone());
+        let accounts = [Pubkey::new_unique()];
+
+        let results =
+            join_all((0..10).map(|_| rpc.get_recent_prioritization_fees(&accounts))).await;
+        assert_eq!(1, counter.get_counter(&RpcMethod::Fees));
+        for result in results {
+            assert_eq!(2, result?.len());
+        }
+
+        // different arguments are not coalesced
+        let other = [Pubkey::new_unique()];
+        let (a, b) = tokio::join!(
+            rpc.get_recent_prioritization_fees(&accounts),
+            rpc.get_recent_prioritization_fees(&other)
+        );
+        a?;
+        b?;
+        assert_eq!(3, counter.get_counter(&RpcMethod::Fees));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_single_flight_simulate() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let mock = MockRpc {
+            simulate_error: Some(TransactionError::AccountNotFound),
+            ..slow_mock()
+        };
+        let counter = CounterRpcProvider::new(mock);
+        let rpc = SingleFlightRpcProvider::new(counter.clone());
+        let tx = TransactionBuilder::default()
+            .with_memo("single", &[&payer])
+            .unsigned_tx(&payer, &rpc)
+            .await?;
+
+        let results = join_all(
+            (0..5).map(|_| rpc.simulate_transaction(&tx, RpcSimulateTransactionConfig::default())),
+        )
+        .await;
+        assert_eq!(1, counter.get_counter(&RpcMethod::Simulate));
+        for result in results {
+            assert!(matches!(
+                result,
+                Err(Error::SolanaSimulateError {
+                    error: TransactionError::AccountNotFound,
+                    ..
+                })
+            ));
+        }
+        Ok(())
+    }
+}
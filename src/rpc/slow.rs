@@ -0,0 +1,193 @@
+use {
+    super::{RpcMethod, SlowLogRpcProvider},
+    crate::{Result, TransactionRpcProvider},
+    solana_hash::Hash,
+    solana_message::AddressLookupTableAccount,
+    solana_pubkey::Pubkey,
+    solana_rpc_client::nonblocking::rpc_client::RpcClient,
+    solana_rpc_client_api::response::RpcPrioritizationFee,
+    solana_signature::Signature,
+    std::{
+        future::Future,
+        time::{Duration, Instant},
+    },
+    tracing::warn,
+};
+
+impl<T: TransactionRpcProvider> SlowLogRpcProvider<T> {
+    pub fn new(inner: T, threshold: Duration) -> Self {
+        Self { inner, threshold }
+    }
+
+    async fn timed<R>(
+        &self,
+        method: RpcMethod,
+        call: impl Future<Output = Result<R>>,
+    ) -> Result<R> {
+        let started = Instant::now();
+        let result = call.await;
+        let elapsed = started.elapsed();
+        if elapsed > self.threshold {
+            warn!(
+                method = %method,
+                elapsed_ms = elapsed.as_millis() as u64,
+                threshold_ms = self.threshold.as_millis() as u64,
+                "slow rpc call"
+            );
+        }
+        result
+    }
+}
+
+impl<T: TransactionRpcProvider + AsRef<RpcClient>> AsRef<RpcClient> for SlowLogRpcProvider<T> {
+    fn as_ref(&self) -> &RpcClient {
+        self.inner.as_ref()
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: TransactionRpcProvider> TransactionRpcProvider for SlowLogRpcProvider<T> {
+    async fn get_recent_prioritization_fees(
+        &self,
+        accounts: &[Pubkey],
+    ) -> Result<Vec<RpcPrioritizationFee>> {
+        self.timed(
+            RpcMethod::Fees,
+            self.inner.get_recent_prioritization_fees(accounts),
+        )
+        .await
+    }
+
+    async fn get_lookup_table_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.timed(
+            RpcMethod::Lookup,
+            self.inner.get_lookup_table_accounts(pubkeys),
+        )
+        .await
+    }
+
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: solana_commitment_config::CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        self.timed(
+            RpcMethod::Lookup,
+            self.inner
+                .get_lookup_table_accounts_with_commitment(pubkeys, commitment),
+        )
+        .await
+    }
+
+    async fn get_latest_blockhash(&self) -> Result<Hash> {
+        self.timed(RpcMethod::Blockhash, self.inner.get_latest_blockhash())
+            .await
+    }
+
+    async fn simulate_transaction(
+        &self,
+        tx: &solana_transaction::versioned::VersionedTransaction,
+        config: solana_rpc_client_api::config::RpcSimulateTransactionConfig,
+    ) -> Result<solana_rpc_client_api::response::RpcSimulateTransactionResult> {
+        self.timed(
+            RpcMethod::Simulate,
+            self.inner.simulate_transaction(tx, config),
+        )
+        .await
+    }
+
+    async fn send_and_confirm_transaction(
+        &self,
+        tx: &solana_transaction::versioned::VersionedTransaction,
+        config: Option<solana_rpc_client_api::config::RpcSendTransactionConfig>,
+    ) -> Result<Signature> {
+        self.timed(
+            RpcMethod::Send,
+            self.inner.send_and_confirm_transaction(tx, config),
+        )
+        .await
+    }
+
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        self.timed(RpcMethod::Balance, self.inner.get_balance(pubkey))
+            .await
+    }
+
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        self.timed(RpcMethod::Account, self.inner.get_account(pubkey))
+            .await
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        self.timed(
+            RpcMethod::Accounts,
+            self.inner.get_multiple_accounts(pubkeys),
+        )
+        .await
+    }
+
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<solana_rpc_client_api::filter::RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        self.timed(
+            RpcMethod::ProgramAccounts,
+            self.inner.get_program_accounts(program, filters),
+        )
+        .await
+    }
+
+    async fn get_recent_performance_samples(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+        self.timed(
+            RpcMethod::PerfSamples,
+            self.inner.get_recent_performance_samples(limit),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::rpc::mock::{MockRpc, capture_logs},
+    };
+
+    #[tokio::test]
+    async fn test_slow_call_logged() -> anyhow::Result<()> {
+        let (_guard, logs) = capture_logs();
+        let rpc = SlowLogRpcProvider::new(
+            MockRpc {
+                latency: Duration::from_millis(50),
+                ..Default::default()
+            },
+            Duration::from_millis(10),
+        );
+        rpc.get_latest_blockhash().await?;
+        let logs = logs.contents();
+        assert!(logs.contains("WARN"), "{logs}");
+        assert!(logs.contains("slow rpc call"), "{logs}");
+        assert!(logs.contains("method=blockhash"), "{logs}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fast_call_not_logged() -> anyhow::Result<()> {
+        let (_guard, logs) = capture_logs();
+        let rpc = SlowLogRpcProvider::new(MockRpc::default(), Duration::from_secs(10));
+        rpc.get_latest_blockhash().await?;
+        rpc.get_balance(&Pubkey::new_unique()).await?;
+        assert!(!logs.contents().contains("slow rpc call"));
+        Ok(())
+    }
+}
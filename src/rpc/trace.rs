@@ -1,5 +1,5 @@
 use {
-    crate::{Result, TraceTransactionProvider, TransactionRpcProvider},
+    crate::{Cluster, Result, TraceTransactionProvider, TransactionRpcProvider, cluster_from_url},
     base64::prelude::*,
     solana_hash::Hash,
     solana_message::AddressLookupTableAccount,
@@ -10,6 +10,13 @@ use {
     tracing::debug,
 };
 
+impl<T: AsRef<RpcClient> + Clone> TraceTransactionProvider<T> {
+    /// Cluster of the client's URL, see [`cluster_from_url`].
+    pub fn cluster(&self) -> Option<Cluster> {
+        cluster_from_url(&self.0.as_ref().url())
+    }
+}
+
 #[async_trait::async_trait]
 impl<T: TransactionRpcProvider + AsRef<RpcClient> + Send + Sync + Clone> TransactionRpcProvider
     for TraceTransactionProvider<T>
@@ -30,6 +37,15 @@ impl<T: TransactionRpcProvider + AsRef<RpcClient> + Send + Sync + Clone> Transac
         crate::lookup::fetch_lookup_tables(pubkeys, &self.0).await
     }
 
+    #[tracing::instrument(skip_all, level = tracing::Level::INFO)]
+    async fn get_lookup_table_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: solana_commitment_config::CommitmentConfig,
+    ) -> Result<Vec<AddressLookupTableAccount>> {
+        crate::lookup::fetch_lookup_tables_with_commitment(pubkeys, &self.0, commitment).await
+    }
+
     #[tracing::instrument(skip_all, level = tracing::Level::INFO)]
     async fn get_latest_blockhash(&self) -> Result<Hash> {
         self.0.get_latest_blockhash().await
@@ -57,4 +73,39 @@ impl<T: TransactionRpcProvider + AsRef<RpcClient> + Send + Sync + Clone> Transac
     ) -> Result<Signature> {
         self.0.send_and_confirm_transaction(tx, config).await
     }
+
+    #[tracing::instrument(skip_all, level = tracing::Level::INFO)]
+    async fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        self.0.get_balance(pubkey).await
+    }
+
+    #[tracing::instrument(skip_all, level = tracing::Level::INFO)]
+    async fn get_account(&self, pubkey: &Pubkey) -> Result<Option<solana_account::Account>> {
+        self.0.get_account(pubkey).await
+    }
+
+    #[tracing::instrument(skip_all, level = tracing::Level::INFO)]
+    async fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<solana_account::Account>>> {
+        self.0.get_multiple_accounts(pubkeys).await
+    }
+
+    #[tracing::instrument(skip_all, level = tracing::Level::INFO)]
+    async fn get_program_accounts(
+        &self,
+        program: &Pubkey,
+        filters: Vec<solana_rpc_client_api::filter::RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, solana_account::Account)>> {
+        self.0.get_program_accounts(program, filters).await
+    }
+
+    #[tracing::instrument(skip_all, level = tracing::Level::INFO)]
+    async fn get_recent_performance_samples(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<solana_rpc_client_api::response::RpcPerfSample>> {
+        self.0.get_recent_performance_samples(limit).await
+    }
 }
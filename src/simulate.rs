@@ -0,0 +1,103 @@
+use {
+    solana_account_decoder::UiAccountEncoding,
+    solana_commitment_config::CommitmentConfig,
+    solana_pubkey::Pubkey,
+    solana_rpc_client_api::config::{
+        RpcSimulateTransactionAccountsConfig,
+        RpcSimulateTransactionConfig,
+    },
+};
+
+/// Named setters for [`RpcSimulateTransactionConfig`], used for every
+/// simulation the crate runs.
+///
+/// Unset fields keep their defaults. The wire encoding is left to the
+/// provider.
+#[derive(Debug, Clone, Default)]
+pub struct SimulateConfigBuilder(RpcSimulateTransactionConfig);
+
+impl SimulateConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Verify the signatures, conflicts with `replace_recent_blockhash`
+    pub fn sig_verify(mut self, sig_verify: bool) -> Self {
+        self.0.sig_verify = sig_verify;
+        self
+    }
+
+    /// Let the RPC replace the blockhash with the most recent one
+    pub fn replace_recent_blockhash(mut self, replace: bool) -> Self {
+        self.0.replace_recent_blockhash = replace;
+        self
+    }
+
+    pub fn commitment(mut self, commitment: CommitmentConfig) -> Self {
+        self.0.commitment = Some(commitment);
+        self
+    }
+
+    /// Return the inner instructions executed by the transaction
+    pub fn inner_instructions(mut self, inner_instructions: bool) -> Self {
+        self.0.inner_instructions = inner_instructions;
+        self
+    }
+
+    /// Return the state of `addresses` after the simulation, base64 encoded
+    pub fn accounts<'a>(mut self, addresses: impl IntoIterator<Item = &'a Pubkey>) -> Self {
+        self.0.accounts = Some(RpcSimulateTransactionAccountsConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            addresses: addresses.into_iter().map(Pubkey::to_string).collect(),
+        });
+        self
+    }
+
+    pub fn min_context_slot(mut self, slot: u64) -> Self {
+        self.0.min_context_slot = Some(slot);
+        self
+    }
+
+    pub fn build(self) -> RpcSimulateTransactionConfig {
+        self.0
+    }
+}
+
+impl From<SimulateConfigBuilder> for RpcSimulateTransactionConfig {
+    fn from(builder: SimulateConfigBuilder) -> Self {
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_commitment_config::CommitmentLevel};
+
+    #[test]
+    fn test_simulate_config_builder() {
+        let account = Pubkey::new_unique();
+        let config = SimulateConfigBuilder::new()
+            .sig_verify(true)
+            .commitment(CommitmentConfig::processed())
+            .inner_instructions(true)
+            .accounts([&account])
+            .min_context_slot(42)
+            .build();
+        assert!(config.sig_verify);
+        assert!(!config.replace_recent_blockhash);
+        assert_eq!(
+            Some(CommitmentLevel::Processed),
+            config.commitment.map(|c| c.commitment)
+        );
+        assert!(config.inner_instructions);
+        assert_eq!(Some(42), config.min_context_slot);
+        assert_eq!(None, config.encoding);
+        let accounts = config.accounts.expect("accounts");
+        assert_eq!(vec![account.to_string()], accounts.addresses);
+        assert_eq!(Some(UiAccountEncoding::Base64), accounts.encoding);
+
+        let default: RpcSimulateTransactionConfig = SimulateConfigBuilder::new().into();
+        assert!(!default.sig_verify);
+        assert!(default.accounts.is_none());
+    }
+}
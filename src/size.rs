@@ -0,0 +1,417 @@
+use {
+    super::{Error, Result, TransactionBuilder},
+    crate::TransactionRpcProvider,
+    solana_hash::Hash,
+    solana_instruction::Instruction,
+    solana_message::{
+        AddressLookupTableAccount,
+        VersionedMessage,
+        compiled_instruction::CompiledInstruction,
+    },
+    solana_pubkey::Pubkey,
+};
+
+/// Maximum size of a serialized transaction (IPv6 MTU minus headers).
+pub const PACKET_DATA_SIZE: usize = 1280 - 40 - 8;
+const SIGNATURE_SIZE: usize = 64;
+/// Maximum number of accounts a transaction may lock, lookup table addresses
+/// included.
+pub const MAX_TX_ACCOUNTS: usize = 64;
+
+/// Bytes used by the compact-u16 ("short vec") length prefix of `len`
+fn short_vec_len(len: usize) -> usize {
+    match len {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    }
+}
+
+fn compiled_instruction_size(ix: &CompiledInstruction) -> usize {
+    1 + short_vec_len(ix.accounts.len())
+        + ix.accounts.len()
+        + short_vec_len(ix.data.len())
+        + ix.data.len()
+}
+
+/// Serialized size in bytes of a transaction carrying `message`, signatures
+/// included.
+///
+/// The size is taken from the message's own serialization, so version
+/// specific overhead (the V0 version prefix and address table lookups) is
+/// accounted for.
+pub fn transaction_size(message: &VersionedMessage) -> Result<usize> {
+    let num_sigs = message.header().num_required_signatures as usize;
+    let message_size: usize = bincode::serialized_size(message)?.try_into()?;
+    Ok(short_vec_len(num_sigs) + num_sigs * SIGNATURE_SIZE + message_size)
+}
+
+/// Number of accounts referenced by `message`, including the addresses it
+/// loads from lookup tables.
+pub fn account_count(message: &VersionedMessage) -> usize {
+    let loaded: usize = message
+        .address_table_lookups()
+        .into_iter()
+        .flatten()
+        .map(|lookup| lookup.writable_indexes.len() + lookup.readonly_indexes.len())
+        .sum();
+    message.static_account_keys().len() + loaded
+}
+
+impl TransactionBuilder {
+    /// Serialized size in bytes of the signed transaction, see
+    /// [`transaction_size`].
+    pub async fn transaction_size<T: TransactionRpcProvider>(
+        &self,
+        payer: &Pubkey,
+        rpc: &T,
+    ) -> Result<usize> {
+        transaction_size(&self.compile_message(payer, rpc).await?)
+    }
+
+    /// Whether the signed transaction exceeds [`PACKET_DATA_SIZE`] once the
+    /// `SetComputeUnitLimit` and `SetComputeUnitPrice` instructions are added,
+    /// e.g. by [`TransactionBuilder::with_priority_fees`].
+    ///
+    /// Check this before fee estimation to move accounts into a lookup table
+    /// or split the transaction first. A builder which already carries
+    /// compute budget instructions is sized as is.
+    pub async fn would_exceed_limit_with_budget<T: TransactionRpcProvider>(
+        &self,
+        payer: &Pubkey,
+        rpc: &T,
+    ) -> Result<bool> {
+        let budgeted = match self.clone().prepend_compute_budget_instructions(0, 0) {
+            Ok(budgeted) => budgeted,
+            Err(Error::ComputeBudgetAlreadyPresent) => self.clone(),
+            Err(e) => return Err(e),
+        };
+        Ok(budgeted.transaction_size(payer, rpc).await? > PACKET_DATA_SIZE)
+    }
+
+    /// Estimates how many more instructions, of the current average compiled
+    /// size, fit before the transaction reaches [`PACKET_DATA_SIZE`].
+    ///
+    /// New instructions are assumed to reference accounts already in the
+    /// message, so treat the result as an upper bound.
+    pub async fn max_instructions_estimate<T: TransactionRpcProvider>(
+        &self,
+        payer: &Pubkey,
+        rpc: &T,
+    ) -> Result<usize> {
+        if self.instructions.is_empty() {
+            return Err(Error::NoInstructions);
+        }
+        let message = self.create_message(payer, rpc).await?;
+        let size = transaction_size(&message)?;
+        let instructions = message.instructions();
+        let average = instructions
+            .iter()
+            .map(compiled_instruction_size)
+            .sum::<usize>()
+            .div_ceil(instructions.len());
+        Ok(PACKET_DATA_SIZE.saturating_sub(size) / average.max(1))
+    }
+
+    /// Partitions the instructions into builders which each compile within
+    /// [`PACKET_DATA_SIZE`] and [`MAX_TX_ACCOUNTS`] with `lookup_tables`,
+    /// keeping their relative order.
+    ///
+    /// Compute budget instructions are repeated at the start of every chunk,
+    /// so each chunk keeps the compute unit limit of the original
+    /// transaction. Memos before the first or after the last other
+    /// instruction are repeated at the same place in every chunk, memos in
+    /// between stay where they are. Compute units are only known from
+    /// simulation: re-estimate each chunk, e.g. with
+    /// [`TransactionBuilder::with_priority_fees`] on its
+    /// [`TransactionBuilder::template`]. A repeated
+    /// [`TransactionBuilder::with_integrity_memo`] memo does not verify
+    /// against the chunk.
+    ///
+    /// Only the first chunk advances a durable nonce, with the
+    /// `AdvanceNonceAccount` instruction in front of the compute budget
+    /// instructions, the others use the latest blockhash. Fails with the size
+    /// error of an instruction which does not fit in a transaction on its
+    /// own.
+    pub fn split(
+        &self,
+        payer: &Pubkey,
+        lookup_tables: &[AddressLookupTableAccount],
+    ) -> Result<Vec<TransactionBuilder>> {
+        let is_budget = |ix: &Instruction| ix.program_id == solana_compute_budget_interface::ID;
+        let is_memo = |ix: &Instruction| ix.program_id == spl_memo_interface::v3::ID;
+        let advance_nonce = self.durable_nonce.as_ref().map(|nonce| {
+            solana_system_interface::instruction::advance_nonce_account(
+                &nonce.account,
+                &nonce.authority,
+            )
+        });
+        let budget: Vec<_> = self
+            .instructions
+            .iter()
+            .filter(|ix| is_budget(ix))
+            .cloned()
+            .collect();
+        let body: Vec<_> = self
+            .instructions
+            .iter()
+            .filter(|ix| !is_budget(ix) && Some(*ix) != advance_nonce.as_ref())
+            .collect();
+        let Some(start) = body.iter().position(|ix| !is_memo(ix)) else {
+            if self.instructions.is_empty() {
+                return Err(Error::NoInstructions);
+            }
+            return Ok(vec![self.clone()]);
+        };
+        let end = body.iter().rposition(|ix| !is_memo(ix)).unwrap_or(start) + 1;
+        let (leading, trailing) = (&body[..start], &body[end..]);
+        let remaining = &body[start..end];
+
+        let chunk = |instructions: &[Instruction], first: bool| {
+            let mut builder = self.clone();
+            let nonce = advance_nonce.iter().filter(|_| first);
+            builder.instructions = nonce
+                .chain(&budget)
+                .chain(leading.iter().copied())
+                .chain(instructions)
+                .chain(trailing.iter().copied())
+                .cloned()
+                .collect();
+            if !lookup_tables.is_empty() {
+                builder.address_lookup_tables = Some(lookup_tables.to_vec());
+            }
+            if !first {
+                builder.durable_nonce = None;
+            }
+            builder
+        };
+        let fits = |builder: &TransactionBuilder| {
+            builder.compile_offline(payer, Hash::default(), lookup_tables)
+        };
+
+        let mut chunks = Vec::new();
+        let mut current: Vec<Instruction> = Vec::new();
+        for &ix in remaining {
+            current.push(ix.clone());
+            match fits(&chunk(&current, chunks.is_empty())) {
+                Ok(_) => {}
+                Err(Error::TransactionTooLarge { .. } | Error::TooManyAccounts { .. })
+                    if current.len() > 1 =>
+                {
+                    current.pop();
+                    chunks.push(chunk(&current, chunks.is_empty()));
+                    current = vec![ix.clone()];
+                    fits(&chunk(&current, false))?;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        chunks.push(chunk(&current, chunks.is_empty()));
+        Ok(chunks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::rpc::mock::MockRpc,
+        solana_message::AddressLookupTableAccount,
+        solana_signature::Signature,
+        solana_transaction::versioned::VersionedTransaction,
+    };
+
+    fn signed_size(message: VersionedMessage) -> usize {
+        let num_sigs = message.header().num_required_signatures as usize;
+        bincode::serialize(&VersionedTransaction {
+            signatures: vec![Signature::default(); num_sigs],
+            message,
+        })
+        .unwrap()
+        .len()
+    }
+
+    #[test]
+    fn test_short_vec_len() {
+        assert_eq!(1, short_vec_len(0));
+        assert_eq!(1, short_vec_len(127));
+        assert_eq!(2, short_vec_len(128));
+        assert_eq!(2, short_vec_len(16_383));
+        assert_eq!(3, short_vec_len(16_384));
+    }
+
+    #[tokio::test]
+    async fn test_transaction_size_legacy() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let rpc = MockRpc::default();
+        let tx = TransactionBuilder::default()
+            .with_memo("size", &[&payer])
+            .with_memo("size", &[&payer]);
+        let message = tx.create_message(&payer, &rpc).await?;
+        assert!(matches!(message, VersionedMessage::Legacy(_)));
+        let size = tx.transaction_size(&payer, &rpc).await?;
+        assert_eq!(signed_size(message), size);
+
+        let estimate = tx.max_instructions_estimate(&payer, &rpc).await?;
+        // memo instruction: program index, 1 account, 4 bytes of data
+        assert_eq!((PACKET_DATA_SIZE - size) / 8, estimate);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_would_exceed_limit_with_budget() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let rpc = MockRpc::default();
+        // memos above 127 bytes, so the data length prefix stays 2 bytes
+        let memo = |len: usize| TransactionBuilder::default().with_memo(vec![b'a'; len], &[&payer]);
+        let small = memo(200);
+        let size = small.transaction_size(&payer, &rpc).await?;
+        assert!(!small.would_exceed_limit_with_budget(&payer, &rpc).await?);
+
+        let near_limit = memo(200 + PACKET_DATA_SIZE - size);
+        assert_eq!(
+            PACKET_DATA_SIZE,
+            near_limit.transaction_size(&payer, &rpc).await?
+        );
+        assert!(
+            near_limit
+                .would_exceed_limit_with_budget(&payer, &rpc)
+                .await?
+        );
+
+        // compute budget already present, the builder is sized as is
+        let budgeted = near_limit.prepend_compute_budget_instructions(1, 1)?;
+        let budget_size = budgeted.transaction_size(&payer, &rpc).await? - PACKET_DATA_SIZE;
+        let budgeted = memo(200 + PACKET_DATA_SIZE - size - budget_size)
+            .prepend_compute_budget_instructions(1, 1)?;
+        assert_eq!(
+            PACKET_DATA_SIZE,
+            budgeted.transaction_size(&payer, &rpc).await?
+        );
+        assert!(
+            !budgeted
+                .would_exceed_limit_with_budget(&payer, &rpc)
+                .await?
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_transaction_size_v0() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let rpc = MockRpc::default();
+        let legacy = TransactionBuilder::default().with_memo("size", &[&payer]);
+        let v0 = legacy
+            .clone()
+            .with_address_tables([AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: vec![Pubkey::new_unique()],
+            }]);
+        let message = v0.create_message(&payer, &rpc).await?;
+        assert!(matches!(message, VersionedMessage::V0(_)));
+        let v0_size = v0.transaction_size(&payer, &rpc).await?;
+        assert_eq!(signed_size(message), v0_size);
+        // version prefix and empty address table lookups
+        assert_eq!(legacy.transaction_size(&payer, &rpc).await? + 2, v0_size);
+        assert!(
+            v0.max_instructions_estimate(&payer, &rpc).await?
+                <= legacy.max_instructions_estimate(&payer, &rpc).await?
+        );
+
+        let empty = TransactionBuilder::default();
+        assert!(matches!(
+            empty.max_instructions_estimate(&payer, &rpc).await,
+            Err(Error::NoInstructions)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_split() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let transfers: Vec<_> = (0..60)
+            .map(|i| {
+                solana_system_interface::instruction::transfer(&payer, &Pubkey::new_unique(), i)
+            })
+            .collect();
+        let tx = TransactionBuilder::from(transfers.clone())
+            .with_memo("split", &[&payer])
+            .prepend_compute_budget_instructions(200_000, 1_000)?;
+        let chunks = tx.split(&payer, &[])?;
+        assert!(chunks.len() > 1);
+
+        let mut split_transfers = Vec::new();
+        for chunk in &chunks {
+            chunk.compile_offline(&payer, Hash::default(), &[])?;
+            assert_eq!(tx.instructions[..2], chunk.instructions[..2]);
+            assert_eq!(tx.instructions.last(), chunk.instructions.last());
+            let inner = &chunk.instructions[2..chunk.instructions.len() - 1];
+            split_transfers.extend(inner.iter().cloned());
+        }
+        assert_eq!(transfers, split_transfers);
+
+        // the table removes the account limit, only the size remains
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: transfers.iter().map(|ix| ix.accounts[1].pubkey).collect(),
+        };
+        let with_table = tx.split(&payer, std::slice::from_ref(&table))?;
+        assert!(with_table.len() < chunks.len());
+        assert_eq!(Some(vec![table]), with_table[0].address_lookup_tables);
+
+        let single = TransactionBuilder::default().with_memo("single", &[&payer]);
+        assert_eq!(1, single.split(&payer, &[])?.len());
+        let too_large = TransactionBuilder::default().push(Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[0; PACKET_DATA_SIZE],
+            vec![],
+        ));
+        assert!(matches!(
+            too_large.split(&payer, &[]),
+            Err(Error::TransactionTooLarge { .. })
+        ));
+        assert!(matches!(
+            TransactionBuilder::default().split(&payer, &[]),
+            Err(Error::NoInstructions)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_durable_nonce() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let nonce = Pubkey::new_unique();
+        let transfers: Vec<_> = (0..60)
+            .map(|i| {
+                solana_system_interface::instruction::transfer(&payer, &Pubkey::new_unique(), i)
+            })
+            .collect();
+        let (first, rest) = transfers.split_at(30);
+        let mut tx = TransactionBuilder::default().with_memo("header", &[&payer]);
+        tx.instructions.extend_from_slice(first);
+        let mut tx = tx.with_memo("middle", &[&payer]);
+        tx.instructions.extend_from_slice(rest);
+        let tx = tx
+            .prepend_compute_budget_instructions(200_000, 1_000)?
+            .with_durable_nonce(&nonce, &payer, Hash::new_unique());
+        let chunks = tx.split(&payer, &[])?;
+        assert!(chunks.len() > 1);
+
+        let advance = solana_system_interface::instruction::advance_nonce_account(&nonce, &payer);
+        assert_eq!(advance, chunks[0].instructions[0]);
+        assert_eq!(tx.instructions[..4], chunks[0].instructions[..4]);
+        assert!(chunks[0].durable_nonce.is_some());
+        chunks[0].compile_offline(&payer, Hash::default(), &[])?;
+        let mut body = chunks[0].instructions[4..].to_vec();
+        for chunk in &chunks[1..] {
+            chunk.compile_offline(&payer, Hash::default(), &[])?;
+            assert!(chunk.durable_nonce.is_none());
+            // budget and header memo, without the nonce
+            assert_eq!(tx.instructions[1..4], chunk.instructions[..3]);
+            body.extend_from_slice(&chunk.instructions[3..]);
+        }
+        // the middle memo keeps its place between the transfers
+        assert_eq!(tx.instructions[4..], body[..]);
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use {
+    futures::channel::oneshot,
+    std::{
+        cmp::Reverse,
+        collections::BinaryHeap,
+        sync::{Condvar, LazyLock, Mutex, Once},
+        time::{Duration, Instant},
+    },
+};
+
+/// Waits `duration` on the tokio timer when a tokio runtime is running.
+///
+/// Elsewhere, e.g. under [`futures::executor::block_on`] or without the
+/// `tokio` feature, the task is woken by a single timer thread shared by all
+/// sleeps, so delays work on any executor.
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(feature = "tokio")]
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return tokio::time::sleep(duration).await;
+    }
+    let _ = TIMER.schedule(Instant::now() + duration).await;
+}
+
+static TIMER: LazyLock<Timer> = LazyLock::new(|| Timer {
+    queue: Default::default(),
+    changed: Condvar::new(),
+    started: Once::new(),
+});
+
+/// A pending sleep, ordered by deadline then scheduling order
+struct Entry {
+    deadline: Instant,
+    id: u64,
+    wake: oneshot::Sender<()>,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        (self.deadline, self.id) == (other.deadline, other.id)
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.deadline, self.id).cmp(&(other.deadline, other.id))
+    }
+}
+
+/// Deadlines of pending sleeps, earliest first, served by one thread started
+/// on first use. Dropped sleeps are discarded once their deadline passes.
+struct Timer {
+    queue: Mutex<(u64, BinaryHeap<Reverse<Entry>>)>,
+    changed: Condvar,
+    started: Once,
+}
+
+impl Timer {
+    fn schedule(&'static self, deadline: Instant) -> oneshot::Receiver<()> {
+        self.started.call_once(|| {
+            std::thread::Builder::new()
+                .name("soly-timer".to_string())
+                .spawn(|| self.run())
+                .expect("failed to spawn the timer thread");
+        });
+        let (wake, receiver) = oneshot::channel();
+        let mut queue = self.queue.lock().unwrap();
+        let (next_id, entries) = &mut *queue;
+        *next_id += 1;
+        entries.push(Reverse(Entry {
+            deadline,
+            id: *next_id,
+            wake,
+        }));
+        self.changed.notify_one();
+        receiver
+    }
+
+    fn run(&self) {
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            queue = match queue.1.peek() {
+                None => self.changed.wait(queue).unwrap(),
+                Some(Reverse(entry)) if entry.deadline <= now => {
+                    if let Some(Reverse(entry)) = queue.1.pop() {
+                        let _ = entry.wake.send(());
+                    }
+                    queue
+                }
+                Some(Reverse(entry)) => {
+                    let timeout = entry.deadline - now;
+                    self.changed.wait_timeout(queue, timeout).unwrap().0
+                }
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sleep_without_runtime() {
+        let started = Instant::now();
+        futures::executor::block_on(sleep(Duration::from_millis(20)));
+        assert!(started.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_concurrent_sleeps_without_runtime() {
+        // wake in deadline order, whatever the scheduling order
+        let started = Instant::now();
+        let order = Mutex::new(Vec::new());
+        let sleeps = [30, 10, 20].map(|ms| {
+            let order = &order;
+            async move {
+                sleep(Duration::from_millis(ms)).await;
+                order.lock().unwrap().push(ms);
+            }
+        });
+        futures::executor::block_on(futures::future::join_all(sleeps));
+        assert_eq!(vec![10, 20, 30], *order.lock().unwrap());
+        assert!(started.elapsed() >= Duration::from_millis(30));
+    }
+}
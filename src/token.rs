@@ -0,0 +1,291 @@
+use {
+    super::{Error, Result, TransactionBuilder},
+    crate::TransactionRpcProvider,
+    solana_instruction::{AccountMeta, Instruction},
+    solana_pubkey::{Pubkey, pubkey},
+};
+
+/// Associated Token Account program
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+/// SPL Token program
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+/// SPL Token-2022 program
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EHFLC1PQuG6KJqTmjmwJ");
+
+/// Associated token account of `owner` for `mint`, owned by `token_program`.
+pub fn get_associated_token_address(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+/// `CreateIdempotent` instruction of the Associated Token Account program,
+/// which succeeds when the account already exists.
+fn create_associated_token_account_idempotent(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    let ata = get_associated_token_address(owner, mint, token_program);
+    Instruction {
+        program_id: ASSOCIATED_TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(ata, false),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        data: vec![1],
+    }
+}
+
+/// `CloseAccount` instruction of the token program, moving the rent of an
+/// empty `account` to `destination`. Token and Token-2022 share the layout.
+fn close_account(
+    account: &Pubkey,
+    destination: &Pubkey,
+    owner: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *token_program,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: vec![9],
+    }
+}
+
+impl TransactionBuilder {
+    /// Prepends a create-idempotent instruction for the associated token
+    /// account of `owner` when it does not exist yet, paid by `payer`.
+    ///
+    /// The token program (Token or Token-2022) is taken from the owner of the
+    /// `mint` account.
+    pub async fn ensure_ata<T: TransactionRpcProvider>(
+        mut self,
+        payer: &Pubkey,
+        owner: &Pubkey,
+        mint: &Pubkey,
+        rpc: &T,
+    ) -> Result<Self> {
+        let token_program = rpc
+            .get_account(mint)
+            .await?
+            .ok_or(Error::AccountNotFound(*mint))?
+            .owner;
+        let ata = get_associated_token_address(owner, mint, &token_program);
+        if rpc.get_account(&ata).await?.is_none() {
+            self.instructions.insert(
+                0,
+                create_associated_token_account_idempotent(payer, owner, mint, &token_program),
+            );
+        }
+        Ok(self)
+    }
+
+    /// Appends a `CloseAccount` instruction for each of `accounts`, sending
+    /// their rent to `destination`. `owner` must sign, and `token_program`
+    /// ([`TOKEN_PROGRAM_ID`] or [`TOKEN_2022_PROGRAM_ID`]) must own the
+    /// accounts.
+    ///
+    /// The accounts must be empty, the token program rejects closing an
+    /// account holding tokens. Fails when `accounts` is empty.
+    pub fn with_close_accounts(
+        mut self,
+        accounts: &[Pubkey],
+        owner: &Pubkey,
+        destination: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<Self> {
+        if accounts.is_empty() {
+            return Err(Error::CustomError("no token accounts to close".to_string()));
+        }
+        self.instructions.extend(
+            accounts
+                .iter()
+                .map(|account| close_account(account, destination, owner, token_program)),
+        );
+        Ok(self)
+    }
+}
+
+/// The `(owner, mint)` pairs whose associated token account does not exist,
+/// in input order.
+///
+/// Meant for payout batches: mints and associated token accounts are each
+/// read with a single [`TransactionRpcProvider::get_multiple_accounts`]
+/// call. The token program of every ATA is taken from the owner of its mint.
+pub async fn missing_atas<T: TransactionRpcProvider>(
+    owners_and_mints: &[(Pubkey, Pubkey)],
+    rpc: &T,
+) -> Result<Vec<(Pubkey, Pubkey)>> {
+    let mut mints: Vec<Pubkey> = owners_and_mints.iter().map(|(_, mint)| *mint).collect();
+    mints.sort_unstable();
+    mints.dedup();
+    let mut token_programs = std::collections::HashMap::with_capacity(mints.len());
+    for (mint, account) in mints.iter().zip(rpc.get_multiple_accounts(&mints).await?) {
+        let account = account.ok_or(Error::AccountNotFound(*mint))?;
+        token_programs.insert(*mint, account.owner);
+    }
+    let atas: Vec<Pubkey> = owners_and_mints
+        .iter()
+        .map(|(owner, mint)| get_associated_token_address(owner, mint, &token_programs[mint]))
+        .collect();
+    let accounts = rpc.get_multiple_accounts(&atas).await?;
+    Ok(owners_and_mints
+        .iter()
+        .zip(accounts)
+        .filter(|(_, account)| account.is_none())
+        .map(|(pair, _)| *pair)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{CounterRpcProvider, RpcMethod, rpc::mock::MockRpc},
+        solana_account::Account,
+    };
+
+    fn mint_account(token_program: Pubkey) -> Account {
+        Account {
+            owner: token_program,
+            ..Account::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ensure_ata_create() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let mut rpc = MockRpc::default();
+        rpc.accounts
+            .insert(mint, mint_account(TOKEN_2022_PROGRAM_ID));
+
+        let tx = TransactionBuilder::default()
+            .with_memo("ata", &[&payer])
+            .ensure_ata(&payer, &owner, &mint, &rpc)
+            .await?;
+        assert_eq!(2, tx.instructions.len());
+        let create = &tx.instructions[0];
+        assert_eq!(ASSOCIATED_TOKEN_PROGRAM_ID, create.program_id);
+        assert_eq!(vec![1], create.data);
+        assert_eq!(
+            get_associated_token_address(&owner, &mint, &TOKEN_2022_PROGRAM_ID),
+            create.accounts[1].pubkey
+        );
+        assert_eq!(TOKEN_2022_PROGRAM_ID, create.accounts[5].pubkey);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_ensure_ata_exists() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let mut rpc = MockRpc::default();
+        rpc.accounts.insert(mint, mint_account(TOKEN_PROGRAM_ID));
+        rpc.accounts.insert(
+            get_associated_token_address(&owner, &mint, &TOKEN_PROGRAM_ID),
+            Account::default(),
+        );
+
+        let tx = TransactionBuilder::default()
+            .with_memo("ata", &[&payer])
+            .ensure_ata(&payer, &owner, &mint, &rpc)
+            .await?;
+        assert_eq!(1, tx.instructions.len());
+
+        let missing_mint = TransactionBuilder::default()
+            .ensure_ata(&payer, &owner, &Pubkey::new_unique(), &rpc)
+            .await;
+        assert!(matches!(missing_mint, Err(Error::AccountNotFound(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_close_accounts() -> anyhow::Result<()> {
+        let owner = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let accounts = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let tx = TransactionBuilder::default().with_close_accounts(
+            &accounts,
+            &owner,
+            &destination,
+            &TOKEN_PROGRAM_ID,
+        )?;
+        assert_eq!(2, tx.instructions.len());
+        for (ix, account) in tx.instructions.iter().zip(accounts) {
+            assert_eq!(TOKEN_PROGRAM_ID, ix.program_id);
+            assert_eq!(vec![9], ix.data);
+            assert_eq!(
+                vec![
+                    AccountMeta::new(account, false),
+                    AccountMeta::new(destination, false),
+                    AccountMeta::new_readonly(owner, true),
+                ],
+                ix.accounts
+            );
+        }
+
+        let empty = TransactionBuilder::default().with_close_accounts(
+            &[],
+            &owner,
+            &destination,
+            &TOKEN_PROGRAM_ID,
+        );
+        assert!(empty.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_missing_atas() -> anyhow::Result<()> {
+        let mint = Pubkey::new_unique();
+        let mint_2022 = Pubkey::new_unique();
+        let owners: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let mut mock = MockRpc::default();
+        mock.accounts.insert(mint, mint_account(TOKEN_PROGRAM_ID));
+        mock.accounts
+            .insert(mint_2022, mint_account(TOKEN_2022_PROGRAM_ID));
+        for (owner, mint, token_program) in [
+            (owners[0], mint, TOKEN_PROGRAM_ID),
+            (owners[2], mint_2022, TOKEN_2022_PROGRAM_ID),
+        ] {
+            mock.accounts.insert(
+                get_associated_token_address(&owner, &mint, &token_program),
+                Account::default(),
+            );
+        }
+        let rpc = CounterRpcProvider::new(mock);
+        let pairs = [
+            (owners[0], mint),
+            (owners[1], mint),
+            (owners[2], mint_2022),
+            (owners[3], mint_2022),
+        ];
+
+        let missing = missing_atas(&pairs, &rpc).await?;
+        assert_eq!(vec![pairs[1], pairs[3]], missing);
+        assert_eq!(2, rpc.get_counter(&RpcMethod::Accounts));
+        assert_eq!(0, rpc.get_counter(&RpcMethod::Account));
+
+        let unknown = missing_atas(&[(owners[0], Pubkey::new_unique())], &rpc).await;
+        assert!(matches!(unknown, Err(Error::AccountNotFound(_))));
+        Ok(())
+    }
+}
@@ -1,19 +1,28 @@
 use {
-    super::{InstructionBuilder, IntoInstruction, Result},
-    crate::TransactionRpcProvider,
+    super::{Error, InstructionBuilder, IntoInstruction, Result},
+    crate::{
+        MAX_TX_ACCOUNTS,
+        PACKET_DATA_SIZE,
+        ResolvedTables,
+        SimulateConfigBuilder,
+        TransactionRpcProvider,
+        account_count,
+        transaction_size,
+    },
+    base64::prelude::*,
     borsh::BorshSerialize,
     solana_hash::Hash,
-    solana_instruction::Instruction,
-    solana_message::{AddressLookupTableAccount, VersionedMessage, v0::Message},
+    solana_instruction::{AccountMeta, Instruction, error::InstructionError},
+    solana_message::{AddressLookupTableAccount, MessageHeader, VersionedMessage, v0::Message},
     solana_pubkey::Pubkey,
     solana_rpc_client_api::{
-        config::RpcSimulateTransactionConfig,
-        response::RpcSimulateTransactionResult,
+        config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig},
+        response::{RpcSimulateTransactionResult, TransactionError},
     },
     solana_signature::Signature,
     solana_signer::signers::Signers,
     solana_transaction::versioned::VersionedTransaction,
-    std::fmt::Debug,
+    std::{borrow::Cow, collections::HashSet, fmt::Debug, sync::Arc},
 };
 
 /// Builder/Helper for creating and sending Solana [`VersionedTransaction`]s,
@@ -29,6 +38,171 @@ pub struct TransactionBuilder {
 
     /// For [`VersionedTransaction`]
     pub address_lookup_tables: Option<Vec<AddressLookupTableAccount>>,
+
+    /// Simulation failures with these errors do not stop
+    /// [`TransactionBuilder::send`], see
+    /// [`TransactionBuilder::with_tolerated_errors`]
+    pub tolerated_errors: Option<Vec<InstructionError>>,
+
+    /// Fallback compute unit limit when simulation reports no units, see
+    /// [`TransactionBuilder::with_default_compute_units`]
+    pub default_compute_units: Option<u32>,
+
+    /// Cap on the compute unit limit, see
+    /// [`TransactionBuilder::with_max_compute_units`]
+    pub max_compute_units: Option<u32>,
+
+    /// Minimum priority fee in microlamports per compute unit for
+    /// transactions write-locking the account, see
+    /// [`TransactionBuilder::with_fee_floors`]
+    pub fee_floors: Option<std::collections::HashMap<Pubkey, u64>>,
+
+    /// Compile with the nonce blockhash instead of the latest one, see
+    /// [`TransactionBuilder::with_durable_nonce`]
+    pub durable_nonce: Option<DurableNonce>,
+
+    /// See [`TransactionBuilder::with_pre_hook`]
+    pub pre_hooks: Option<Vec<InstructionHook>>,
+
+    /// See [`TransactionBuilder::with_post_hook`]
+    pub post_hooks: Option<Vec<InstructionHook>>,
+
+    /// [`TransactionBuilder::send`] refuses to submit, see
+    /// [`TransactionBuilder::simulate_only`]
+    #[builder(default)]
+    pub simulate_only: bool,
+
+    /// Recorded in the tracing spans, see [`TransactionBuilder::with_label`]
+    #[builder(into)]
+    pub label: Option<String>,
+}
+
+/// Durable nonce account used instead of a recent blockhash, see
+/// [`TransactionBuilder::with_durable_nonce`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DurableNonce {
+    pub account: Pubkey,
+    /// Signs the `AdvanceNonceAccount` instruction
+    pub authority: Pubkey,
+    /// Blockhash stored in the nonce account
+    pub blockhash: Hash,
+}
+
+/// The authority and blockhash of an initialized nonce account: a `u32`
+/// version and a `u32` state, followed by the authority, the blockhash and the
+/// fee calculator.
+fn parse_nonce_account(account: &Pubkey, data: &[u8]) -> Result<(Pubkey, Hash)> {
+    const INITIALIZED: [u8; 4] = 1u32.to_le_bytes();
+    let invalid = || Error::CustomError(format!("{account} is not an initialized nonce account"));
+    if data.len() < 80 || data[4..8] != INITIALIZED {
+        return Err(invalid());
+    }
+    let authority = Pubkey::try_from(&data[8..40]).map_err(|_| invalid())?;
+    let blockhash = Hash::new_from_array(data[40..72].try_into().map_err(|_| invalid())?);
+    Ok((authority, blockhash))
+}
+
+/// Result of [`TransactionBuilder::send`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendOutcome {
+    Confirmed(Signature),
+    /// Broadcast, but the confirmation timed out. The transaction may still
+    /// land.
+    Unconfirmed(Signature),
+}
+
+impl SendOutcome {
+    pub fn signature(&self) -> Signature {
+        match self {
+            Self::Confirmed(signature) | Self::Unconfirmed(signature) => *signature,
+        }
+    }
+
+    pub fn is_confirmed(&self) -> bool {
+        matches!(self, Self::Confirmed(_))
+    }
+}
+
+impl std::fmt::Display for SendOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Confirmed(signature) => write!(f, "{signature}"),
+            Self::Unconfirmed(signature) => write!(f, "{signature} (unconfirmed)"),
+        }
+    }
+}
+
+impl From<SendOutcome> for Signature {
+    fn from(outcome: SendOutcome) -> Self {
+        outcome.signature()
+    }
+}
+
+/// Injects instructions into every compiled message, see
+/// [`TransactionBuilder::with_pre_hook`] and
+/// [`TransactionBuilder::with_post_hook`].
+pub type InstructionHook = Arc<dyn Fn(&mut Vec<Instruction>) + Send + Sync>;
+
+/// Fails with [`Error::TransactionTooLarge`] when the signed transaction
+/// would exceed [`PACKET_DATA_SIZE`], and with [`Error::TooManyAccounts`]
+/// when it references more than [`MAX_TX_ACCOUNTS`].
+fn check_limits(message: &VersionedMessage) -> Result<()> {
+    let size = transaction_size(message)?;
+    if size > PACKET_DATA_SIZE {
+        return Err(Error::TransactionTooLarge {
+            size,
+            limit: PACKET_DATA_SIZE,
+        });
+    }
+    let count = account_count(message);
+    if count > MAX_TX_ACCOUNTS {
+        return Err(Error::TooManyAccounts {
+            count,
+            limit: MAX_TX_ACCOUNTS,
+        });
+    }
+    Ok(())
+}
+
+/// `message` with a default signature for each required signer
+fn unsigned(message: VersionedMessage) -> VersionedTransaction {
+    let num_sigs = message.header().num_required_signatures as usize;
+    VersionedTransaction {
+        signatures: vec![Signature::default(); num_sigs],
+        message,
+    }
+}
+
+/// Prefix of the memo added by [`TransactionBuilder::with_integrity_memo`]
+pub const INTEGRITY_MEMO_PREFIX: &str = "soly-integrity:";
+
+/// SHA-256 over the program id, accounts (with their signer and writable
+/// flags) and data of every instruction, in order.
+///
+/// Recompute it over all instructions before the memo of
+/// [`TransactionBuilder::with_integrity_memo`] to verify it.
+pub fn instructions_hash(instructions: &[Instruction]) -> Hash {
+    let mut hasher = solana_sha256_hasher::Hasher::default();
+    for ix in instructions {
+        hasher.hash(ix.program_id.as_ref());
+        hasher.hash(&(ix.accounts.len() as u64).to_le_bytes());
+        for meta in &ix.accounts {
+            hasher.hash(meta.pubkey.as_ref());
+            hasher.hash(&[u8::from(meta.is_signer), u8::from(meta.is_writable)]);
+        }
+        hasher.hash(&(ix.data.len() as u64).to_le_bytes());
+        hasher.hash(&ix.data);
+    }
+    hasher.result()
+}
+
+/// Drops repeated keys, keeping the first occurrence of each.
+fn dedup_lookup_keys(keys: &[Pubkey]) -> Vec<Pubkey> {
+    let mut seen = HashSet::with_capacity(keys.len());
+    keys.iter()
+        .copied()
+        .filter(|key| seen.insert(*key))
+        .collect()
 }
 
 impl Debug for TransactionBuilder {
@@ -42,48 +216,204 @@ impl TransactionBuilder {
         rpc.get_latest_blockhash().await
     }
 
+    /// The nonce blockhash in nonce mode, otherwise the latest blockhash
+    async fn recent_blockhash<T: TransactionRpcProvider>(&self, rpc: &T) -> Result<Hash> {
+        match &self.durable_nonce {
+            Some(nonce) => Ok(nonce.blockhash),
+            None => TransactionBuilder::get_latest_blockhash(rpc).await,
+        }
+    }
+
+    /// Compiles the message with a fresh blockhash.
+    ///
+    /// An account referenced by several instructions appears once in the
+    /// message, with the most permissive flags of all its [`AccountMeta`]s:
+    /// writable in any instruction makes it writable, and likewise for signer.
+    /// Mark an account readonly everywhere to avoid a write lock on it.
+    ///
+    /// Fails fast with [`Error::TransactionTooLarge`] when the signed
+    /// transaction would exceed [`PACKET_DATA_SIZE`], or with
+    /// [`Error::TooManyAccounts`] past [`MAX_TX_ACCOUNTS`], instead of leaving
+    /// the RPC to reject it.
+    #[tracing::instrument(
+        skip(rpc),
+        fields(label = self.label.as_deref()),
+        level = tracing::Level::DEBUG
+    )]
     pub async fn create_message<T: TransactionRpcProvider>(
         &self,
         payer: &Pubkey,
         rpc: &T,
     ) -> Result<VersionedMessage> {
-        Ok(match &self.address_lookup_tables {
+        let message = self.compile_message(payer, rpc).await?;
+        check_limits(&message)?;
+        Ok(message)
+    }
+
+    /// [`TransactionBuilder::create_message`] which also returns the lookup
+    /// tables it used, to be passed to sibling transactions with
+    /// [`TransactionBuilder::with_resolved_tables`] instead of fetching them
+    /// again.
+    pub async fn create_message_with_tables<T: TransactionRpcProvider>(
+        &self,
+        payer: &Pubkey,
+        rpc: &T,
+    ) -> Result<(VersionedMessage, ResolvedTables)> {
+        let (tables, blockhash) = self.resolve_tables_and_blockhash(rpc).await?;
+        let message = self.compile_with_tables(payer, tables.as_deref(), blockhash)?;
+        check_limits(&message)?;
+        Ok((
+            message,
+            ResolvedTables(tables.map(Cow::into_owned).unwrap_or_default()),
+        ))
+    }
+
+    /// Compiles the message without any RPC call, for air-gapped signing.
+    ///
+    /// The configured lookup tables and durable nonce are ignored in favour of
+    /// `blockhash` and `tables`, empty `tables` compiles a legacy message.
+    pub fn compile_offline(
+        &self,
+        payer: &Pubkey,
+        blockhash: Hash,
+        tables: &[AddressLookupTableAccount],
+    ) -> Result<VersionedMessage> {
+        let tables = (!tables.is_empty()).then_some(tables);
+        let message = self.compile_with_tables(payer, tables, blockhash)?;
+        check_limits(&message)?;
+        Ok(message)
+    }
+
+    /// Compiles the message with the caller's `blockhash` instead of fetching
+    /// one, e.g. a durable nonce read beforehand, so no RPC is needed.
+    ///
+    /// Lookup tables must be resolved, see
+    /// [`TransactionBuilder::with_address_tables`]: only
+    /// [`TransactionBuilder::lookup_tables_keys`] fails with
+    /// [`Error::LookupTableMiss`].
+    pub fn create_message_with_blockhash(
+        &self,
+        payer: &Pubkey,
+        blockhash: Hash,
+    ) -> Result<VersionedMessage> {
+        if self.address_lookup_tables.is_none() && self.lookup_tables_keys.is_some() {
+            return Err(Error::LookupTableMiss);
+        }
+        let message =
+            self.compile_with_tables(payer, self.address_lookup_tables.as_deref(), blockhash)?;
+        check_limits(&message)?;
+        Ok(message)
+    }
+
+    /// [`TransactionBuilder::unsigned_tx`] with the caller's `blockhash`, see
+    /// [`TransactionBuilder::create_message_with_blockhash`]
+    pub fn unsigned_tx_with_blockhash(
+        &self,
+        payer: &Pubkey,
+        blockhash: Hash,
+    ) -> Result<VersionedTransaction> {
+        Ok(unsigned(
+            self.create_message_with_blockhash(payer, blockhash)?,
+        ))
+    }
+
+    /// [`TransactionBuilder::create_message`] without the size check
+    pub(crate) async fn compile_message<T: TransactionRpcProvider>(
+        &self,
+        payer: &Pubkey,
+        rpc: &T,
+    ) -> Result<VersionedMessage> {
+        let (tables, blockhash) = self.resolve_tables_and_blockhash(rpc).await?;
+        self.compile_with_tables(payer, tables.as_deref(), blockhash)
+    }
+
+    /// Resolves the lookup tables and the blockhash concurrently, failing on
+    /// the first error
+    async fn resolve_tables_and_blockhash<T: TransactionRpcProvider>(
+        &self,
+        rpc: &T,
+    ) -> Result<(Option<Cow<'_, [AddressLookupTableAccount]>>, Hash)> {
+        futures::try_join!(self.resolve_tables(rpc), self.recent_blockhash(rpc))
+    }
+
+    /// The configured lookup tables, fetched when only their keys are known.
+    /// `None` compiles a legacy message.
+    async fn resolve_tables<T: TransactionRpcProvider>(
+        &self,
+        rpc: &T,
+    ) -> Result<Option<Cow<'_, [AddressLookupTableAccount]>>> {
+        Ok(
+            match (&self.address_lookup_tables, &self.lookup_tables_keys) {
+                (Some(accounts), _) => Some(Cow::Borrowed(accounts)),
+                (None, Some(keys)) => Some(Cow::Owned(
+                    rpc.get_lookup_table_accounts(&dedup_lookup_keys(keys))
+                        .await?,
+                )),
+                (None, None) => None,
+            },
+        )
+    }
+
+    pub(crate) fn compile_with_tables(
+        &self,
+        payer: &Pubkey,
+        tables: Option<&[AddressLookupTableAccount]>,
+        blockhash: Hash,
+    ) -> Result<VersionedMessage> {
+        let instructions = self.hooked_instructions();
+        Ok(match tables {
             Some(accounts) => VersionedMessage::V0(Message::try_compile(
                 payer,
-                self.instructions.as_ref(),
+                instructions.as_ref(),
                 accounts,
-                TransactionBuilder::get_latest_blockhash(rpc).await?,
+                blockhash,
             )?),
-            None => match self.lookup_tables_keys {
-                Some(ref keys) => {
-                    let accounts = rpc.get_lookup_table_accounts(keys).await?;
-                    VersionedMessage::V0(Message::try_compile(
-                        payer,
-                        self.instructions.as_ref(),
-                        &accounts,
-                        TransactionBuilder::get_latest_blockhash(rpc).await?,
-                    )?)
-                }
-                None => VersionedMessage::Legacy(solana_message::Message::new_with_blockhash(
-                    &self.instructions,
-                    Some(payer),
-                    &TransactionBuilder::get_latest_blockhash(rpc).await?,
-                )),
-            },
+            None => VersionedMessage::Legacy(solana_message::Message::new_with_blockhash(
+                &instructions,
+                Some(payer),
+                &blockhash,
+            )),
         })
     }
 
+    /// The instructions with the output of the pre hooks before, and the
+    /// output of the post hooks after them.
+    pub(crate) fn hooked_instructions(&self) -> Cow<'_, [Instruction]> {
+        if self.pre_hooks.is_none() && self.post_hooks.is_none() {
+            return Cow::Borrowed(&self.instructions);
+        }
+        let run = |hooks: &Option<Vec<InstructionHook>>| {
+            let mut injected = Vec::new();
+            for hook in hooks.iter().flatten() {
+                hook(&mut injected);
+            }
+            injected
+        };
+        let mut instructions = run(&self.pre_hooks);
+        instructions.extend(self.instructions.iter().cloned());
+        instructions.extend(run(&self.post_hooks));
+        Cow::Owned(instructions)
+    }
+
     /// Simulates the [`VersionedTransaction`] using
     /// [`TransactionRpcProvider::simulate_transaction`].
+    ///
+    /// `config` is an [`RpcSimulateTransactionConfig`] or a
+    /// [`SimulateConfigBuilder`].
+    #[tracing::instrument(
+        skip(rpc, signers, config),
+        fields(label = self.label.as_deref()),
+        level = tracing::Level::DEBUG
+    )]
     pub async fn simulate<S: Signers + ?Sized, T: TransactionRpcProvider>(
         &self,
         payer: &Pubkey,
         signers: &S,
         rpc: &T,
-        config: RpcSimulateTransactionConfig,
+        config: impl Into<RpcSimulateTransactionConfig>,
     ) -> Result<RpcSimulateTransactionResult> {
         let tx = VersionedTransaction::try_new(self.create_message(payer, rpc).await?, signers)?;
-        self.simulate_internal(rpc, &tx, config).await
+        self.simulate_internal(rpc, &tx, config.into()).await
     }
 
     pub(super) async fn simulate_internal<T: TransactionRpcProvider>(
@@ -97,20 +427,178 @@ impl TransactionBuilder {
 
     /// Simulates, signs, and sends the transaction using
     /// [`TransactionRpcProvider::send_and_confirm_transaction`].
-    #[tracing::instrument(skip(rpc, signers), level = tracing::Level::INFO)]
+    ///
+    /// A transaction broadcast but not confirmed in time (the provider
+    /// returns [`Error::Unconfirmed`]) is [`SendOutcome::Unconfirmed`]
+    /// instead of an error: it may still land, so keep polling its signature
+    /// before resending.
+    ///
+    /// Fails without any RPC call on a
+    /// [`TransactionBuilder::simulate_only`] builder. In nonce mode (see
+    /// [`TransactionBuilder::with_durable_nonce`]) the simulation keeps the
+    /// nonce blockhash and skips signature verification. The nonce advances
+    /// once the transaction lands, call
+    /// [`TransactionBuilder::refresh_durable_nonce`] before sending the
+    /// builder again.
+    ///
+    /// The returned future is cancellation safe with the providers of this
+    /// crate: no task is spawned, so dropping it (e.g. when a client
+    /// disconnects) stops confirmation polling. A transaction already
+    /// broadcast may still land.
+    #[tracing::instrument(
+        skip(rpc, signers),
+        fields(label = self.label.as_deref()),
+        level = tracing::Level::INFO
+    )]
     pub async fn send<S: Signers + ?Sized, T: TransactionRpcProvider>(
         &self,
         rpc: &T,
         payer: &Pubkey,
         signers: &S,
-    ) -> Result<Signature> {
+    ) -> Result<SendOutcome> {
+        if self.simulate_only {
+            return Err(Error::CustomError("simulate-only builder".to_string()));
+        }
         let tx = VersionedTransaction::try_new(self.create_message(payer, rpc).await?, signers)?;
-        self.simulate_internal(rpc, &tx, RpcSimulateTransactionConfig {
-            sig_verify: true,
-            ..Default::default()
-        })
-        .await?;
-        rpc.send_and_confirm_transaction(&tx, None).await
+        let config = match self.durable_nonce {
+            // simulate as compiled, against the nonce blockhash
+            Some(_) => SimulateConfigBuilder::new()
+                .sig_verify(false)
+                .replace_recent_blockhash(false),
+            None => SimulateConfigBuilder::new().sig_verify(true),
+        };
+        let simulation = self.simulate_internal(rpc, &tx, config.build()).await;
+        let sent = match simulation {
+            Ok(_) => rpc.send_and_confirm_transaction(&tx, None).await,
+            Err(e) if self.is_tolerated(&e) => {
+                tracing::warn!("simulation failed with tolerated error, sending anyway: {e}");
+                rpc.send_and_confirm_transaction(
+                    &tx,
+                    Some(RpcSendTransactionConfig {
+                        skip_preflight: true,
+                        ..Default::default()
+                    }),
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        match sent {
+            Ok(signature) => Ok(SendOutcome::Confirmed(signature)),
+            Err(Error::Unconfirmed(signature)) => {
+                tracing::warn!(%signature, "transaction sent but not confirmed");
+                Ok(SendOutcome::Unconfirmed(signature))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Reads the nonce account with [`TransactionRpcProvider::get_account`]
+    /// and stores its current blockhash, after a nonce-mode send advanced it.
+    ///
+    /// Fails when the builder is not in nonce mode, the account is missing or
+    /// not an initialized nonce account, or its authority changed. An
+    /// unchanged blockhash is logged: the send has not landed yet at the RPC's
+    /// commitment.
+    pub async fn refresh_durable_nonce<T: TransactionRpcProvider>(
+        mut self,
+        rpc: &T,
+    ) -> Result<Self> {
+        let Some(nonce) = self.durable_nonce.as_mut() else {
+            return Err(Error::CustomError(
+                "builder has no durable nonce".to_string(),
+            ));
+        };
+        let account = rpc
+            .get_account(&nonce.account)
+            .await?
+            .ok_or(Error::AccountNotFound(nonce.account))?;
+        let (authority, blockhash) = parse_nonce_account(&nonce.account, &account.data)?;
+        if authority != nonce.authority {
+            return Err(Error::CustomError(format!(
+                "nonce account {} authority changed to {authority}",
+                nonce.account
+            )));
+        }
+        if blockhash == nonce.blockhash {
+            tracing::warn!(account = %nonce.account, "durable nonce has not advanced");
+        }
+        nonce.blockhash = blockhash;
+        Ok(self)
+    }
+
+    fn is_tolerated(&self, error: &Error) -> bool {
+        let (Some(tolerated), Error::SolanaSimulateError { error, .. }) =
+            (&self.tolerated_errors, error)
+        else {
+            return false;
+        };
+        match error {
+            TransactionError::InstructionError(_, e) => tolerated.contains(e),
+            _ => false,
+        }
+    }
+
+    /// Fails with [`Error::InsufficientBalance`] when `payer` holds less than
+    /// `estimated_cost` lamports, using
+    /// [`TransactionRpcProvider::get_balance`].
+    pub async fn check_payer_balance<T: TransactionRpcProvider>(
+        &self,
+        payer: &Pubkey,
+        rpc: &T,
+        estimated_cost: u64,
+    ) -> Result<()> {
+        let balance = rpc.get_balance(payer).await?;
+        if balance < estimated_cost {
+            return Err(Error::InsufficientBalance(balance, estimated_cost));
+        }
+        Ok(())
+    }
+
+    /// Compiles the message and returns its bincode serialization, base64
+    /// encoded.
+    pub async fn message_base64<T: TransactionRpcProvider>(
+        &self,
+        payer: &Pubkey,
+        rpc: &T,
+    ) -> Result<String> {
+        let message = self.create_message(payer, rpc).await?;
+        Ok(BASE64_STANDARD.encode(bincode::serialize(&message)?))
+    }
+
+    /// The unsigned transaction, bincode serialized and base64 encoded, as
+    /// accepted by `VersionedTransaction.deserialize` in `@solana/web3.js`.
+    ///
+    /// Hand it to a browser wallet for `signTransaction`: the signatures are
+    /// zeroed placeholders for the wallet to fill in.
+    pub async fn to_base64<T: TransactionRpcProvider>(
+        &self,
+        payer: &Pubkey,
+        rpc: &T,
+    ) -> Result<String> {
+        let tx = self.unsigned_tx(payer, rpc).await?;
+        Ok(BASE64_STANDARD.encode(bincode::serialize(&tx)?))
+    }
+
+    /// [`TransactionBuilder::to_base64`] with the caller's `blockhash`, see
+    /// [`TransactionBuilder::create_message_with_blockhash`]
+    pub fn to_base64_with_blockhash(&self, payer: &Pubkey, blockhash: Hash) -> Result<String> {
+        let tx = self.unsigned_tx_with_blockhash(payer, blockhash)?;
+        Ok(BASE64_STANDARD.encode(bincode::serialize(&tx)?))
+    }
+
+    /// SHA-256 hash of the compiled message, as serialized for signing.
+    ///
+    /// Co-signers can compare it to check they sign the same message. The
+    /// message is compiled with a fresh blockhash, so compare hashes from
+    /// the same compiled message rather than recompiling per signer.
+    pub async fn message_hash<T: TransactionRpcProvider>(
+        &self,
+        payer: &Pubkey,
+        rpc: &T,
+    ) -> Result<Hash> {
+        let message = self.create_message(payer, rpc).await?;
+        Ok(solana_sha256_hasher::hash(&bincode::serialize(&message)?))
     }
 
     pub async fn unsigned_tx<T: TransactionRpcProvider>(
@@ -118,11 +606,153 @@ impl TransactionBuilder {
         payer: &Pubkey,
         rpc: &T,
     ) -> Result<VersionedTransaction> {
-        let message = self.create_message(payer, rpc).await?;
-        let num_sigs = message.header().num_required_signatures as usize;
-        Ok(VersionedTransaction {
-            signatures: vec![Signature::default(); num_sigs],
-            message,
+        Ok(unsigned(self.create_message(payer, rpc).await?))
+    }
+
+    /// The number of signatures the compiled message requires, fee payer
+    /// included
+    pub async fn num_required_signatures<T: TransactionRpcProvider>(
+        &self,
+        payer: &Pubkey,
+        rpc: &T,
+    ) -> Result<u8> {
+        Ok(self
+            .message_header(payer, rpc)
+            .await?
+            .num_required_signatures)
+    }
+
+    /// The header of the compiled message, to check its signer and readonly
+    /// account counts when signature verification fails unexpectedly
+    pub async fn message_header<T: TransactionRpcProvider>(
+        &self,
+        payer: &Pubkey,
+        rpc: &T,
+    ) -> Result<MessageHeader> {
+        let message = self.compile_message(payer, rpc).await?;
+        Ok(*message.header())
+    }
+
+    /// Like [`TransactionBuilder::unsigned_tx`], reserving `num_sigs` default
+    /// signatures for signers added later.
+    ///
+    /// Fails with [`Error::InvalidSignatureCount`] when `num_sigs` is less than
+    /// the message header requires. The message is not changed, so whoever
+    /// adds the extra signers must recompile it before submitting.
+    pub async fn unsigned_tx_with_sigs<T: TransactionRpcProvider>(
+        &self,
+        payer: &Pubkey,
+        rpc: &T,
+        num_sigs: usize,
+    ) -> Result<VersionedTransaction> {
+        let mut tx = self.unsigned_tx(payer, rpc).await?;
+        if num_sigs < tx.signatures.len() {
+            return Err(Error::InvalidSignatureCount(num_sigs, tx.signatures.len()));
+        }
+        tx.signatures.resize(num_sigs, Signature::default());
+        Ok(tx)
+    }
+
+    /// Rebuilds a builder from a compiled transaction, e.g. to re-estimate
+    /// fees and resend a timed out transaction with a fresh blockhash.
+    ///
+    /// Lookup tables are resolved with
+    /// [`TransactionRpcProvider::get_lookup_table_accounts`] and kept as
+    /// [`TransactionBuilder::lookup_tables_keys`]. Compute budget instructions
+    /// are kept, see [`TransactionBuilder::template`] to drop them. The fee
+    /// payer is writable in every instruction referencing it.
+    pub async fn from_versioned<T: TransactionRpcProvider>(
+        tx: &VersionedTransaction,
+        rpc: &T,
+    ) -> Result<Self> {
+        let message = &tx.message;
+        let header = message.header();
+        let static_keys = message.static_account_keys();
+        let num_signed = usize::from(header.num_required_signatures);
+        let writable_signed =
+            num_signed.saturating_sub(usize::from(header.num_readonly_signed_accounts));
+        let writable_unsigned = static_keys
+            .len()
+            .saturating_sub(usize::from(header.num_readonly_unsigned_accounts));
+        let mut accounts: Vec<AccountMeta> = static_keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| AccountMeta {
+                pubkey: *key,
+                is_signer: i < num_signed,
+                is_writable: if i < num_signed {
+                    i < writable_signed
+                } else {
+                    i < writable_unsigned
+                },
+            })
+            .collect();
+
+        let lookups = message.address_table_lookups().unwrap_or_default();
+        let lookup_keys: Vec<Pubkey> = lookups.iter().map(|l| l.account_key).collect();
+        if !lookups.is_empty() {
+            let tables = rpc
+                .get_lookup_table_accounts(&dedup_lookup_keys(&lookup_keys))
+                .await?;
+            let resolve = |key: &Pubkey, index: &u8| -> Result<Pubkey> {
+                let table = tables
+                    .iter()
+                    .find(|table| table.key == *key)
+                    .ok_or(Error::LookupTableMiss)?;
+                table
+                    .addresses
+                    .get(usize::from(*index))
+                    .copied()
+                    .ok_or_else(|| {
+                        Error::CustomError(format!("lookup table {key} has no index {index}"))
+                    })
+            };
+            // loaded addresses are ordered writable first, across all tables
+            for writable in [true, false] {
+                for lookup in lookups {
+                    let indexes = if writable {
+                        &lookup.writable_indexes
+                    } else {
+                        &lookup.readonly_indexes
+                    };
+                    for index in indexes {
+                        accounts.push(AccountMeta {
+                            pubkey: resolve(&lookup.account_key, index)?,
+                            is_signer: false,
+                            is_writable: writable,
+                        });
+                    }
+                }
+            }
+        }
+
+        let account = |index: u8| {
+            accounts
+                .get(usize::from(index))
+                .cloned()
+                .ok_or_else(|| Error::CustomError(format!("account index {index} out of bounds")))
+        };
+        let instructions = message
+            .instructions()
+            .iter()
+            .map(|ix| {
+                Ok(Instruction {
+                    program_id: account(ix.program_id_index)?.pubkey,
+                    accounts: ix
+                        .accounts
+                        .iter()
+                        .map(|index| account(*index))
+                        .collect::<Result<_>>()?,
+                    data: ix.data.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let builder = TransactionBuilder::from(instructions);
+        Ok(if lookup_keys.is_empty() {
+            builder
+        } else {
+            builder.with_lookup_keys(lookup_keys)
         })
     }
 }
@@ -165,6 +795,113 @@ impl TransactionBuilder {
         self
     }
 
+    /// Uses tables resolved by
+    /// [`TransactionBuilder::create_message_with_tables`], replacing any
+    /// configured [`TransactionBuilder::address_lookup_tables`]. No RPC call
+    /// is made for them.
+    pub fn with_resolved_tables(mut self, tables: ResolvedTables) -> Self {
+        self.address_lookup_tables = Some(tables.into());
+        self
+    }
+
+    /// When the simulation in [`TransactionBuilder::send`] fails with one of
+    /// these instruction errors, the transaction is broadcast anyway (without
+    /// preflight) instead of returning the error.
+    ///
+    /// Only use this for errors known to be benign, such as an idempotent
+    /// "account already exists" from a program.
+    /// [`InstructionError::Custom`] matches a program's custom error code.
+    pub fn with_tolerated_errors<I>(mut self, errors: I) -> Self
+    where
+        I: IntoIterator<Item = InstructionError>,
+    {
+        let new_errors: Vec<InstructionError> = errors.into_iter().collect();
+        match self.tolerated_errors {
+            Some(ref mut existing) => existing.extend(new_errors),
+            None => self.tolerated_errors = Some(new_errors),
+        }
+        self
+    }
+
+    /// Registers a hook run every time the message is compiled (see
+    /// [`TransactionBuilder::create_message`]). Instructions it pushes are
+    /// placed before all instructions of the builder.
+    ///
+    /// Hooks run in registration order and their instructions are not stored
+    /// in [`TransactionBuilder::instructions`]. They land before compute budget
+    /// instructions, which is harmless, but also before an `AdvanceNonce`
+    /// instruction, which must stay first: use
+    /// [`TransactionBuilder::with_post_hook`] for durable nonce transactions.
+    pub fn with_pre_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&mut Vec<Instruction>) + Send + Sync + 'static,
+    {
+        self.pre_hooks
+            .get_or_insert_with(Vec::new)
+            .push(Arc::new(hook));
+        self
+    }
+
+    /// Like [`TransactionBuilder::with_pre_hook`], but the instructions are
+    /// placed after all instructions of the builder, which keeps compute
+    /// budget and nonce instructions in front.
+    pub fn with_post_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&mut Vec<Instruction>) + Send + Sync + 'static,
+    {
+        self.post_hooks
+            .get_or_insert_with(Vec::new)
+            .push(Arc::new(hook));
+        self
+    }
+
+    /// Names the logical operation of this transaction. The label is recorded
+    /// as the `label` field of the [`TransactionBuilder::send`],
+    /// [`TransactionBuilder::simulate`] and
+    /// [`TransactionBuilder::create_message`] spans, so logs can be searched
+    /// by it.
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Switches to nonce mode: inserts the `AdvanceNonceAccount` instruction
+    /// first and compiles with the nonce `blockhash` instead of fetching the
+    /// latest one, so the signed transaction stays valid until the nonce
+    /// advances.
+    ///
+    /// Call it last: instructions prepended afterwards, such as compute
+    /// budget instructions, would move the `AdvanceNonceAccount` instruction
+    /// from the front and fail the transaction.
+    pub fn with_durable_nonce(
+        mut self,
+        account: &Pubkey,
+        authority: &Pubkey,
+        blockhash: Hash,
+    ) -> Self {
+        self.instructions.insert(
+            0,
+            solana_system_interface::instruction::advance_nonce_account(account, authority),
+        );
+        self.durable_nonce = Some(DurableNonce {
+            account: *account,
+            authority: *authority,
+            blockhash,
+        });
+        self
+    }
+
+    /// Marks the builder as simulation only: [`TransactionBuilder::simulate`]
+    /// works as usual, but [`TransactionBuilder::send`] returns an error
+    /// instead of broadcasting.
+    ///
+    /// A guard for read-only analysis pipelines that share code with sending
+    /// pipelines.
+    pub fn simulate_only(mut self) -> Self {
+        self.simulate_only = true;
+        self
+    }
+
     pub fn with_memo(mut self, memo: impl AsRef<[u8]>, signer_pubkeys: &[&Pubkey]) -> Self {
         self.instructions
             .push(spl_memo_interface::instruction::build_memo(
@@ -175,6 +912,148 @@ impl TransactionBuilder {
         self
     }
 
+    /// Appends a memo with the [`instructions_hash`] of the current
+    /// instructions, prefixed by [`INTEGRITY_MEMO_PREFIX`], so observers can
+    /// verify the instruction set was not altered.
+    ///
+    /// The memo must be added last: instructions added afterwards, including
+    /// compute budget instructions from
+    /// [`TransactionBuilder::with_priority_fees`], are not covered and make
+    /// the verification fail. Hook instructions are not covered either.
+    pub fn with_integrity_memo(self) -> Self {
+        let memo = format!(
+            "{INTEGRITY_MEMO_PREFIX}{}",
+            instructions_hash(&self.instructions)
+        );
+        self.with_memo(memo, &[])
+    }
+
+    /// Clones this builder as a base for fanning out per-payer transactions.
+    ///
+    /// Compute budget instructions are removed so each copy re-estimates its
+    /// fees, see [`TransactionBuilder::with_priority_fees`]. The durable nonce
+    /// and its `AdvanceNonceAccount` instruction are removed too: a nonce only
+    /// lands once, so each copy needs its own. Lookup table configuration is
+    /// kept.
+    #[must_use]
+    pub fn template(&self) -> TransactionBuilder {
+        let mut template = self.clone();
+        let advance_nonce = template.durable_nonce.take().map(|nonce| {
+            solana_system_interface::instruction::advance_nonce_account(
+                &nonce.account,
+                &nonce.authority,
+            )
+        });
+        template.instructions.retain(|ix| {
+            ix.program_id != solana_compute_budget_interface::ID
+                && Some(ix) != advance_nonce.as_ref()
+        });
+        template
+    }
+
+    /// Whether both builders compile to the same transaction, ignoring the
+    /// latest blockhash.
+    ///
+    /// Instructions, including the output of the hooks, the durable nonce and
+    /// lookup table configuration are compared in order. Unset and empty
+    /// lookup configuration are equivalent, and
+    /// [`TransactionBuilder::with_tolerated_errors`] and
+    /// [`TransactionBuilder::simulate_only`] are ignored since they do not
+    /// change the transaction.
+    pub fn instructions_eq(&self, other: &TransactionBuilder) -> bool {
+        self.hooked_instructions() == other.hooked_instructions()
+            && self.durable_nonce == other.durable_nonce
+            && self.lookup_tables_keys.as_deref().unwrap_or_default()
+                == other.lookup_tables_keys.as_deref().unwrap_or_default()
+            && self.address_lookup_tables.as_deref().unwrap_or_default()
+                == other.address_lookup_tables.as_deref().unwrap_or_default()
+    }
+
+    /// Index and instruction of every instruction calling `program_id`, in
+    /// order. Hook instructions are not included.
+    pub fn find_instructions(&self, program_id: Pubkey) -> Vec<(usize, &Instruction)> {
+        self.instructions
+            .iter()
+            .enumerate()
+            .filter(|(_, ix)| ix.program_id == program_id)
+            .collect()
+    }
+
+    /// Accounts write-locked by the transaction: the fee `payer` and every
+    /// writable account of the instructions, including hook instructions.
+    ///
+    /// Transactions with disjoint sets can be scheduled concurrently.
+    pub fn write_locked_accounts(&self, payer: &Pubkey) -> HashSet<Pubkey> {
+        std::iter::once(*payer)
+            .chain(
+                self.hooked_instructions()
+                    .iter()
+                    .flat_map(|ix| &ix.accounts)
+                    .filter(|meta| meta.is_writable)
+                    .map(|meta| meta.pubkey),
+            )
+            .collect()
+    }
+
+    /// Number of distinct accounts the message references: the fee `payer`,
+    /// the program ids and the accounts of every instruction, including hook
+    /// instructions.
+    ///
+    /// Each account costs 32 bytes in a legacy message, but only a 1 byte
+    /// index when loaded from a lookup table. With few accounts the table
+    /// reference costs more than it saves.
+    pub fn unique_account_count(&self, payer: &Pubkey) -> usize {
+        let instructions = self.hooked_instructions();
+        let mut accounts = HashSet::from([*payer]);
+        for ix in instructions.iter() {
+            accounts.insert(ix.program_id);
+            accounts.extend(ix.accounts.iter().map(|meta| meta.pubkey));
+        }
+        accounts.len()
+    }
+
+    /// A builder with instructions derived only from `seed`, for snapshot
+    /// tests of message compilation. Use
+    /// [`TransactionBuilder::deterministic_pubkey`] for a payer.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn deterministic(seed: u64) -> Self {
+        let from = Self::deterministic_pubkey(seed, 1);
+        let to = Self::deterministic_pubkey(seed, 2);
+        TransactionBuilder::from(vec![solana_system_interface::instruction::transfer(
+            &from, &to, seed,
+        )])
+        .with_memo(format!("deterministic {seed}"), &[&from])
+    }
+
+    /// The `index`th pubkey derived from `seed`
+    #[cfg(any(test, feature = "testing"))]
+    pub fn deterministic_pubkey(seed: u64, index: u8) -> Pubkey {
+        Pubkey::new_from_array(
+            solana_sha256_hasher::hashv(&[b"soly", &seed.to_le_bytes(), &[index]]).to_bytes(),
+        )
+    }
+
+    /// Starts from an empty builder and applies `f`, for assembling larger
+    /// transactions in one expression.
+    ///
+    /// ```
+    /// # use soly::TransactionBuilder;
+    /// # use solana_instruction::Instruction;
+    /// # use solana_pubkey::Pubkey;
+    /// let program_id = Pubkey::new_unique();
+    /// let builder = TransactionBuilder::build_with(|b| {
+    ///     b.push(Instruction::new_with_bytes(program_id, &[1], vec![]))
+    ///         .push(Instruction::new_with_bytes(program_id, &[2], vec![]))
+    /// });
+    /// assert_eq!(builder.instructions.len(), 2);
+    /// ```
+    pub fn build_with<F>(f: F) -> Self
+    where
+        F: FnOnce(TransactionBuilder) -> TransactionBuilder,
+    {
+        f(TransactionBuilder::default())
+    }
+
     /// Adds an instruction to the transaction.
     pub fn push<T: IntoInstruction>(mut self, builder: T) -> Self {
         self.instructions.push(builder.into_instruction());
@@ -220,7 +1099,18 @@ impl IntoIterator for TransactionBuilder {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use {
+        super::*,
+        crate::{
+            CounterRpcProvider,
+            RpcMethod,
+            SlowLogRpcProvider,
+            rpc::mock::{MockRpc, capture_logs},
+        },
+        solana_keypair::Keypair,
+        solana_signer::Signer,
+        std::time::Duration,
+    };
 
     #[test]
     fn test_with_memo() {
@@ -277,4 +1167,837 @@ mod tests {
         assert_eq!(tables[0].key, pk1);
         assert_eq!(tables[1].key, pk2);
     }
+
+    #[test]
+    fn test_instructions_eq() {
+        let payer = Pubkey::new_unique();
+        let table = Pubkey::new_unique();
+        let tx = TransactionBuilder::default()
+            .with_memo("eq", &[&payer])
+            .with_lookup_keys([table]);
+
+        assert!(tx.instructions_eq(&tx.clone()));
+        assert!(
+            tx.instructions_eq(
+                &tx.clone()
+                    .with_tolerated_errors([InstructionError::Custom(1)])
+            )
+        );
+        assert!(TransactionBuilder::default().instructions_eq(
+            &TransactionBuilder::default().with_lookup_keys(Vec::<Pubkey>::new())
+        ));
+
+        assert!(!tx.instructions_eq(&tx.clone().with_memo("eq", &[&payer])));
+        assert!(
+            !tx.instructions_eq(
+                &TransactionBuilder::default()
+                    .with_memo("ne", &[&payer])
+                    .with_lookup_keys([table])
+            )
+        );
+        assert!(!tx.instructions_eq(&TransactionBuilder::default().with_memo("eq", &[&payer])));
+        assert!(
+            !tx.instructions_eq(
+                &TransactionBuilder::default()
+                    .with_memo("eq", &[&payer])
+                    .with_lookup_keys([Pubkey::new_unique()])
+            )
+        );
+        assert!(!tx.instructions_eq(&tx.clone().with_address_tables([
+            AddressLookupTableAccount {
+                key: table,
+                addresses: vec![],
+            }
+        ])));
+
+        // hooks and the nonce change the compiled message
+        let memo =
+            spl_memo_interface::instruction::build_memo(&spl_memo_interface::v3::ID, b"hook", &[]);
+        let hooked = tx.clone().with_post_hook(move |ixs| ixs.push(memo.clone()));
+        assert!(!tx.instructions_eq(&hooked));
+        assert!(hooked.instructions_eq(&hooked.clone()));
+        let nonce_account = Pubkey::new_unique();
+        let nonce = |blockhash| {
+            tx.clone()
+                .with_durable_nonce(&nonce_account, &payer, blockhash)
+        };
+        let blockhash = Hash::new_unique();
+        assert!(nonce(blockhash).instructions_eq(&nonce(blockhash)));
+        assert!(!nonce(blockhash).instructions_eq(&nonce(Hash::new_unique())));
+    }
+
+    #[tokio::test]
+    async fn test_transaction_too_large() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let rpc = MockRpc::default();
+        let tx = TransactionBuilder::default().with_memo(vec![b'a'; PACKET_DATA_SIZE], &[&payer]);
+        let result = tx.create_message(&payer, &rpc).await;
+        let Err(Error::TransactionTooLarge { size, limit }) = result else {
+            panic!("expected TransactionTooLarge, got {result:?}");
+        };
+        assert_eq!(PACKET_DATA_SIZE, limit);
+        assert_eq!(tx.transaction_size(&payer, &rpc).await?, size);
+        assert!(matches!(
+            tx.send(&rpc, &payer, &[&Keypair::new()]).await,
+            Err(Error::TransactionTooLarge { .. })
+        ));
+        assert!(rpc.simulated().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_dedup_lookup_keys() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        let c = Pubkey::new_unique();
+        assert_eq!(vec![b, a, c], dedup_lookup_keys(&[b, a, b, c, a]));
+        assert!(dedup_lookup_keys(&[]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_lookup_keys() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![spl_memo_interface::v3::ID, Pubkey::new_unique()],
+        };
+        let rpc = MockRpc {
+            lookup_tables: vec![table.clone()],
+            ..Default::default()
+        };
+        let mut ix =
+            spl_memo_interface::instruction::build_memo(&spl_memo_interface::v3::ID, b"dedup", &[
+                &payer,
+            ]);
+        // an account only reachable through the table
+        ix.accounts
+            .push(solana_instruction::AccountMeta::new_readonly(
+                table.addresses[1],
+                false,
+            ));
+        let tx = TransactionBuilder::from(vec![ix])
+            .with_lookup_keys([table.key, table.key])
+            .with_lookup_keys([table.key]);
+
+        let message = tx.create_message(&payer, &rpc).await?;
+        let lookups = message.address_table_lookups().unwrap_or_default();
+        assert_eq!(1, lookups.len());
+        assert_eq!(table.key, lookups[0].account_key);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hooks() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let rpc = MockRpc::default();
+        let memo = |text: &'static str| {
+            spl_memo_interface::instruction::build_memo(
+                &spl_memo_interface::v3::ID,
+                text.as_bytes(),
+                &[],
+            )
+        };
+        let tx = TransactionBuilder::default()
+            .with_memo("user", &[&payer])
+            .with_post_hook(move |ixs| ixs.push(memo("post")))
+            .with_pre_hook(move |ixs| ixs.push(memo("pre-1")))
+            .with_pre_hook(move |ixs| ixs.push(memo("pre-2")))
+            .prepend_compute_budget_instructions(1_000, 1)?;
+        assert_eq!(3, tx.instructions.len());
+
+        let message = tx.create_message(&payer, &rpc).await?;
+        let data: Vec<&[u8]> = message
+            .instructions()
+            .iter()
+            .map(|ix| ix.data.as_slice())
+            .collect();
+        assert_eq!(6, data.len());
+        assert_eq!(b"pre-1", data[0]);
+        assert_eq!(b"pre-2", data[1]);
+        assert_eq!(b"user", data[4]);
+        assert_eq!(b"post", data[5]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unsigned_tx_with_sigs() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let cosigner = Pubkey::new_unique();
+        let rpc = MockRpc::default();
+        let tx = TransactionBuilder::default().with_memo("sigs", &[&payer, &cosigner]);
+
+        let unsigned = tx.unsigned_tx(&payer, &rpc).await?;
+        assert_eq!(2, unsigned.signatures.len());
+        let reserved = tx.unsigned_tx_with_sigs(&payer, &rpc, 4).await?;
+        assert_eq!(vec![Signature::default(); 4], reserved.signatures);
+        assert_eq!(unsigned.message, reserved.message);
+        assert_eq!(
+            2,
+            tx.unsigned_tx_with_sigs(&payer, &rpc, 2)
+                .await?
+                .signatures
+                .len()
+        );
+
+        let result = tx.unsigned_tx_with_sigs(&payer, &rpc, 1).await;
+        assert!(matches!(result, Err(Error::InvalidSignatureCount(1, 2))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_message_hash() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let rpc = MockRpc::default();
+        let tx = TransactionBuilder::default().with_memo("hash", &[&payer]);
+
+        let hash = tx.message_hash(&payer, &rpc).await?;
+        assert_eq!(hash, tx.clone().message_hash(&payer, &rpc).await?);
+        let message = tx.create_message(&payer, &rpc).await?;
+        assert_eq!(
+            hash,
+            solana_sha256_hasher::hash(&bincode::serialize(&message)?)
+        );
+
+        let modified = tx.clone().with_memo("hash", &[&payer]);
+        assert_ne!(hash, modified.message_hash(&payer, &rpc).await?);
+        let other_payer = Pubkey::new_unique();
+        assert_ne!(hash, tx.message_hash(&other_payer, &rpc).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_tolerated_errors() -> anyhow::Result<()> {
+        let kp = Keypair::new();
+        let payer = kp.pubkey();
+        let rpc = MockRpc {
+            simulate_error: Some(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(0),
+            )),
+            ..Default::default()
+        };
+
+        let tx = TransactionBuilder::default()
+            .with_memo("tolerated", &[&payer])
+            .with_tolerated_errors([InstructionError::Custom(0)]);
+        tx.send(&rpc, &payer, &[&kp]).await?;
+        let sent = rpc.sent();
+        assert_eq!(1, sent.len());
+        assert!(sent[0].1.is_some_and(|config| config.skip_preflight));
+
+        let tx = TransactionBuilder::default()
+            .with_memo("not tolerated", &[&payer])
+            .with_tolerated_errors([InstructionError::Custom(1)]);
+        let result = tx.send(&rpc, &payer, &[&kp]).await;
+        assert!(matches!(result, Err(Error::SolanaSimulateError { .. })));
+        assert_eq!(1, rpc.sent().len());
+
+        let tx = TransactionBuilder::default().with_memo("no allowlist", &[&payer]);
+        assert!(tx.send(&rpc, &payer, &[&kp]).await.is_err());
+        assert_eq!(1, rpc.sent().len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_instructions() {
+        let payer = Pubkey::new_unique();
+        let transfer =
+            solana_system_interface::instruction::transfer(&payer, &Pubkey::new_unique(), 1);
+        let tx = TransactionBuilder::default()
+            .with_memo("first", &[&payer])
+            .push(transfer.clone())
+            .with_memo("second", &[&payer]);
+
+        assert_eq!(
+            vec![(1, &transfer)],
+            tx.find_instructions(solana_system_interface::program::ID)
+        );
+        let memos = tx.find_instructions(spl_memo_interface::v3::ID);
+        assert_eq!(
+            vec![0, 2],
+            memos.iter().map(|(i, _)| *i).collect::<Vec<_>>()
+        );
+        assert!(tx.find_instructions(Pubkey::new_unique()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_label_in_spans() -> anyhow::Result<()> {
+        let (_guard, logs) = capture_logs();
+        let kp = Keypair::new();
+        let payer = kp.pubkey();
+        // logs every call, inside the builder spans
+        let rpc = SlowLogRpcProvider::new(MockRpc::default(), Duration::ZERO);
+        let tx = TransactionBuilder::default()
+            .with_memo("label", &[&payer])
+            .with_label("mint-rewards");
+        tx.send(&rpc, &payer, &[&kp]).await?;
+        let logs = logs.contents();
+        let line = logs
+            .lines()
+            .find(|line| line.contains("send{") && line.contains(":create_message{"))
+            .expect("rpc call logged inside the spans");
+        // both spans carry the label
+        assert_eq!(2, line.matches(r#"label="mint-rewards""#).count(), "{line}");
+
+        let (_guard, logs) = capture_logs();
+        let unlabeled = TransactionBuilder::default().with_memo("label", &[&payer]);
+        unlabeled.send(&rpc, &payer, &[&kp]).await?;
+        assert!(!logs.contents().contains("label="));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_simulate_only() -> anyhow::Result<()> {
+        let kp = Keypair::new();
+        let payer = kp.pubkey();
+        let rpc = MockRpc::default();
+        let tx = TransactionBuilder::default()
+            .with_memo("audit", &[&payer])
+            .simulate_only();
+
+        tx.simulate(
+            &payer,
+            &[&kp],
+            &rpc,
+            RpcSimulateTransactionConfig::default(),
+        )
+        .await?;
+        assert_eq!(1, rpc.simulated().len());
+
+        let result = tx.send(&rpc, &payer, &[&kp]).await;
+        assert!(matches!(result, Err(Error::CustomError(msg)) if msg == "simulate-only builder"));
+        assert_eq!(1, rpc.simulated().len());
+        assert!(rpc.sent().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_template_strips_compute_budget() -> anyhow::Result<()> {
+        let pk = Pubkey::new_unique();
+        let tx = TransactionBuilder::default()
+            .with_memo("template", &[&pk])
+            .with_lookup_keys([pk])
+            .prepend_compute_budget_instructions(200_000, 1_000)?;
+        assert_eq!(3, tx.instructions.len());
+
+        let template = tx.template();
+        assert_eq!(1, template.instructions.len());
+        assert_eq!(
+            spl_memo_interface::v3::ID,
+            template.instructions[0].program_id
+        );
+        assert_eq!(Some(vec![pk]), template.lookup_tables_keys);
+        // the template can be priced again
+        template.prepend_compute_budget_instructions(100_000, 2_000)?;
+
+        // copies do not share the nonce
+        let template = tx
+            .with_durable_nonce(&Pubkey::new_unique(), &pk, Hash::new_unique())
+            .template();
+        assert_eq!(None, template.durable_nonce);
+        assert_eq!(1, template.instructions.len());
+        assert_eq!(
+            spl_memo_interface::v3::ID,
+            template.instructions[0].program_id
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_payer_balance() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let rpc = MockRpc {
+            balance: 10_000,
+            ..Default::default()
+        };
+        let tx = TransactionBuilder::default().with_memo("balance", &[&payer]);
+        tx.check_payer_balance(&payer, &rpc, 5_000).await?;
+        tx.check_payer_balance(&payer, &rpc, 10_000).await?;
+        let result = tx.check_payer_balance(&payer, &rpc, 10_001).await;
+        assert!(matches!(
+            result,
+            Err(Error::InsufficientBalance(10_000, 10_001))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_deterministic() -> anyhow::Result<()> {
+        let rpc = MockRpc::default();
+        let payer = TransactionBuilder::deterministic_pubkey(7, 0);
+        let first = TransactionBuilder::deterministic(7)
+            .create_message(&payer, &rpc)
+            .await?;
+        let second = TransactionBuilder::deterministic(7)
+            .create_message(&payer, &rpc)
+            .await?;
+        assert_eq!(first, second);
+
+        let other = TransactionBuilder::deterministic(8)
+            .create_message(&payer, &rpc)
+            .await?;
+        assert_ne!(first, other);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resolved_tables_reuse() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![destination],
+        };
+        let rpc = CounterRpcProvider::new(MockRpc {
+            lookup_tables: vec![table.clone()],
+            ..Default::default()
+        });
+        let transfer = |lamports| {
+            TransactionBuilder::from(vec![solana_system_interface::instruction::transfer(
+                &payer,
+                &destination,
+                lamports,
+            )])
+        };
+
+        let (_, tables) = transfer(1)
+            .with_lookup_keys([table.key])
+            .create_message_with_tables(&payer, &rpc)
+            .await?;
+        assert_eq!(ResolvedTables(vec![table]), tables);
+        assert_eq!(1, rpc.get_counter(&RpcMethod::Lookup));
+
+        for lamports in [2, 3] {
+            let message = transfer(lamports)
+                .with_resolved_tables(tables.clone())
+                .create_message(&payer, &rpc)
+                .await?;
+            let VersionedMessage::V0(message) = message else {
+                panic!("expected a v0 message");
+            };
+            assert_eq!(1, message.address_table_lookups.len());
+        }
+        assert_eq!(1, rpc.get_counter(&RpcMethod::Lookup));
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_locked_accounts() {
+        let payer = Pubkey::new_unique();
+        let first = Pubkey::new_unique();
+        let second = Pubkey::new_unique();
+        let tx = TransactionBuilder::from(vec![
+            solana_system_interface::instruction::transfer(&payer, &first, 1),
+            solana_system_interface::instruction::transfer(&payer, &second, 1),
+        ])
+        .with_memo("readonly", &[]);
+
+        let locked = tx.write_locked_accounts(&payer);
+        assert_eq!(HashSet::from([payer, first, second]), locked);
+        assert!(!locked.contains(&solana_system_interface::program::ID));
+    }
+
+    #[tokio::test]
+    async fn test_from_versioned_round_trip() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let readonly = Pubkey::new_unique();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![readonly, destination],
+        };
+        let rpc = MockRpc {
+            lookup_tables: vec![table.clone()],
+            ..Default::default()
+        };
+        let builder = TransactionBuilder::from(vec![
+            solana_system_interface::instruction::transfer(&payer, &destination, 42),
+            Instruction::new_with_bytes(spl_memo_interface::v3::ID, b"resend", vec![
+                AccountMeta::new_readonly(readonly, false),
+            ]),
+        ])
+        .with_lookup_keys([table.key]);
+        let tx = builder.unsigned_tx(&payer, &rpc).await?;
+
+        let rebuilt = TransactionBuilder::from_versioned(&tx, &rpc).await?;
+        assert!(builder.instructions_eq(&rebuilt));
+        assert_eq!(tx.message, rebuilt.create_message(&payer, &rpc).await?);
+
+        let legacy = TransactionBuilder::default()
+            .with_memo("legacy", &[])
+            .unsigned_tx(&payer, &rpc)
+            .await?;
+        let rebuilt = TransactionBuilder::from_versioned(&legacy, &rpc).await?;
+        assert_eq!(None, rebuilt.lookup_tables_keys);
+        assert_eq!(legacy.message, rebuilt.create_message(&payer, &rpc).await?);
+
+        let missing = TransactionBuilder::from_versioned(&tx, &MockRpc::default()).await;
+        assert!(matches!(missing, Err(Error::LookupTableMiss)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_integrity_memo() {
+        let payer = Pubkey::new_unique();
+        let tx = TransactionBuilder::from(vec![solana_system_interface::instruction::transfer(
+            &payer,
+            &Pubkey::new_unique(),
+            1,
+        )])
+        .with_memo("correlation-id", &[&payer])
+        .with_integrity_memo();
+
+        let (memo, covered) = tx.instructions.split_last().unwrap();
+        assert_eq!(spl_memo_interface::v3::ID, memo.program_id);
+        assert_eq!(
+            format!("{INTEGRITY_MEMO_PREFIX}{}", instructions_hash(covered)).as_bytes(),
+            memo.data
+        );
+
+        // any change to the covered instructions changes the hash
+        let mut altered = covered.to_vec();
+        altered[0].accounts[1].pubkey = Pubkey::new_unique();
+        assert_ne!(instructions_hash(covered), instructions_hash(&altered));
+    }
+
+    #[tokio::test]
+    async fn test_send_durable_nonce() -> anyhow::Result<()> {
+        let payer = Keypair::new();
+        let nonce_account = Pubkey::new_unique();
+        let nonce_blockhash = Hash::new_unique();
+        let rpc = MockRpc {
+            blockhash: Hash::new_unique(),
+            ..Default::default()
+        };
+        TransactionBuilder::default()
+            .with_memo("nonce", &[&payer.pubkey()])
+            .with_durable_nonce(&nonce_account, &payer.pubkey(), nonce_blockhash)
+            .send(&rpc, &payer.pubkey(), &[&payer])
+            .await?;
+
+        let simulated = rpc.simulated();
+        assert!(!simulated[0].sig_verify);
+        assert!(!simulated[0].replace_recent_blockhash);
+        let (tx, _) = &rpc.sent()[0];
+        assert_eq!(nonce_blockhash, *tx.message.recent_blockhash());
+        let advance = &tx.message.instructions()[0];
+        assert_eq!(
+            solana_system_interface::program::ID,
+            tx.message.static_account_keys()[usize::from(advance.program_id_index)]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_send_unconfirmed() -> anyhow::Result<()> {
+        let payer = Keypair::new();
+        let rpc = MockRpc {
+            unconfirmed: true,
+            ..Default::default()
+        };
+        let outcome = TransactionBuilder::default()
+            .with_memo("unconfirmed", &[&payer.pubkey()])
+            .send(&rpc, &payer.pubkey(), &[&payer])
+            .await?;
+        assert!(!outcome.is_confirmed());
+        assert_eq!(
+            SendOutcome::Unconfirmed(rpc.sent()[0].0.signatures[0]),
+            outcome
+        );
+
+        let rpc = MockRpc::default();
+        let outcome = TransactionBuilder::default()
+            .with_memo("confirmed", &[&payer.pubkey()])
+            .send(&rpc, &payer.pubkey(), &[&payer])
+            .await?;
+        assert_eq!(
+            SendOutcome::Confirmed(rpc.sent()[0].0.signatures[0]),
+            outcome
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unique_account_count() {
+        let payer = Pubkey::new_unique();
+        let destinations = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let tx = TransactionBuilder::from(vec![
+            solana_system_interface::instruction::transfer(&payer, &destinations[0], 1),
+            solana_system_interface::instruction::transfer(&payer, &destinations[1], 1),
+            solana_system_interface::instruction::transfer(&payer, &destinations[0], 1),
+        ])
+        .with_memo("count", &[&payer]);
+        // payer, 2 destinations, system and memo programs
+        assert_eq!(5, tx.unique_account_count(&payer));
+        assert_eq!(6, tx.unique_account_count(&Pubkey::new_unique()));
+        assert_eq!(
+            1,
+            TransactionBuilder::default().unique_account_count(&payer)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_num_required_signatures() -> anyhow::Result<()> {
+        let rpc = MockRpc::default();
+        let payer = Pubkey::new_unique();
+        let destination = Pubkey::new_unique();
+        let transfer =
+            TransactionBuilder::from(vec![solana_system_interface::instruction::transfer(
+                &payer,
+                &destination,
+                1,
+            )]);
+        assert_eq!(1, transfer.num_required_signatures(&payer, &rpc).await?);
+
+        let signers = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let multi = transfer.with_memo("multi", &[&signers[0], &signers[1], &payer]);
+        assert_eq!(3, multi.num_required_signatures(&payer, &rpc).await?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_offline() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let blockhash = Hash::new_unique();
+        let tx = TransactionBuilder::default().with_memo("offline", &[&payer]);
+
+        let message = tx.compile_offline(&payer, blockhash, &[])?;
+        assert!(matches!(message, VersionedMessage::Legacy(_)));
+        assert_eq!(&blockhash, message.recent_blockhash());
+
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![Pubkey::new_unique()],
+        };
+        let message = tx.compile_offline(&payer, blockhash, &[table])?;
+        assert!(matches!(message, VersionedMessage::V0(_)));
+        assert_eq!(&blockhash, message.recent_blockhash());
+        assert_eq!(1, message.header().num_required_signatures);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_message_concurrent() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![Pubkey::new_unique()],
+        };
+        let rpc = MockRpc {
+            lookup_tables: vec![table.clone()],
+            latency: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let tx = TransactionBuilder::default()
+            .with_memo("concurrent", &[&payer])
+            .with_lookup_keys([table.key]);
+
+        let message = tx.create_message(&payer, &rpc).await?;
+        assert!(matches!(message, VersionedMessage::V0(_)));
+        // the lookup tables and blockhash were fetched together
+        assert_eq!(2, rpc.max_in_flight());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_message_header() -> anyhow::Result<()> {
+        let rpc = MockRpc::default();
+        let payer = Pubkey::new_unique();
+        let tx = TransactionBuilder::from(vec![solana_system_interface::instruction::transfer(
+            &payer,
+            &Pubkey::new_unique(),
+            1,
+        )]);
+        // the system program is the only readonly account
+        assert_eq!(
+            MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            tx.message_header(&payer, &rpc).await?
+        );
+
+        let signer = Pubkey::new_unique();
+        let header = tx
+            .with_memo("header", &[&signer])
+            .message_header(&payer, &rpc)
+            .await?;
+        assert_eq!(2, header.num_required_signatures);
+        assert_eq!(1, header.num_readonly_signed_accounts);
+        assert_eq!(2, header.num_readonly_unsigned_accounts);
+        Ok(())
+    }
+
+    #[test]
+    fn test_merged_account_flags() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let shared = Pubkey::new_unique();
+        let tx = TransactionBuilder::from(vec![
+            Instruction::new_with_bytes(program_id, &[1], vec![AccountMeta::new_readonly(
+                shared, false,
+            )]),
+            Instruction::new_with_bytes(program_id, &[2], vec![AccountMeta::new(shared, false)]),
+            Instruction::new_with_bytes(program_id, &[3], vec![AccountMeta::new_readonly(
+                shared, true,
+            )]),
+        ]);
+        let message = tx.compile_offline(&payer, Hash::new_unique(), &[])?;
+        let keys = message.static_account_keys();
+        assert_eq!(1, keys.iter().filter(|key| **key == shared).count());
+        let index = keys.iter().position(|key| *key == shared).unwrap();
+        assert!(message.is_signer(index));
+        assert!(message.is_maybe_writable(index, None));
+        assert_eq!(2, message.header().num_required_signatures);
+        assert_eq!(0, message.header().num_readonly_signed_accounts);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_refresh_durable_nonce() -> anyhow::Result<()> {
+        let payer = Keypair::new();
+        let nonce_account = Pubkey::new_unique();
+        let nonce_data = |authority: &Pubkey, blockhash: &Hash| {
+            let mut data = vec![1, 0, 0, 0, 1, 0, 0, 0];
+            data.extend_from_slice(authority.as_ref());
+            data.extend_from_slice(blockhash.as_ref());
+            data.extend_from_slice(&5_000u64.to_le_bytes());
+            solana_account::Account {
+                data,
+                owner: solana_system_interface::program::ID,
+                ..Default::default()
+            }
+        };
+        let advanced = Hash::new_unique();
+        let rpc = MockRpc {
+            accounts: [(nonce_account, nonce_data(&payer.pubkey(), &advanced))].into(),
+            ..Default::default()
+        };
+        let tx = TransactionBuilder::default()
+            .with_memo("nonce", &[&payer.pubkey()])
+            .with_durable_nonce(&nonce_account, &payer.pubkey(), Hash::new_unique());
+        tx.send(&rpc, &payer.pubkey(), &[&payer]).await?;
+
+        let tx = tx.refresh_durable_nonce(&rpc).await?;
+        assert_eq!(
+            Some(advanced),
+            tx.durable_nonce.map(|nonce| nonce.blockhash)
+        );
+        // the advance instruction is not added again
+        assert_eq!(2, tx.instructions.len());
+        tx.send(&rpc, &payer.pubkey(), &[&payer]).await?;
+        assert_eq!(advanced, *rpc.sent()[1].0.message.recent_blockhash());
+
+        let other_authority = MockRpc {
+            accounts: [(nonce_account, nonce_data(&Pubkey::new_unique(), &advanced))].into(),
+            ..Default::default()
+        };
+        assert!(
+            tx.clone()
+                .refresh_durable_nonce(&other_authority)
+                .await
+                .is_err()
+        );
+        assert!(tx.refresh_durable_nonce(&MockRpc::default()).await.is_err());
+        assert!(
+            TransactionBuilder::default()
+                .refresh_durable_nonce(&rpc)
+                .await
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_too_many_accounts() -> anyhow::Result<()> {
+        let payer = Keypair::new();
+        let addresses: Vec<Pubkey> = (0..MAX_TX_ACCOUNTS).map(|_| Pubkey::new_unique()).collect();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: addresses.clone(),
+        };
+        let rpc = MockRpc::default();
+        let program_id = Pubkey::new_unique();
+        let tx = TransactionBuilder::from(vec![Instruction::new_with_bytes(
+            program_id,
+            &[],
+            addresses
+                .iter()
+                .map(|key| AccountMeta::new_readonly(*key, false))
+                .collect(),
+        )])
+        .with_address_tables([table]);
+
+        // the payer and the program come on top of the table
+        let result = tx.create_message(&payer.pubkey(), &rpc).await;
+        let Err(Error::TooManyAccounts { count, limit }) = result else {
+            panic!("expected TooManyAccounts, got {result:?}");
+        };
+        assert_eq!(MAX_TX_ACCOUNTS + 2, count);
+        assert_eq!(MAX_TX_ACCOUNTS, limit);
+        assert!(matches!(
+            tx.send(&rpc, &payer.pubkey(), &[&payer]).await,
+            Err(Error::TooManyAccounts { .. })
+        ));
+        assert!(rpc.simulated().is_empty());
+
+        // every signer is required before anything is sent
+        let signer = Pubkey::new_unique();
+        let result = TransactionBuilder::default()
+            .with_memo("signers", &[&payer.pubkey(), &signer])
+            .send(&rpc, &payer.pubkey(), &[&payer])
+            .await;
+        assert!(matches!(result, Err(Error::SignerError(_))));
+        assert!(rpc.sent().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_message_with_blockhash() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let blockhash = Hash::new_unique();
+        let rpc = MockRpc {
+            blockhash,
+            ..Default::default()
+        };
+        let tx = TransactionBuilder::default().with_memo("offline", &[&payer]);
+        let offline = tx.unsigned_tx_with_blockhash(&payer, blockhash)?;
+        assert_eq!(tx.unsigned_tx(&payer, &rpc).await?, offline);
+        assert_eq!(&blockhash, offline.message.recent_blockhash());
+
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![payer],
+        };
+        let v0 = tx.clone().with_address_tables([table.clone()]);
+        assert!(matches!(
+            v0.create_message_with_blockhash(&payer, blockhash)?,
+            VersionedMessage::V0(_)
+        ));
+        assert!(matches!(
+            tx.with_lookup_keys([table.key])
+                .create_message_with_blockhash(&payer, blockhash),
+            Err(Error::LookupTableMiss)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_to_base64() -> anyhow::Result<()> {
+        let payer = Pubkey::new_unique();
+        let rpc = MockRpc {
+            blockhash: Hash::new_unique(),
+            ..Default::default()
+        };
+        let tx = TransactionBuilder::default().with_memo("wallet", &[&payer]);
+        let encoded = tx.to_base64(&payer, &rpc).await?;
+        assert_eq!(encoded, tx.to_base64_with_blockhash(&payer, rpc.blockhash)?);
+
+        let decoded: VersionedTransaction =
+            bincode::deserialize(&BASE64_STANDARD.decode(&encoded)?)?;
+        assert_eq!(tx.unsigned_tx(&payer, &rpc).await?, decoded);
+        assert_eq!(vec![Signature::default()], decoded.signatures);
+        Ok(())
+    }
 }
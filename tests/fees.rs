@@ -130,3 +130,28 @@ async fn test_fee_with_priority_fees() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_fee_auto_accounts() -> anyhow::Result<()> {
+    let (kp, rpc) = init()?;
+    let span = tracing::info_span!("fee_auto_accounts");
+    let _g = span.enter();
+    let payer = kp.pubkey();
+    let manual = solana_system_interface::instruction::transfer(&payer, &RANDO, 1);
+    let writable: Vec<Pubkey> = manual
+        .accounts
+        .iter()
+        .filter(|meta| meta.is_writable)
+        .map(|meta| meta.pubkey)
+        .collect();
+    assert_eq!(writable, builder(&payer).priority_fee_accounts());
+
+    let tx = builder(&payer)
+        .with_priority_fees_auto(&payer, &rpc, 1_000_000, None)
+        .await?;
+    assert!(tx.instructions[0].program_id == solana_compute_budget_interface::ID);
+    assert!(tx.instructions[1].program_id == solana_compute_budget_interface::ID);
+    let sig = tx.send(&rpc, &payer, &[&kp]).await?;
+    info!(sig =? sig);
+    Ok(())
+}